@@ -0,0 +1,290 @@
+use crate::client::{Client, Protocol};
+use crate::error::Error;
+use crate::invoker::grpc::account::AccountInvoke as GrpcAccountInvoke;
+use crate::invoker::grpc::proto::bank_client::BankClient;
+use crate::invoker::grpc::storage::StorageInvoke as GrpcStorageInvoke;
+use crate::invoker::grpc::transaction::TransactionInvoke as GrpcTransactionInvoke;
+use crate::invoker::grpc::{GRPCInvoker, Headers};
+use crate::invoker::http::account::AccountInvoke;
+use crate::invoker::http::storage::StorageInvoke;
+use crate::invoker::http::transaction::TransactionInvoke;
+use crate::invoker::http::{HttpClient, HttpInvoker};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint};
+
+/// User agent клиента по умолчанию.
+pub const DEFAULT_USER_AGENT: &str = concat!("bank-client/", env!("CARGO_PKG_VERSION"));
+
+/// Политика повторных попыток соединения с сервером.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Retry {
+    // число повторов после первой неудачной попытки
+    pub(crate) retries: u32,
+    // пауза перед первым повтором, удваивается с каждой попыткой
+    pub(crate) backoff: Duration,
+}
+
+impl Retry {
+    /// Пауза перед повтором с номером `attempt` (с 0).
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+/// Конфигурация клиента: адрес сервера, таймауты, заголовки, TLS, повторы соединения.
+/// Из одной конфигурации собираются http и gRPC объекты вызова.
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    url: String,
+    grpc_url: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    tls_roots: Vec<Vec<u8>>,
+    retry: Retry,
+    user_agent: String,
+    protocol: Protocol,
+}
+
+impl ClientBuilder {
+    /// Конструктор с адресом сервера, например `http://localhost:8080`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            grpc_url: None,
+            timeout: None,
+            connect_timeout: None,
+            headers: Vec::new(),
+            tls_roots: Vec::new(),
+            retry: Retry::default(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            protocol: Protocol::default(),
+        }
+    }
+
+    /// Адрес gRPC сервера, если он отличается от http (`--protocol both`).
+    pub fn grpc_url(mut self, url: impl Into<String>) -> Self {
+        self.grpc_url = Some(url.into());
+        self
+    }
+
+    /// Таймаут запроса.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Таймаут установки соединения.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Заголовок (gRPC метаданные), добавляемый к каждому запросу.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Корневой сертификат (PEM) для проверки сервера по https.
+    pub fn tls_root(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls_roots.push(pem.into());
+        self
+    }
+
+    /// Число повторов неудачного соединения и пауза перед первым повтором.
+    /// Повторяются только запросы, не дошедшие до сервера.
+    pub fn connect_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retry = Retry { retries, backoff };
+        self
+    }
+
+    /// User agent клиента.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Протокол клиента, собираемого `build` (по умолчанию http).
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Клиент банка с протоколом из конфигурации.
+    pub async fn build(&self) -> Result<Client, Error> {
+        match self.protocol {
+            Protocol::Http => self.build_http().map(Client::Http),
+            Protocol::Grpc => self.build_grpc().await.map(Client::Grpc),
+        }
+    }
+
+    /// Объект http вызовов.
+    pub fn build_http(
+        &self,
+    ) -> Result<HttpInvoker<AccountInvoke, TransactionInvoke, StorageInvoke>, Error> {
+        // заголовки
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|err| Error::Config(format!("header '{}': {}", name, err)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|err| Error::Config(format!("header '{}': {}", name, err)))?;
+            headers.append(name, value);
+        }
+        // клиент
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .user_agent(self.user_agent.as_str());
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        for pem in &self.tls_roots {
+            let cert = reqwest::Certificate::from_pem(pem)
+                .map_err(|err| Error::Config(format!("tls root: {}", err)))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        let client = builder
+            .build()
+            .map_err(|err| Error::Config(err.to_string()))?;
+        let client = HttpClient::new(client, &self.url, self.retry)?;
+
+        Ok(HttpInvoker {
+            account: AccountInvoke::new(client.clone()),
+            transaction: TransactionInvoke::new(client.clone()),
+            storage: StorageInvoke::new(client),
+        })
+    }
+
+    /// Объект gRPC вызовов. Соединение устанавливается сразу, с повторами по политике.
+    pub async fn build_grpc(
+        &self,
+    ) -> Result<GRPCInvoker<GrpcAccountInvoke, GrpcTransactionInvoke, GrpcStorageInvoke>, Error>
+    {
+        let endpoint: Endpoint = self.endpoint()?;
+        // заголовки
+        let mut metadata = MetadataMap::new();
+        for (name, value) in &self.headers {
+            let key = AsciiMetadataKey::from_bytes(name.to_lowercase().as_bytes())
+                .map_err(|err| Error::Config(format!("header '{}': {}", name, err)))?;
+            let value = AsciiMetadataValue::try_from(value.as_str())
+                .map_err(|err| Error::Config(format!("header '{}': {}", name, err)))?;
+            metadata.append(key, value);
+        }
+        // соединение
+        let mut attempt: u32 = 0;
+        let channel: Channel = loop {
+            match endpoint.connect().await {
+                Ok(channel) => break channel,
+                Err(_) if attempt < self.retry.retries => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+        let client = BankClient::with_interceptor(channel, Headers::new(metadata));
+
+        Ok(GRPCInvoker {
+            account: GrpcAccountInvoke::new(client.clone()),
+            transaction: GrpcTransactionInvoke::new(client.clone()),
+            storage: GrpcStorageInvoke::new(client),
+        })
+    }
+
+    /// Точка подключения gRPC.
+    fn endpoint(&self) -> Result<Endpoint, Error> {
+        let url: &str = self.grpc_url.as_deref().unwrap_or(&self.url);
+        let mut endpoint = Endpoint::from_shared(url.to_string())
+            .map_err(|err| Error::Config(format!("url '{}': {}", url, err)))?
+            .user_agent(self.user_agent.as_str())
+            .map_err(|err| Error::Config(format!("user agent: {}", err)))?;
+        if let Some(timeout) = self.timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            endpoint = endpoint.connect_timeout(timeout);
+        }
+        if endpoint.uri().scheme_str() == Some("https") {
+            let mut tls = ClientTlsConfig::new();
+            if !self.tls_roots.is_empty() {
+                tls = tls.ca_certificate(Certificate::from_pem(self.tls_roots.join(&b'\n')));
+            }
+            endpoint = endpoint
+                .tls_config(tls)
+                .map_err(|err| Error::Config(format!("tls: {}", err)))?;
+        }
+
+        Ok(endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_url_is_config_error() {
+        let err = ClientBuilder::new("not a url").build_http().err().unwrap();
+        assert!(
+            matches!(&err, Error::Config(msg) if msg.starts_with("url 'not a url'")),
+            "{:?}",
+            err
+        );
+        let err = ClientBuilder::new("http://localhost:8080")
+            .grpc_url("not a url")
+            .endpoint()
+            .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "{:?}", err);
+    }
+
+    #[test]
+    fn invalid_header_is_config_error() {
+        for (name, value) in [("bad header", "value"), ("x-token", "bad\nvalue")] {
+            let err = ClientBuilder::new("http://localhost:8080")
+                .header(name, value)
+                .build_http()
+                .err()
+                .unwrap();
+            assert!(
+                matches!(&err, Error::Config(msg) if msg.starts_with("header")),
+                "{:?}",
+                err
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_grpc_metadata_is_config_error() {
+        // ошибка конфигурации возвращается до установки соединения
+        let err = ClientBuilder::new("http://localhost:1")
+            .header("bad header", "value")
+            .build_grpc()
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&err, Error::Config(msg) if msg.starts_with("header")),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn valid_config_builds_http_client() {
+        let builder = ClientBuilder::new("http://localhost:8080/api")
+            .header("x-token", "secret")
+            .timeout(Duration::from_secs(1))
+            .connect_retries(2, Duration::from_millis(10));
+
+        assert!(builder.build_http().is_ok());
+        assert_eq!(builder.retry.delay(0), Duration::from_millis(10));
+        assert_eq!(builder.retry.delay(2), Duration::from_millis(40));
+        assert!(builder.endpoint().is_ok());
+    }
+}
@@ -0,0 +1,317 @@
+use crate::entities::{Account, AccountKey, Money, Transaction};
+use crate::error::Error;
+use crate::invoker::grpc::account::AccountInvoke as GrpcAccountInvoke;
+use crate::invoker::grpc::storage::StorageInvoke as GrpcStorageInvoke;
+use crate::invoker::grpc::transaction::TransactionInvoke as GrpcTransactionInvoke;
+use crate::invoker::grpc::GRPCInvoker;
+use crate::invoker::http::account::AccountInvoke;
+use crate::invoker::http::storage::StorageInvoke;
+use crate::invoker::http::transaction::TransactionInvoke;
+use crate::invoker::http::HttpInvoker;
+use crate::schemas::request::HistoryQuery;
+use crate::schemas::response::{
+    BalanceResponse, HistoryPage, StatusResponse, TransactionResponse, TransferResponse,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Клиент банка, не зависящий от протокола. Реализуется http и gRPC объектами вызова.
+#[trait_variant::make(Send)]
+pub trait BankClient {
+    /// Создание счета с необязательным уникальным именем.
+    async fn create(&self, name: Option<&str>) -> Result<TransactionResponse, Error>;
+
+    /// Пополнение счета.
+    async fn replenish(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error>;
+
+    /// Снятие со счета.
+    async fn withdraw(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error>;
+
+    /// Перевод с одного счета на другой.
+    async fn transfer(
+        &self,
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransferResponse, Error>;
+
+    /// Запрос баланса счета.
+    async fn balance(
+        &self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<BalanceResponse, Error>;
+
+    /// Баланс счета на момент `as_of`.
+    async fn balance_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<BalanceResponse, Error>;
+
+    /// Счет со всей историей.
+    async fn account(&self, account: impl Into<AccountKey> + Send) -> Result<Account, Error>;
+
+    /// Счет на момент `as_of`: статус, баланс и транзакции, проведенные до этого момента.
+    async fn account_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Account, Error>;
+
+    /// Страница истории счета. Все страницы обходит `History::account`.
+    async fn account_history(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, Error>;
+
+    /// Закрытие счета.
+    async fn close(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error>;
+
+    /// Заморозка счета.
+    async fn freeze(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error>;
+
+    /// Повторное открытие (разморозка) счета.
+    async fn reopen(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error>;
+
+    /// Получение транзакции по id.
+    async fn transaction(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_id: u32,
+    ) -> Result<Transaction, Error>;
+
+    /// Все счета и их транзакции.
+    async fn history(&self) -> Result<HashMap<u32, Account>, Error>;
+
+    /// Страница истории всех счетов. Все страницы обходит `History::all`.
+    async fn transactions(&self, query: &HistoryQuery) -> Result<HistoryPage, Error>;
+
+    /// Backup БД на стороне сервера.
+    async fn backup(&self) -> Result<(), Error>;
+}
+
+/// Протокол клиента.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    #[default]
+    Http,
+    Grpc,
+}
+
+impl FromStr for Protocol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "http" => Ok(Self::Http),
+            "grpc" => Ok(Self::Grpc),
+            other => Err(Error::Config(format!("unknown protocol: '{}'", other))),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http => write!(f, "http"),
+            Self::Grpc => write!(f, "grpc"),
+        }
+    }
+}
+
+/// Клиент банка с протоколом, выбранным в конфигурации (`ClientBuilder::build`).
+// клиент создается один раз, размер варианта не важен
+#[allow(clippy::large_enum_variant)]
+pub enum Client {
+    Http(HttpInvoker<AccountInvoke, TransactionInvoke, StorageInvoke>),
+    Grpc(GRPCInvoker<GrpcAccountInvoke, GrpcTransactionInvoke, GrpcStorageInvoke>),
+}
+
+impl Client {
+    /// Протокол клиента.
+    pub fn protocol(&self) -> Protocol {
+        match self {
+            Self::Http(_) => Protocol::Http,
+            Self::Grpc(_) => Protocol::Grpc,
+        }
+    }
+}
+
+impl BankClient for Client {
+    async fn create(&self, name: Option<&str>) -> Result<TransactionResponse, Error> {
+        match self {
+            Self::Http(client) => client.create(name).await,
+            Self::Grpc(client) => client.create(name).await,
+        }
+    }
+
+    async fn replenish(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
+        match self {
+            Self::Http(client) => {
+                client
+                    .replenish(account, transaction_value, reference)
+                    .await
+            }
+            Self::Grpc(client) => {
+                client
+                    .replenish(account, transaction_value, reference)
+                    .await
+            }
+        }
+    }
+
+    async fn withdraw(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
+        match self {
+            Self::Http(client) => client.withdraw(account, transaction_value, reference).await,
+            Self::Grpc(client) => client.withdraw(account, transaction_value, reference).await,
+        }
+    }
+
+    async fn transfer(
+        &self,
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransferResponse, Error> {
+        match self {
+            Self::Http(client) => {
+                client
+                    .transfer(account_from, account_to, transfer_value, reference)
+                    .await
+            }
+            Self::Grpc(client) => {
+                client
+                    .transfer(account_from, account_to, transfer_value, reference)
+                    .await
+            }
+        }
+    }
+
+    async fn balance(
+        &self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<BalanceResponse, Error> {
+        match self {
+            Self::Http(client) => client.balance(account).await,
+            Self::Grpc(client) => client.balance(account).await,
+        }
+    }
+
+    async fn balance_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<BalanceResponse, Error> {
+        match self {
+            Self::Http(client) => client.balance_as_of(account, as_of).await,
+            Self::Grpc(client) => client.balance_as_of(account, as_of).await,
+        }
+    }
+
+    async fn account(&self, account: impl Into<AccountKey> + Send) -> Result<Account, Error> {
+        match self {
+            Self::Http(client) => client.account(account).await,
+            Self::Grpc(client) => client.account(account).await,
+        }
+    }
+
+    async fn account_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Account, Error> {
+        match self {
+            Self::Http(client) => client.account_as_of(account, as_of).await,
+            Self::Grpc(client) => client.account_as_of(account, as_of).await,
+        }
+    }
+
+    async fn account_history(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, Error> {
+        match self {
+            Self::Http(client) => client.account_history(account, query).await,
+            Self::Grpc(client) => client.account_history(account, query).await,
+        }
+    }
+
+    async fn close(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        match self {
+            Self::Http(client) => client.close(account).await,
+            Self::Grpc(client) => client.close(account).await,
+        }
+    }
+
+    async fn freeze(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        match self {
+            Self::Http(client) => client.freeze(account).await,
+            Self::Grpc(client) => client.freeze(account).await,
+        }
+    }
+
+    async fn reopen(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        match self {
+            Self::Http(client) => client.reopen(account).await,
+            Self::Grpc(client) => client.reopen(account).await,
+        }
+    }
+
+    async fn transaction(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_id: u32,
+    ) -> Result<Transaction, Error> {
+        match self {
+            Self::Http(client) => client.transaction(account, transaction_id).await,
+            Self::Grpc(client) => client.transaction(account, transaction_id).await,
+        }
+    }
+
+    async fn history(&self) -> Result<HashMap<u32, Account>, Error> {
+        match self {
+            Self::Http(client) => client.history().await,
+            Self::Grpc(client) => client.history().await,
+        }
+    }
+
+    async fn transactions(&self, query: &HistoryQuery) -> Result<HistoryPage, Error> {
+        match self {
+            Self::Http(client) => client.transactions(query).await,
+            Self::Grpc(client) => client.transactions(query).await,
+        }
+    }
+
+    async fn backup(&self) -> Result<(), Error> {
+        match self {
+            Self::Http(client) => client.backup().await,
+            Self::Grpc(client) => client.backup().await,
+        }
+    }
+}
@@ -1,10 +1,24 @@
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Статусы счета.
 #[derive(Debug, Clone, Deserialize)]
 pub enum Status {
     Opened,
     Closed,
+    Frozen,
+}
+
+/// Порядок выдачи истории.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    /// От старых транзакций к новым.
+    #[default]
+    Asc,
+    /// От новых транзакций к старым.
+    Desc,
 }
 
 /// Структура транзакции.
@@ -12,16 +26,65 @@ pub enum Status {
 pub struct Transaction {
     pub id: u32,
     pub operation: String,
-    pub previous: f64,
-    pub delta: f64,
-    pub current: f64,
+    pub previous: Money,
+    pub delta: Money,
+    pub current: Money,
+    // время проведения
+    #[serde(default)]
+    pub timestamp: DateTime<Utc>,
+    // назначение платежа
+    #[serde(default)]
+    pub reference: Option<String>,
+    // счет контрагента (для переводов и комиссий)
+    #[serde(default)]
+    pub counterparty: Option<u32>,
 }
 
 /// Структура счета.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Account {
     pub id: u32,
+    #[serde(default)]
+    pub name: Option<String>,
     pub status: Status,
-    pub balance: f64,
+    pub balance: Money,
     pub transactions: Vec<Transaction>,
 }
+
+/// Ключ поиска счета: id или уникальное имя.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AccountKey {
+    Id(u32),
+    Name(String),
+}
+
+impl From<u32> for AccountKey {
+    fn from(id: u32) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<&str> for AccountKey {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_string())
+    }
+}
+
+impl From<String> for AccountKey {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl fmt::Display for AccountKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Денежная сумма с фиксированной точкой (общий с сервером крейт `money`).
+pub use money::{Money, ParseMoneyError};
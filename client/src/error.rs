@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Причина ошибки сервера: стабильный машиночитаемый код (например, `ACCOUNT_NOT_FOUND`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Reason {
+    AccountNotFound,
+    AccountNameExists,
+    InvalidAccountName,
+    TransactionNotFound,
+    OperationNotFound,
+    InvalidReference,
+    InvalidIdempotencyKey,
+    InvalidHistoryQuery,
+    IdempotencyKeyReused,
+    ZeroValueTransaction,
+    Overdraft,
+    InvalidAmount,
+    BalanceOverflow,
+    SelfTransfer,
+    AccountClosed,
+    AccountFrozen,
+    NonZeroBalance,
+    StatusUnchanged,
+    SystemAccount,
+    EmptyDb,
+    EmptyBackupFile,
+    InvalidBackupFile,
+    BackupLoadFile,
+    ReplayMismatch,
+    StorageFailure,
+    Recovering,
+    StorageUnavailable,
+    InvalidRequest,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    InvalidBody,
+    /// Код, неизвестный этой версии клиента.
+    Unknown(String),
+}
+
+impl Reason {
+    /// Код причины ошибки.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::AccountNotFound => "ACCOUNT_NOT_FOUND",
+            Self::AccountNameExists => "ACCOUNT_NAME_EXISTS",
+            Self::InvalidAccountName => "INVALID_ACCOUNT_NAME",
+            Self::TransactionNotFound => "TRANSACTION_NOT_FOUND",
+            Self::OperationNotFound => "OPERATION_NOT_FOUND",
+            Self::InvalidReference => "INVALID_REFERENCE",
+            Self::InvalidIdempotencyKey => "INVALID_IDEMPOTENCY_KEY",
+            Self::InvalidHistoryQuery => "INVALID_HISTORY_QUERY",
+            Self::IdempotencyKeyReused => "IDEMPOTENCY_KEY_REUSED",
+            Self::ZeroValueTransaction => "ZERO_VALUE_TRANSACTION",
+            Self::Overdraft => "OVERDRAFT",
+            Self::InvalidAmount => "INVALID_AMOUNT",
+            Self::BalanceOverflow => "BALANCE_OVERFLOW",
+            Self::SelfTransfer => "SELF_TRANSFER",
+            Self::AccountClosed => "ACCOUNT_CLOSED",
+            Self::AccountFrozen => "ACCOUNT_FROZEN",
+            Self::NonZeroBalance => "NON_ZERO_BALANCE",
+            Self::StatusUnchanged => "STATUS_UNCHANGED",
+            Self::SystemAccount => "SYSTEM_ACCOUNT",
+            Self::EmptyDb => "EMPTY_DB",
+            Self::EmptyBackupFile => "EMPTY_BACKUP_FILE",
+            Self::InvalidBackupFile => "INVALID_BACKUP_FILE",
+            Self::BackupLoadFile => "BACKUP_LOAD_FILE",
+            Self::ReplayMismatch => "REPLAY_MISMATCH",
+            Self::StorageFailure => "STORAGE_FAILURE",
+            Self::Recovering => "RECOVERING",
+            Self::StorageUnavailable => "STORAGE_UNAVAILABLE",
+            Self::InvalidRequest => "INVALID_REQUEST",
+            Self::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            Self::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            Self::InvalidBody => "INVALID_BODY",
+            Self::Unknown(reason) => reason,
+        }
+    }
+}
+
+impl From<&str> for Reason {
+    fn from(reason: &str) -> Self {
+        match reason {
+            "ACCOUNT_NOT_FOUND" => Self::AccountNotFound,
+            "ACCOUNT_NAME_EXISTS" => Self::AccountNameExists,
+            "INVALID_ACCOUNT_NAME" => Self::InvalidAccountName,
+            "TRANSACTION_NOT_FOUND" => Self::TransactionNotFound,
+            "OPERATION_NOT_FOUND" => Self::OperationNotFound,
+            "INVALID_REFERENCE" => Self::InvalidReference,
+            "INVALID_IDEMPOTENCY_KEY" => Self::InvalidIdempotencyKey,
+            "INVALID_HISTORY_QUERY" => Self::InvalidHistoryQuery,
+            "IDEMPOTENCY_KEY_REUSED" => Self::IdempotencyKeyReused,
+            "ZERO_VALUE_TRANSACTION" => Self::ZeroValueTransaction,
+            "OVERDRAFT" => Self::Overdraft,
+            "INVALID_AMOUNT" => Self::InvalidAmount,
+            "BALANCE_OVERFLOW" => Self::BalanceOverflow,
+            "SELF_TRANSFER" => Self::SelfTransfer,
+            "ACCOUNT_CLOSED" => Self::AccountClosed,
+            "ACCOUNT_FROZEN" => Self::AccountFrozen,
+            "NON_ZERO_BALANCE" => Self::NonZeroBalance,
+            "STATUS_UNCHANGED" => Self::StatusUnchanged,
+            "SYSTEM_ACCOUNT" => Self::SystemAccount,
+            "EMPTY_DB" => Self::EmptyDb,
+            "EMPTY_BACKUP_FILE" => Self::EmptyBackupFile,
+            "INVALID_BACKUP_FILE" => Self::InvalidBackupFile,
+            "BACKUP_LOAD_FILE" => Self::BackupLoadFile,
+            "REPLAY_MISMATCH" => Self::ReplayMismatch,
+            "STORAGE_FAILURE" => Self::StorageFailure,
+            "RECOVERING" => Self::Recovering,
+            "STORAGE_UNAVAILABLE" => Self::StorageUnavailable,
+            "INVALID_REQUEST" => Self::InvalidRequest,
+            "PAYLOAD_TOO_LARGE" => Self::PayloadTooLarge,
+            "UNSUPPORTED_MEDIA_TYPE" => Self::UnsupportedMediaType,
+            "INVALID_BODY" => Self::InvalidBody,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Ошибка бизнес-логики, возвращенная сервером.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerError {
+    pub reason: Reason,
+    pub message: String,
+    // параметры ошибки: account, transaction_id, operation_id и т.п.
+    pub metadata: HashMap<String, String>,
+    // id запроса (только http, заголовок `x-request-id`)
+    pub request_id: Option<String>,
+}
+
+impl ServerError {
+    /// Счет, к которому относится ошибка.
+    pub fn account(&self) -> Option<&str> {
+        self.metadata.get("account").map(String::as_str)
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.reason, self.message)
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Ошибки клиента.
+#[derive(Debug, Error)]
+pub enum Error {
+    // сервер недоступен, соединение разорвано и т.п.
+    #[error("connection failed: {0}")]
+    Transport(#[source] Box<dyn std::error::Error + Send + Sync>),
+    // ошибка бизнес-логики сервера (overdraft, account not found, ...)
+    #[error(transparent)]
+    Server(ServerError),
+    // http ответ с ошибкой без описания (прокси, неизвестный ендпоинт и т.п.)
+    #[error("http status {status}: {message}")]
+    Http { status: u16, message: String },
+    // gRPC статус с ошибкой без деталей ErrorInfo
+    #[error("grpc status {code:?}: {message}")]
+    Grpc { code: tonic::Code, message: String },
+    // ответ сервера не удалось разобрать
+    #[error("decode error: {0}")]
+    Decode(String),
+    // истекло время ожидания ответа
+    #[error("request timed out")]
+    Timeout,
+    // некорректная конфигурация клиента (адрес, заголовок, сертификат)
+    #[error("invalid client config: {0}")]
+    Config(String),
+}
+
+impl Error {
+    /// Ошибка бизнес-логики сервера.
+    pub fn server(&self) -> Option<&ServerError> {
+        match self {
+            Self::Server(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Причина ошибки сервера.
+    pub fn reason(&self) -> Option<&Reason> {
+        self.server().map(|err| &err.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_reasons_round_trip() {
+        let reasons = [
+            Reason::AccountNotFound,
+            Reason::Overdraft,
+            Reason::SelfTransfer,
+            Reason::InvalidBody,
+            Reason::UnsupportedMediaType,
+            Reason::StorageUnavailable,
+        ];
+        for reason in reasons {
+            assert_eq!(Reason::from(reason.as_str()), reason);
+        }
+        assert_eq!(Reason::from("OVERDRAFT").to_string(), "OVERDRAFT");
+    }
+
+    #[test]
+    fn unknown_reason_keeps_code() {
+        let reason: Reason = Reason::from("NEW_SERVER_CODE");
+
+        assert_eq!(reason, Reason::Unknown("NEW_SERVER_CODE".to_string()));
+        assert_eq!(reason.as_str(), "NEW_SERVER_CODE");
+        assert_eq!(Reason::from(reason.as_str()), reason);
+    }
+
+    #[test]
+    fn server_error_accessors() {
+        let err = Error::Server(ServerError {
+            reason: Reason::AccountNotFound,
+            message: "account: '99' not found".to_string(),
+            metadata: HashMap::from([("account".to_string(), "99".to_string())]),
+            request_id: None,
+        });
+
+        assert_eq!(err.reason(), Some(&Reason::AccountNotFound));
+        assert_eq!(err.server().and_then(ServerError::account), Some("99"));
+        assert_eq!(
+            err.to_string(),
+            "ACCOUNT_NOT_FOUND: account: '99' not found"
+        );
+        assert_eq!(Error::Timeout.reason(), None);
+    }
+}
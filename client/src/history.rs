@@ -0,0 +1,90 @@
+use crate::client::BankClient;
+use crate::entities::AccountKey;
+use crate::error::Error;
+use crate::schemas::request::HistoryQuery;
+use crate::schemas::response::{HistoryEntry, HistoryPage};
+use std::collections::VecDeque;
+
+/// Постраничный обход истории любым клиентом банка (http, gRPC, `Client`).
+/// Следующая страница запрашивается по курсору, когда выданы транзакции текущей.
+///
+/// ```ignore
+/// let mut history = History::account(&client, 1, HistoryQuery::new().limit(50));
+/// while let Some(entry) = history.next().await {
+///     println!("{:?}", entry?);
+/// }
+/// ```
+pub struct History<'a, C> {
+    client: &'a C,
+    // счет (None - история всех счетов)
+    account: Option<AccountKey>,
+    // фильтры и курсор следующей страницы
+    query: HistoryQuery,
+    // невыданные транзакции текущей страницы
+    page: VecDeque<HistoryEntry>,
+    // последняя страница получена
+    done: bool,
+}
+
+impl<'a, C: BankClient> History<'a, C> {
+    /// Обход истории счета.
+    pub fn account(client: &'a C, account: impl Into<AccountKey>, query: HistoryQuery) -> Self {
+        Self::new(client, Some(account.into()), query)
+    }
+
+    /// Обход истории всех счетов.
+    pub fn all(client: &'a C, query: HistoryQuery) -> Self {
+        Self::new(client, None, query)
+    }
+
+    fn new(client: &'a C, account: Option<AccountKey>, query: HistoryQuery) -> Self {
+        Self {
+            client,
+            account,
+            query,
+            page: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Следующая транзакция, None после последней.
+    /// После ошибки обход можно продолжить: повторяется запрос той же страницы.
+    pub async fn next(&mut self) -> Option<Result<HistoryEntry, Error>> {
+        while self.page.is_empty() {
+            match self.next_page().await? {
+                Ok(page) => self.page.extend(page),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.page.pop_front().map(Ok)
+    }
+
+    /// Следующая страница (невыданные транзакции текущей страницы, если они есть), None после последней.
+    pub async fn next_page(&mut self) -> Option<Result<Vec<HistoryEntry>, Error>> {
+        if !self.page.is_empty() {
+            return Some(Ok(self.page.drain(..).collect()));
+        }
+        if self.done {
+            return None;
+        }
+        let page: Result<HistoryPage, Error> = match &self.account {
+            Some(account) => {
+                self.client
+                    .account_history(account.clone(), &self.query)
+                    .await
+            }
+            None => self.client.transactions(&self.query).await,
+        };
+        let page: HistoryPage = match page {
+            Ok(page) => page,
+            Err(err) => return Some(Err(err)),
+        };
+        // курсор следующей страницы
+        match page.next_cursor {
+            Some(cursor) => self.query.cursor = Some(cursor),
+            None => self.done = true,
+        }
+
+        Some(Ok(page.transactions))
+    }
+}
@@ -1,16 +1,21 @@
-use crate::invoker::grpc::proto;
+use crate::entities::{AccountKey, Money};
+use crate::error::Error;
 use crate::invoker::grpc::proto::bank_client::BankClient;
-use tonic::transport::Channel;
+use crate::invoker::grpc::GrpcChannel;
+use crate::invoker::grpc::{account_query, account_ref, proto};
+use crate::schemas::request::HistoryQuery;
+use chrono::{DateTime, Utc};
 use tonic::Response;
 
 /// Структура объекта вызова для работы со счетами.
+#[derive(Clone)]
 pub struct AccountInvoke {
-    client: BankClient<Channel>,
+    client: BankClient<GrpcChannel>,
 }
 
 impl AccountInvoke {
     /// Конструктор.
-    pub fn new(client: BankClient<Channel>) -> Self {
+    pub fn new(client: BankClient<GrpcChannel>) -> Self {
         Self { client }
     }
 }
@@ -18,65 +23,117 @@ impl AccountInvoke {
 /// Интерфейс работы со счетами.
 #[trait_variant::make(Send)]
 pub trait AccountInvoker {
-    /// Создание счета.
+    /// Создание счета с необязательным уникальным именем.
     async fn create(
         &mut self,
-    ) -> Result<Response<proto::TransactionResponse>, Box<dyn std::error::Error>>;
+        name: Option<&str>,
+    ) -> Result<Response<proto::TransactionResponse>, Error>;
 
     /// Пополнение счета
     async fn replenish(
         &mut self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<Response<proto::TransactionResponse>, Box<dyn std::error::Error>>;
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<Response<proto::TransactionResponse>, Error>;
 
     /// Списание со счета.
     async fn withdraw(
         &mut self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<Response<proto::TransactionResponse>, Box<dyn std::error::Error>>;
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<Response<proto::TransactionResponse>, Error>;
 
     /// Перевод со счета на счет
     async fn transfer(
         &mut self,
-        account_from: u32,
-        account_to: u32,
-        transfer_value: f64,
-    ) -> Result<Response<proto::TransferResponse>, Box<dyn std::error::Error>>;
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<Response<proto::TransferResponse>, Error>;
 
     /// Запрос баланса счета.
     async fn balance(
         &mut self,
-        account_id: u32,
-    ) -> Result<Response<proto::BalanceResponse>, Box<dyn std::error::Error>>;
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::BalanceResponse>, Error>;
+
+    /// Баланс счета на момент `as_of`.
+    async fn balance_as_of(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Response<proto::BalanceResponse>, Error>;
 
     /// Получение счета.
     async fn account(
         &mut self,
-        account_id: u32,
-    ) -> Result<Response<proto::AccountResponse>, Box<dyn std::error::Error>>;
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::AccountResponse>, Error>;
+
+    /// Счет на момент `as_of`: статус, баланс и транзакции, проведенные до этого момента.
+    async fn account_as_of(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Response<proto::AccountResponse>, Error>;
+
+    /// Страница истории счета.
+    async fn history(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<Response<proto::HistoryPage>, Error>;
+
+    /// Закрытие счета.
+    async fn close(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::StatusResponse>, Error>;
+
+    /// Заморозка счета.
+    async fn freeze(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::StatusResponse>, Error>;
+
+    /// Повторное открытие (разморозка) счета.
+    async fn reopen(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::StatusResponse>, Error>;
 }
 
 impl AccountInvoker for AccountInvoke {
     async fn create(
         &mut self,
-    ) -> Result<Response<proto::TransactionResponse>, Box<dyn std::error::Error>> {
+        name: Option<&str>,
+    ) -> Result<Response<proto::TransactionResponse>, Error> {
+        // req body
+        let req: proto::NewAccountRequest = proto::NewAccountRequest {
+            name: name.map(str::to_string),
+        };
         // запрос
-        let resp: Response<proto::TransactionResponse> = self.client.new_account(()).await?;
+        let resp: Response<proto::TransactionResponse> = self.client.new_account(req).await?;
         // парсинг ответа
         Ok(resp)
     }
 
     async fn replenish(
         &mut self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<Response<proto::TransactionResponse>, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<Response<proto::TransactionResponse>, Error> {
         // req body
+        let (account_id, account_name) = account_ref(account.into());
         let req: proto::TransactionRequest = proto::TransactionRequest {
             account_id,
-            transaction_value: transaction_value as f32,
+            transaction_amount: Some(transaction_value.into()),
+            account_name,
+            reference: reference.map(str::to_string),
         };
         // запрос
         let resp: Response<proto::TransactionResponse> = self.client.replenish(req).await?;
@@ -86,13 +143,17 @@ impl AccountInvoker for AccountInvoke {
 
     async fn withdraw(
         &mut self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<Response<proto::TransactionResponse>, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<Response<proto::TransactionResponse>, Error> {
         // req body
+        let (account_id, account_name) = account_ref(account.into());
         let req: proto::TransactionRequest = proto::TransactionRequest {
             account_id,
-            transaction_value: transaction_value as f32,
+            transaction_amount: Some(transaction_value.into()),
+            account_name,
+            reference: reference.map(str::to_string),
         };
         // запрос
         let resp: Response<proto::TransactionResponse> = self.client.withdraw(req).await?;
@@ -102,15 +163,21 @@ impl AccountInvoker for AccountInvoke {
 
     async fn transfer(
         &mut self,
-        account_from: u32,
-        account_to: u32,
-        transfer_value: f64,
-    ) -> Result<Response<proto::TransferResponse>, Box<dyn std::error::Error>> {
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<Response<proto::TransferResponse>, Error> {
         // req body
+        let (account_from, account_from_name) = account_ref(account_from.into());
+        let (account_to, account_to_name) = account_ref(account_to.into());
         let req: proto::TransferRequest = proto::TransferRequest {
             account_from,
             account_to,
-            transfer_value: transfer_value as f32,
+            transfer_amount: Some(transfer_value.into()),
+            account_from_name,
+            account_to_name,
+            reference: reference.map(str::to_string),
         };
         // запрос
         let resp: Response<proto::TransferResponse> = self.client.transfer(req).await?;
@@ -120,10 +187,23 @@ impl AccountInvoker for AccountInvoke {
 
     async fn balance(
         &mut self,
-        account_id: u32,
-    ) -> Result<Response<proto::BalanceResponse>, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::BalanceResponse>, Error> {
+        // req body
+        let req: proto::AccountQuery = account_query(account.into(), None);
+        // запрос
+        let resp: Response<proto::BalanceResponse> = self.client.balance(req).await?;
+
+        Ok(resp)
+    }
+
+    async fn balance_as_of(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Response<proto::BalanceResponse>, Error> {
         // req body
-        let req: proto::AccountId = proto::AccountId { account_id };
+        let req: proto::AccountQuery = account_query(account.into(), Some(as_of));
         // запрос
         let resp: Response<proto::BalanceResponse> = self.client.balance(req).await?;
 
@@ -132,13 +212,78 @@ impl AccountInvoker for AccountInvoke {
 
     async fn account(
         &mut self,
-        account_id: u32,
-    ) -> Result<Response<proto::AccountResponse>, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::AccountResponse>, Error> {
+        // req body
+        let req: proto::AccountQuery = account_query(account.into(), None);
+        // запрос
+        let resp: Response<proto::AccountResponse> = self.client.account(req).await?;
+
+        Ok(resp)
+    }
+
+    async fn account_as_of(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Response<proto::AccountResponse>, Error> {
         // req body
-        let req: proto::AccountId = proto::AccountId { account_id };
+        let req: proto::AccountQuery = account_query(account.into(), Some(as_of));
         // запрос
         let resp: Response<proto::AccountResponse> = self.client.account(req).await?;
 
         Ok(resp)
     }
+
+    async fn history(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<Response<proto::HistoryPage>, Error> {
+        // req body
+        let req = proto::AccountHistoryRequest {
+            account: Some(account.into().into()),
+            query: Some(query.into()),
+        };
+        // запрос
+        let resp: Response<proto::HistoryPage> = self.client.account_history(req).await?;
+
+        Ok(resp)
+    }
+
+    async fn close(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::StatusResponse>, Error> {
+        // req body
+        let req: proto::AccountId = account.into().into();
+        // запрос
+        let resp: Response<proto::StatusResponse> = self.client.close(req).await?;
+
+        Ok(resp)
+    }
+
+    async fn freeze(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::StatusResponse>, Error> {
+        // req body
+        let req: proto::AccountId = account.into().into();
+        // запрос
+        let resp: Response<proto::StatusResponse> = self.client.freeze(req).await?;
+
+        Ok(resp)
+    }
+
+    async fn reopen(
+        &mut self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<Response<proto::StatusResponse>, Error> {
+        // req body
+        let req: proto::AccountId = account.into().into();
+        // запрос
+        let resp: Response<proto::StatusResponse> = self.client.reopen(req).await?;
+
+        Ok(resp)
+    }
 }
@@ -0,0 +1,303 @@
+use crate::client::BankClient;
+use crate::entities::{Account, AccountKey, Money, Status, Transaction};
+use crate::error::Error;
+use crate::invoker::grpc::account::{AccountInvoke, AccountInvoker};
+use crate::invoker::grpc::proto;
+use crate::invoker::grpc::storage::{StorageInvoke, StorageInvoker};
+use crate::invoker::grpc::transaction::{TransactionInvoke, TransactionInvoker};
+use crate::invoker::grpc::GRPCInvoker;
+use crate::schemas::request::{HistoryQuery, TransferRequest};
+use crate::schemas::response::{
+    BalanceResponse, HistoryEntry, HistoryPage, StatusResponse, TransactionResponse,
+    TransferResponse,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Клиент банка поверх gRPC.
+/// Объекты вызова клонируются на каждый запрос: клон разделяет канал gRPC.
+impl BankClient for GRPCInvoker<AccountInvoke, TransactionInvoke, StorageInvoke> {
+    async fn create(&self, name: Option<&str>) -> Result<TransactionResponse, Error> {
+        let resp = self.account.clone().create(name).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn replenish(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
+        let resp = self
+            .account
+            .clone()
+            .replenish(account, transaction_value, reference)
+            .await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn withdraw(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
+        let resp = self
+            .account
+            .clone()
+            .withdraw(account, transaction_value, reference)
+            .await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn transfer(
+        &self,
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransferResponse, Error> {
+        let resp = self
+            .account
+            .clone()
+            .transfer(account_from, account_to, transfer_value, reference)
+            .await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn balance(
+        &self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<BalanceResponse, Error> {
+        let resp = self.account.clone().balance(account).await?;
+        Ok(BalanceResponse {
+            balance: money(resp.into_inner().balance_amount)?,
+        })
+    }
+
+    async fn balance_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<BalanceResponse, Error> {
+        let resp = self.account.clone().balance_as_of(account, as_of).await?;
+        Ok(BalanceResponse {
+            balance: money(resp.into_inner().balance_amount)?,
+        })
+    }
+
+    async fn account(&self, account: impl Into<AccountKey> + Send) -> Result<Account, Error> {
+        let resp = self.account.clone().account(account).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn account_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Account, Error> {
+        let resp = self.account.clone().account_as_of(account, as_of).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn account_history(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, Error> {
+        let resp = self.account.clone().history(account, query).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn close(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        let resp = self.account.clone().close(account).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn freeze(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        let resp = self.account.clone().freeze(account).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn reopen(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        let resp = self.account.clone().reopen(account).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn transaction(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_id: u32,
+    ) -> Result<Transaction, Error> {
+        let resp = self
+            .transaction
+            .clone()
+            .transaction(account, transaction_id)
+            .await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn history(&self) -> Result<HashMap<u32, Account>, Error> {
+        let resp = self.storage.clone().history().await?;
+        resp.into_inner()
+            .response
+            .into_iter()
+            .map(|(id, account)| Ok((id, account.try_into()?)))
+            .collect()
+    }
+
+    async fn transactions(&self, query: &HistoryQuery) -> Result<HistoryPage, Error> {
+        let resp = self.storage.clone().transactions(query).await?;
+        resp.into_inner().try_into()
+    }
+
+    async fn backup(&self) -> Result<(), Error> {
+        self.storage.clone().backup().await?;
+        Ok(())
+    }
+}
+
+/// Денежная сумма из gRPC формата.
+fn money(value: Option<proto::Money>) -> Result<Money, Error> {
+    let value: proto::Money = value.ok_or_else(|| Error::Decode("missing money".to_string()))?;
+    Money::from_scaled(value.units, value.scale).ok_or_else(|| {
+        Error::Decode(format!(
+            "invalid money: units={}, scale={}",
+            value.units, value.scale
+        ))
+    })
+}
+
+/// Время из gRPC формата. Отсутствующее время - начало эпохи, как в http ответе без поля.
+fn timestamp(value: Option<prost_types::Timestamp>) -> DateTime<Utc> {
+    value
+        .and_then(|ts| DateTime::from_timestamp(ts.seconds, u32::try_from(ts.nanos).ok()?))
+        .unwrap_or_default()
+}
+
+/// Ключ счета из пары (id, имя) gRPC формата.
+fn account_key(id: u32, name: Option<String>) -> AccountKey {
+    match name {
+        Some(name) => AccountKey::Name(name),
+        None => AccountKey::Id(id),
+    }
+}
+
+/// Статус счета из gRPC формата.
+fn status(value: i32) -> Result<Status, Error> {
+    match proto::Status::try_from(value) {
+        Ok(proto::Status::Opened) => Ok(Status::Opened),
+        Ok(proto::Status::Closed) => Ok(Status::Closed),
+        Ok(proto::Status::Frozen) => Ok(Status::Frozen),
+        Err(_) => Err(Error::Decode(format!("unknown account status: {}", value))),
+    }
+}
+
+impl TryFrom<proto::TransactionResponse> for TransactionResponse {
+    type Error = Error;
+
+    fn try_from(value: proto::TransactionResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_id: value.account_id,
+            transaction_id: value.transaction_id,
+            balance: money(value.balance_amount)?,
+            operation_id: value.operation_id,
+            fee: money(value.fee)?,
+        })
+    }
+}
+
+impl TryFrom<proto::TransferResponse> for TransferResponse {
+    type Error = Error;
+
+    fn try_from(value: proto::TransferResponse) -> Result<Self, Self::Error> {
+        let details: proto::TransferRequest = value
+            .details
+            .ok_or_else(|| Error::Decode("missing transfer details".to_string()))?;
+
+        Ok(Self {
+            info: value.info,
+            details: TransferRequest {
+                account_from: account_key(details.account_from, details.account_from_name),
+                account_to: account_key(details.account_to, details.account_to_name),
+                transfer_value: money(details.transfer_amount)?,
+                reference: details.reference,
+            },
+            operation_id: value.operation_id,
+            fee: money(value.fee)?,
+        })
+    }
+}
+
+impl TryFrom<proto::StatusResponse> for StatusResponse {
+    type Error = Error;
+
+    fn try_from(value: proto::StatusResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            account_id: value.account_id,
+            status: status(value.status)?,
+            operation_id: value.operation_id,
+        })
+    }
+}
+
+impl TryFrom<proto::Transaction> for Transaction {
+    type Error = Error;
+
+    fn try_from(value: proto::Transaction) -> Result<Self, Self::Error> {
+        let operation: proto::Operation = proto::Operation::try_from(value.operation)
+            .map_err(|_| Error::Decode(format!("unknown operation: {}", value.operation)))?;
+
+        Ok(Self {
+            id: value.id,
+            operation: operation.as_str_name().to_string(),
+            previous: money(value.previous_amount)?,
+            delta: money(value.delta_amount)?,
+            current: money(value.current_amount)?,
+            timestamp: timestamp(value.timestamp),
+            reference: value.reference,
+            counterparty: value.counterparty,
+        })
+    }
+}
+
+impl TryFrom<proto::AccountResponse> for Account {
+    type Error = Error;
+
+    fn try_from(value: proto::AccountResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: value.id,
+            name: value.name,
+            status: status(value.status)?,
+            balance: money(value.balance_amount)?,
+            transactions: value
+                .transaction
+                .into_iter()
+                .map(Transaction::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl TryFrom<proto::HistoryPage> for HistoryPage {
+    type Error = Error;
+
+    fn try_from(value: proto::HistoryPage) -> Result<Self, Self::Error> {
+        Ok(Self {
+            transactions: value
+                .transactions
+                .into_iter()
+                .map(|entry| {
+                    let transaction: proto::Transaction = entry
+                        .transaction
+                        .ok_or_else(|| Error::Decode("missing history transaction".to_string()))?;
+                    Ok(HistoryEntry {
+                        account_id: entry.account_id,
+                        transaction: transaction.try_into()?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            next_cursor: value.next_cursor,
+        })
+    }
+}
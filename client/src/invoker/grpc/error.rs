@@ -0,0 +1,176 @@
+use crate::error::{Error, ServerError};
+use crate::invoker::grpc::proto;
+use prost::Message;
+use tonic::{Code, Status};
+
+/// Тип ErrorInfo в деталях ответа (совместим с google.rpc.ErrorInfo).
+const ERROR_INFO_TYPE: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+/// Сообщение статуса при срабатывании таймаута канала.
+const TIMEOUT_EXPIRED: &str = "Timeout expired";
+
+/// Детали ошибки сервера из gRPC статуса. None - статус без ErrorInfo (ошибка транспорта и т.п.).
+pub fn server_error(status: &Status) -> Option<ServerError> {
+    let details = proto::ErrorStatus::decode(status.details()).ok()?;
+    let info: proto::ErrorInfo = details
+        .details
+        .iter()
+        .find(|any| any.type_url == ERROR_INFO_TYPE)
+        .and_then(|any| proto::ErrorInfo::decode(any.value.as_slice()).ok())?;
+
+    Some(ServerError {
+        reason: info.reason.as_str().into(),
+        message: status.message().to_string(),
+        metadata: info.metadata,
+        request_id: None,
+    })
+}
+
+/// Перекладка gRPC статуса в ошибку клиента: ошибка сервера, если статус содержит ErrorInfo,
+/// иначе - по коду статуса.
+impl From<Status> for Error {
+    fn from(status: Status) -> Self {
+        if let Some(err) = server_error(&status) {
+            return Error::Server(err);
+        }
+        match status.code() {
+            Code::DeadlineExceeded => Error::Timeout,
+            // таймаут канала (`ClientBuilder::timeout`) tonic возвращает как Cancelled
+            Code::Cancelled if status.message() == TIMEOUT_EXPIRED => Error::Timeout,
+            Code::Unavailable => Error::Transport(Box::new(status)),
+            code => Error::Grpc {
+                code,
+                message: status.message().to_string(),
+            },
+        }
+    }
+}
+
+/// Ошибка установки соединения.
+impl From<tonic::transport::Error> for Error {
+    fn from(err: tonic::transport::Error) -> Self {
+        Error::Transport(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Reason;
+    use std::collections::HashMap;
+
+    /// Статус с деталями ErrorInfo, как его возвращает сервер.
+    fn status(code: Code, reason: &str, metadata: &[(&str, &str)]) -> Status {
+        let info = proto::ErrorInfo {
+            reason: reason.to_string(),
+            domain: "bank".to_string(),
+            metadata: metadata
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        };
+        let details = proto::ErrorStatus {
+            code: code as i32,
+            message: "details".to_string(),
+            details: vec![prost_types::Any {
+                type_url: ERROR_INFO_TYPE.to_string(),
+                value: info.encode_to_vec(),
+            }],
+        };
+
+        Status::with_details(
+            code,
+            "account: '7' is frozen",
+            details.encode_to_vec().into(),
+        )
+    }
+
+    #[test]
+    fn error_info_is_server_error() {
+        let err: Error = status(
+            Code::FailedPrecondition,
+            "ACCOUNT_FROZEN",
+            &[("account", "7")],
+        )
+        .into();
+        let Error::Server(err) = err else {
+            panic!("expected server error, got: {:?}", err);
+        };
+
+        assert_eq!(err.reason, Reason::AccountFrozen);
+        assert_eq!(err.message, "account: '7' is frozen");
+        assert_eq!(
+            err.metadata,
+            HashMap::from([("account".to_string(), "7".to_string())])
+        );
+        assert_eq!(err.request_id, None);
+    }
+
+    #[test]
+    fn unknown_reason_is_kept() {
+        let err: Error = status(Code::Internal, "NEW_SERVER_CODE", &[]).into();
+
+        assert_eq!(
+            err.reason(),
+            Some(&Reason::Unknown("NEW_SERVER_CODE".to_string()))
+        );
+    }
+
+    #[test]
+    fn status_without_details_follows_code() {
+        let err: Error = Status::not_found("no route").into();
+        assert!(
+            matches!(&err, Error::Grpc { code: Code::NotFound, message } if message == "no route"),
+            "{:?}",
+            err
+        );
+        let err: Error = Status::unavailable("connection refused").into();
+        assert!(matches!(err, Error::Transport(_)), "{:?}", err);
+        let err: Error = Status::deadline_exceeded("deadline").into();
+        assert!(matches!(err, Error::Timeout), "{:?}", err);
+        let err: Error = Status::cancelled(TIMEOUT_EXPIRED).into();
+        assert!(matches!(err, Error::Timeout), "{:?}", err);
+        let err: Error = Status::cancelled("cancelled by client").into();
+        assert!(
+            matches!(
+                err,
+                Error::Grpc {
+                    code: Code::Cancelled,
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn foreign_details_are_ignored() {
+        let details = proto::ErrorStatus {
+            code: Code::Internal as i32,
+            message: "details".to_string(),
+            details: vec![prost_types::Any {
+                type_url: "type.googleapis.com/google.rpc.DebugInfo".to_string(),
+                value: vec![1, 2, 3],
+            }],
+        };
+        let status = Status::with_details(Code::Internal, "boom", details.encode_to_vec().into());
+
+        assert!(server_error(&status).is_none());
+        let err: Error = status.into();
+        assert!(
+            matches!(
+                err,
+                Error::Grpc {
+                    code: Code::Internal,
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+        // бинарные детали не в формате ErrorStatus
+        let status = Status::with_details(Code::Internal, "boom", vec![0xff, 0xff].into());
+        assert!(server_error(&status).is_none());
+    }
+}
@@ -1,17 +1,95 @@
 pub mod account;
+mod client;
+pub mod error;
 pub mod storage;
 pub mod transaction;
 
-use crate::invoker::grpc::account::{AccountInvoke, AccountInvoker};
-use crate::invoker::grpc::storage::{StorageInvoke, StorageInvoker};
-use crate::invoker::grpc::transaction::{TransactionInvoke, TransactionInvoker};
-use crate::invoker::URL;
-use proto::bank_client::BankClient;
+use crate::entities::{AccountKey, Money, Order};
+use crate::invoker::grpc::account::AccountInvoker;
+use crate::invoker::grpc::storage::StorageInvoker;
+use crate::invoker::grpc::transaction::TransactionInvoker;
+use crate::schemas::request::HistoryQuery;
+use chrono::{DateTime, Utc};
+use tonic::metadata::MetadataMap;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::Channel;
+use tonic::{Request, Status};
 
+#[allow(clippy::enum_variant_names)]
 pub mod proto {
     tonic::include_proto!("bank");
 }
 
+/// Перекладка денежной суммы в gRPC формат.
+impl From<Money> for proto::Money {
+    fn from(value: Money) -> Self {
+        Self {
+            units: value.minor(),
+            scale: Money::SCALE,
+        }
+    }
+}
+
+/// Перекладка ключа счета в пару (id, имя) gRPC формата.
+pub(crate) fn account_ref(key: AccountKey) -> (u32, Option<String>) {
+    match key {
+        AccountKey::Id(id) => (id, None),
+        AccountKey::Name(name) => (0, Some(name)),
+    }
+}
+
+/// Перекладка ключа счета в gRPC формат.
+impl From<AccountKey> for proto::AccountId {
+    fn from(key: AccountKey) -> Self {
+        let (account_id, name) = account_ref(key);
+        Self { account_id, name }
+    }
+}
+
+/// Запрос состояния счета: текущего или на момент `as_of`.
+pub(crate) fn account_query(key: AccountKey, as_of: Option<DateTime<Utc>>) -> proto::AccountQuery {
+    let (account_id, name) = account_ref(key);
+    proto::AccountQuery {
+        account_id,
+        name,
+        as_of: as_of.map(timestamp),
+    }
+}
+
+/// Перекладка времени в gRPC формат.
+fn timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Перекладка параметров страницы истории в gRPC формат.
+/// Неизвестный тип операции передается как есть и отклоняется сервером, как в http.
+impl From<&HistoryQuery> for proto::HistoryQuery {
+    fn from(query: &HistoryQuery) -> Self {
+        Self {
+            cursor: query.cursor.clone(),
+            limit: query.limit,
+            operation: query.operation.as_deref().map(|name| {
+                proto::Operation::from_str_name(name).map_or(-1, |operation| operation as i32)
+            }),
+            from_id: query.from_id,
+            to_id: query.to_id,
+            min_amount: query.min_amount.map(Into::into),
+            max_amount: query.max_amount.map(Into::into),
+            from_time: query.from_time.map(timestamp),
+            to_time: query.to_time.map(timestamp),
+            order: match query.order {
+                Order::Asc => proto::Order::Asc,
+                Order::Desc => proto::Order::Desc,
+            } as i32,
+        }
+    }
+}
+
+/// Общая структура объектов вызова gRPC сервера. Создается через `ClientBuilder::build_grpc`.
 #[derive(Default)]
 pub struct GRPCInvoker<A, T, S>
 where
@@ -27,16 +105,29 @@ where
     pub storage: S,
 }
 
-impl GRPCInvoker<AccountInvoke, TransactionInvoke, StorageInvoke> {
+/// Канал gRPC с заголовками клиента.
+pub type GrpcChannel = InterceptedService<Channel, Headers>;
+
+/// Метаданные, добавляемые к каждому gRPC запросу.
+#[derive(Clone, Default)]
+pub struct Headers {
+    metadata: MetadataMap,
+}
+
+impl Headers {
     /// Конструктор.
-    pub async fn new() -> Self {
-        // Клиент для создания TCP соединения
-        let client = BankClient::connect(URL).await.unwrap();
+    pub fn new(metadata: MetadataMap) -> Self {
+        Self { metadata }
+    }
+}
 
-        Self {
-            account: AccountInvoke::new(client.clone()),
-            transaction: TransactionInvoke::new(client.clone()),
-            storage: StorageInvoke::new(client),
+impl Interceptor for Headers {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        for entry in self.metadata.iter() {
+            if let tonic::metadata::KeyAndValueRef::Ascii(key, value) = entry {
+                request.metadata_mut().append(key.clone(), value.clone());
+            }
         }
+        Ok(request)
     }
 }
@@ -1,16 +1,19 @@
+use crate::error::Error;
 use crate::invoker::grpc::proto;
 use crate::invoker::grpc::proto::bank_client::BankClient;
-use tonic::transport::Channel;
+use crate::invoker::grpc::GrpcChannel;
+use crate::schemas::request::HistoryQuery;
 use tonic::Response;
 
 /// Структура объекта вызова для работы с БД.
+#[derive(Clone)]
 pub struct StorageInvoke {
-    client: BankClient<Channel>,
+    client: BankClient<GrpcChannel>,
 }
 
 impl StorageInvoke {
     /// Конструктор.
-    pub fn new(client: BankClient<Channel>) -> Self {
+    pub fn new(client: BankClient<GrpcChannel>) -> Self {
         Self { client }
     }
 }
@@ -19,24 +22,37 @@ impl StorageInvoke {
 #[trait_variant::make(Send)]
 pub trait StorageInvoker {
     /// Запрос всей БД (все счета и их транзакции).
-    async fn history(
+    async fn history(&mut self) -> Result<Response<proto::HistoryResponse>, Error>;
+    /// Страница истории всех счетов.
+    async fn transactions(
         &mut self,
-    ) -> Result<Response<proto::HistoryResponse>, Box<dyn std::error::Error>>;
+        query: &HistoryQuery,
+    ) -> Result<Response<proto::HistoryPage>, Error>;
     /// Backup БД из реплики.
     /// Реплика создается и хранится на стороне сервера (server/src/backup/backup.json).
-    async fn backup(&mut self) -> Result<Response<()>, Box<dyn std::error::Error>>;
+    async fn backup(&mut self) -> Result<Response<()>, Error>;
 }
 
 impl StorageInvoker for StorageInvoke {
-    async fn history(
-        &mut self,
-    ) -> Result<Response<proto::HistoryResponse>, Box<dyn std::error::Error>> {
+    async fn history(&mut self) -> Result<Response<proto::HistoryResponse>, Error> {
         // запрос
         let resp: Response<proto::HistoryResponse> = self.client.history(()).await?;
         Ok(resp)
     }
 
-    async fn backup(&mut self) -> Result<Response<()>, Box<dyn std::error::Error>> {
+    async fn transactions(
+        &mut self,
+        query: &HistoryQuery,
+    ) -> Result<Response<proto::HistoryPage>, Error> {
+        // запрос
+        let resp: Response<proto::HistoryPage> = self
+            .client
+            .transactions(proto::HistoryQuery::from(query))
+            .await?;
+        Ok(resp)
+    }
+
+    async fn backup(&mut self) -> Result<Response<()>, Error> {
         // запрос
         let resp: Response<()> = self.client.backup(()).await?;
         Ok(resp)
@@ -1,16 +1,19 @@
-use crate::invoker::grpc::proto;
+use crate::entities::AccountKey;
+use crate::error::Error;
 use crate::invoker::grpc::proto::bank_client::BankClient;
-use tonic::transport::Channel;
+use crate::invoker::grpc::GrpcChannel;
+use crate::invoker::grpc::{account_ref, proto};
 use tonic::Response;
 
 /// Структура объекта вызова для работы с транзакциями.
+#[derive(Clone)]
 pub struct TransactionInvoke {
-    client: BankClient<Channel>,
+    client: BankClient<GrpcChannel>,
 }
 
 impl TransactionInvoke {
     /// Конструктор.
-    pub fn new(client: BankClient<Channel>) -> Self {
+    pub fn new(client: BankClient<GrpcChannel>) -> Self {
         Self { client }
     }
 }
@@ -21,21 +24,23 @@ pub trait TransactionInvoker {
     /// Получение транзакции по id.
     async fn transaction(
         &mut self,
-        account_id: u32,
+        account: impl Into<AccountKey> + Send,
         transaction_id: u32,
-    ) -> Result<Response<proto::Transaction>, Box<dyn std::error::Error>>;
+    ) -> Result<Response<proto::Transaction>, Error>;
 }
 
 impl TransactionInvoker for TransactionInvoke {
     async fn transaction(
         &mut self,
-        account_id: u32,
+        account: impl Into<AccountKey> + Send,
         transaction_id: u32,
-    ) -> Result<Response<proto::Transaction>, Box<dyn std::error::Error>> {
+    ) -> Result<Response<proto::Transaction>, Error> {
         // req body
+        let (account_id, account_name) = account_ref(account.into());
         let req: proto::Trans = proto::Trans {
             account_id,
             transaction_id,
+            account_name,
         };
         // запрос
         let resp: Response<proto::Transaction> = self.client.get_transaction(req).await?;
@@ -1,20 +1,25 @@
-use crate::entities::Account;
-use crate::invoker::URL;
+use crate::entities::{Account, AccountKey, Money};
+use crate::error::Error;
+use crate::invoker::http::error::decode;
+use crate::invoker::http::HttpClient;
 use crate::schemas::{
-    request::{ChangeBalanceRequest, TransferRequest},
-    response::{BalanceResponse, TransactionResponse, TransferResponse},
+    request::{ChangeBalanceRequest, HistoryQuery, NewAccountRequest, TransferRequest},
+    response::{
+        BalanceResponse, HistoryPage, StatusResponse, TransactionResponse, TransferResponse,
+    },
 };
-use reqwest::{Client, Response};
+use chrono::{DateTime, Utc};
+use reqwest::{RequestBuilder, Response};
 use std::path::Path;
 
 /// Структура объекта вызова для работы со счетами.
 pub struct AccountInvoke {
-    client: Client,
+    client: HttpClient,
 }
 
 impl AccountInvoke {
     /// Конструктор.
-    pub fn new(client: Client) -> Self {
+    pub fn new(client: HttpClient) -> Self {
         Self { client }
     }
 }
@@ -22,130 +27,272 @@ impl AccountInvoke {
 /// Интерфейс работы со счетами.
 #[trait_variant::make(Send)]
 pub trait AccountInvoker {
-    /// Создание счета.
-    async fn create(&self) -> Result<TransactionResponse, Box<dyn std::error::Error>>;
+    /// Создание счета с необязательным уникальным именем.
+    async fn create(&self, name: Option<&str>) -> Result<TransactionResponse, Error>;
 
     /// Пополнение счета.
     async fn replenish(
         &self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<TransactionResponse, Box<dyn std::error::Error>>;
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error>;
 
     /// Снятие со счета.
     async fn withdraw(
         &self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<TransactionResponse, Box<dyn std::error::Error>>;
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error>;
 
     /// Преевод с одного счета на другой.
     async fn transfer(
         &self,
-        account_from: u32,
-        account_to: u32,
-        transfer_value: f64,
-    ) -> Result<TransferResponse, Box<dyn std::error::Error>>;
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransferResponse, Error>;
 
     /// Запрос баланса счета.
-    async fn balance(&self, account_id: u32)
-        -> Result<BalanceResponse, Box<dyn std::error::Error>>;
+    async fn balance(
+        &self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<BalanceResponse, Error>;
+
+    /// Баланс счета на момент `as_of`.
+    async fn balance_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<BalanceResponse, Error>;
 
     /// Получение всей истории счета.
-    async fn account(&self, account_id: u32) -> Result<Account, Box<dyn std::error::Error>>;
+    async fn account(&self, account: impl Into<AccountKey> + Send) -> Result<Account, Error>;
+
+    /// Счет на момент `as_of`: статус, баланс и транзакции, проведенные до этого момента.
+    async fn account_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Account, Error>;
+
+    /// Страница истории счета.
+    async fn history(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, Error>;
+
+    /// Закрытие счета.
+    async fn close(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error>;
+
+    /// Заморозка счета.
+    async fn freeze(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error>;
+
+    /// Повторное открытие (разморозка) счета.
+    async fn reopen(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error>;
 }
 
 impl AccountInvoker for AccountInvoke {
-    async fn create(&self) -> Result<TransactionResponse, Box<dyn std::error::Error>> {
+    async fn create(&self, name: Option<&str>) -> Result<TransactionResponse, Error> {
+        // req body
+        let req: NewAccountRequest = NewAccountRequest::new(name);
         // путь
-        let path: String = Path::new(URL).join("account/new").display().to_string();
+        let path: &str = "account/new";
         // запрос
-        let res: Response = self.client.post(path).send().await?;
+        let request: RequestBuilder = self.client.post(path)?.json(&req);
+        let res: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(res.json::<TransactionResponse>().await?)
+        decode::<TransactionResponse>(res).await
     }
 
     async fn replenish(
         &self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<TransactionResponse, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
         // req body
-        let req: ChangeBalanceRequest = ChangeBalanceRequest::new(account_id, transaction_value);
+        let req: ChangeBalanceRequest =
+            ChangeBalanceRequest::new(account.into(), transaction_value, reference);
         // путь
-        let path: String = Path::new(URL)
-            .join("account/replenish")
-            .display()
-            .to_string();
+        let path: &str = "account/replenish";
         // запрос
-        let resp: Response = self.client.post(path).json(&req).send().await?;
+        let request: RequestBuilder = self.client.post(path)?.json(&req);
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<TransactionResponse>().await?)
+        decode::<TransactionResponse>(resp).await
     }
 
     async fn withdraw(
         &self,
-        account_id: u32,
-        transaction_value: f64,
-    ) -> Result<TransactionResponse, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
         // req body
-        let req: ChangeBalanceRequest = ChangeBalanceRequest::new(account_id, transaction_value);
+        let req: ChangeBalanceRequest =
+            ChangeBalanceRequest::new(account.into(), transaction_value, reference);
         // путь
-        let path: String = Path::new(URL)
-            .join("account/withdraw")
-            .display()
-            .to_string();
+        let path: &str = "account/withdraw";
         // запрос
-        let resp: Response = self.client.post(path).json(&req).send().await?;
+        let request: RequestBuilder = self.client.post(path)?.json(&req);
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<TransactionResponse>().await?)
+        decode::<TransactionResponse>(resp).await
     }
 
     async fn transfer(
         &self,
-        account_from: u32,
-        account_to: u32,
-        transfer_value: f64,
-    ) -> Result<TransferResponse, Box<dyn std::error::Error>> {
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransferResponse, Error> {
         // req body
-        let req: TransferRequest = TransferRequest::new(account_from, account_to, transfer_value);
+        let req: TransferRequest = TransferRequest::new(
+            account_from.into(),
+            account_to.into(),
+            transfer_value,
+            reference,
+        );
         // путь
-        let path: String = Path::new(URL)
-            .join("account/transfer")
+        let path: &str = "account/transfer";
+        // запрос
+        let request: RequestBuilder = self.client.post(path)?.json(&req);
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<TransferResponse>(resp).await
+    }
+
+    async fn balance(
+        &self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<BalanceResponse, Error> {
+        // путь
+        let path: String = Path::new("account/balance")
+            .join(account.into().to_string())
             .display()
             .to_string();
         // запрос
-        let resp: Response = self.client.post(path).json(&req).send().await?;
+        let request: RequestBuilder = self.client.get(&path)?;
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<TransferResponse>().await?)
+        decode::<BalanceResponse>(resp).await
     }
 
-    async fn balance(
+    async fn balance_as_of(
         &self,
-        account_id: u32,
-    ) -> Result<BalanceResponse, Box<dyn std::error::Error>> {
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<BalanceResponse, Error> {
+        // путь
+        let path: String = Path::new("account/balance")
+            .join(account.into().to_string())
+            .display()
+            .to_string();
+        // запрос
+        let request: RequestBuilder = self
+            .client
+            .get(&path)?
+            .query(&[("as_of", as_of.to_rfc3339())]);
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<BalanceResponse>(resp).await
+    }
+
+    async fn account(&self, account: impl Into<AccountKey> + Send) -> Result<Account, Error> {
+        // путь
+        let path: String = Path::new("account")
+            .join(account.into().to_string())
+            .display()
+            .to_string();
+        // запрос
+        let request: RequestBuilder = self.client.get(&path)?;
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<Account>(resp).await
+    }
+
+    async fn account_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Account, Error> {
+        // путь
+        let path: String = Path::new("account")
+            .join(account.into().to_string())
+            .display()
+            .to_string();
+        // запрос
+        let request: RequestBuilder = self
+            .client
+            .get(&path)?
+            .query(&[("as_of", as_of.to_rfc3339())]);
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<Account>(resp).await
+    }
+
+    async fn history(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, Error> {
+        // путь
+        let path: String = Path::new("account")
+            .join(account.into().to_string())
+            .join("history")
+            .display()
+            .to_string();
+        // запрос
+        let request: RequestBuilder = self.client.get(&path)?.query(query);
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<HistoryPage>(resp).await
+    }
+
+    async fn close(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        // путь
+        let path: String = Path::new("account")
+            .join(account.into().to_string())
+            .join("close")
+            .display()
+            .to_string();
+        // запрос
+        let request: RequestBuilder = self.client.post(&path)?;
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<StatusResponse>(resp).await
+    }
+
+    async fn freeze(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
         // путь
-        let path: String = Path::new(URL)
-            .join("account/balance")
-            .join(account_id.to_string())
+        let path: String = Path::new("account")
+            .join(account.into().to_string())
+            .join("freeze")
             .display()
             .to_string();
         // запрос
-        let resp: Response = self.client.get(path).send().await?;
+        let request: RequestBuilder = self.client.post(&path)?;
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<BalanceResponse>().await?)
+        decode::<StatusResponse>(resp).await
     }
 
-    async fn account(&self, account_id: u32) -> Result<Account, Box<dyn std::error::Error>> {
+    async fn reopen(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
         // путь
-        let path: String = Path::new(URL)
-            .join("account")
-            .join(account_id.to_string())
+        let path: String = Path::new("account")
+            .join(account.into().to_string())
+            .join("reopen")
             .display()
             .to_string();
         // запрос
-        let resp: Response = self.client.get(path).send().await?;
+        let request: RequestBuilder = self.client.post(&path)?;
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<Account>().await?)
+        decode::<StatusResponse>(resp).await
     }
 }
@@ -0,0 +1,121 @@
+use crate::client::BankClient;
+use crate::entities::{Account, AccountKey, Money, Transaction};
+use crate::error::Error;
+use crate::invoker::http::account::{AccountInvoke, AccountInvoker};
+use crate::invoker::http::storage::{StorageInvoke, StorageInvoker};
+use crate::invoker::http::transaction::{TransactionInvoke, TransactionInvoker};
+use crate::invoker::http::HttpInvoker;
+use crate::schemas::request::HistoryQuery;
+use crate::schemas::response::{
+    BalanceResponse, HistoryPage, StatusResponse, TransactionResponse, TransferResponse,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Клиент банка поверх http.
+impl BankClient for HttpInvoker<AccountInvoke, TransactionInvoke, StorageInvoke> {
+    async fn create(&self, name: Option<&str>) -> Result<TransactionResponse, Error> {
+        self.account.create(name).await
+    }
+
+    async fn replenish(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
+        self.account
+            .replenish(account, transaction_value, reference)
+            .await
+    }
+
+    async fn withdraw(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransactionResponse, Error> {
+        self.account
+            .withdraw(account, transaction_value, reference)
+            .await
+    }
+
+    async fn transfer(
+        &self,
+        account_from: impl Into<AccountKey> + Send,
+        account_to: impl Into<AccountKey> + Send,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Result<TransferResponse, Error> {
+        self.account
+            .transfer(account_from, account_to, transfer_value, reference)
+            .await
+    }
+
+    async fn balance(
+        &self,
+        account: impl Into<AccountKey> + Send,
+    ) -> Result<BalanceResponse, Error> {
+        self.account.balance(account).await
+    }
+
+    async fn balance_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<BalanceResponse, Error> {
+        self.account.balance_as_of(account, as_of).await
+    }
+
+    async fn account(&self, account: impl Into<AccountKey> + Send) -> Result<Account, Error> {
+        self.account.account(account).await
+    }
+
+    async fn account_as_of(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        as_of: DateTime<Utc>,
+    ) -> Result<Account, Error> {
+        self.account.account_as_of(account, as_of).await
+    }
+
+    async fn account_history(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        query: &HistoryQuery,
+    ) -> Result<HistoryPage, Error> {
+        self.account.history(account, query).await
+    }
+
+    async fn close(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        self.account.close(account).await
+    }
+
+    async fn freeze(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        self.account.freeze(account).await
+    }
+
+    async fn reopen(&self, account: impl Into<AccountKey> + Send) -> Result<StatusResponse, Error> {
+        self.account.reopen(account).await
+    }
+
+    async fn transaction(
+        &self,
+        account: impl Into<AccountKey> + Send,
+        transaction_id: u32,
+    ) -> Result<Transaction, Error> {
+        self.transaction.transaction(account, transaction_id).await
+    }
+
+    async fn history(&self) -> Result<HashMap<u32, Account>, Error> {
+        self.storage.history().await
+    }
+
+    async fn transactions(&self, query: &HistoryQuery) -> Result<HistoryPage, Error> {
+        self.storage.transactions(query).await
+    }
+
+    async fn backup(&self) -> Result<(), Error> {
+        self.storage.backup().await.map(|_| ())
+    }
+}
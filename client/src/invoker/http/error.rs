@@ -0,0 +1,136 @@
+use crate::error::{Error, ServerError};
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Ответ сервера с ошибкой.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+/// Описание ошибки в ответе сервера.
+#[derive(Deserialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+    #[serde(default)]
+    fields: HashMap<String, String>,
+    request_id: Option<String>,
+}
+
+/// Парсинг ответа: тело успешного ответа или ошибка сервера.
+/// Ответ с ошибкой без описания (прокси, неизвестный ендпоинт) возвращается как http статус.
+pub(crate) async fn decode<T: DeserializeOwned>(resp: Response) -> Result<T, Error> {
+    let status: StatusCode = resp.status();
+    if status.is_success() {
+        return Ok(resp.json::<T>().await?);
+    }
+    let body = resp.bytes().await?;
+    match serde_json::from_slice::<ErrorResponse>(&body) {
+        Ok(ErrorResponse { error }) => Err(Error::Server(ServerError {
+            reason: error.code.as_str().into(),
+            message: error.message,
+            metadata: error.fields,
+            request_id: error.request_id,
+        })),
+        Err(_) => Err(Error::Http {
+            status: status.as_u16(),
+            message: String::from_utf8_lossy(&body).into_owned(),
+        }),
+    }
+}
+
+/// Перекладка ошибки http клиента в ошибку клиента.
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Error::Timeout
+        } else if err.is_decode() {
+            Error::Decode(err.to_string())
+        } else if let Some(status) = err.status() {
+            Error::Http {
+                status: status.as_u16(),
+                message: err.to_string(),
+            }
+        } else {
+            Error::Transport(Box::new(err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Reason;
+
+    /// Ответ сервера с заданным статусом и телом.
+    fn response(status: u16, body: &str) -> Response {
+        http::Response::builder()
+            .status(status)
+            .body(body.to_string())
+            .unwrap()
+            .into()
+    }
+
+    #[tokio::test]
+    async fn envelope_is_server_error() {
+        let body = r#"{"error": {
+            "code": "ACCOUNT_NOT_FOUND",
+            "message": "account: '99' not found",
+            "fields": {"account": "99"},
+            "request_id": "req-1"
+        }}"#;
+        let err: Error = decode::<serde_json::Value>(response(404, body))
+            .await
+            .unwrap_err();
+        let Error::Server(err) = err else {
+            panic!("expected server error, got: {:?}", err);
+        };
+
+        assert_eq!(err.reason, Reason::AccountNotFound);
+        assert_eq!(err.message, "account: '99' not found");
+        assert_eq!(err.account(), Some("99"));
+        assert_eq!(err.request_id.as_deref(), Some("req-1"));
+    }
+
+    #[tokio::test]
+    async fn unknown_code_is_kept() {
+        let body =
+            r#"{"error": {"code": "NEW_SERVER_CODE", "message": "new", "request_id": null}}"#;
+        let err: Error = decode::<serde_json::Value>(response(409, body))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.reason(),
+            Some(&Reason::Unknown("NEW_SERVER_CODE".to_string()))
+        );
+        assert!(err.server().unwrap().metadata.is_empty());
+    }
+
+    #[tokio::test]
+    async fn error_without_envelope_is_http_status() {
+        let err: Error = decode::<serde_json::Value>(response(502, "bad gateway"))
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(&err, Error::Http { status: 502, message } if message == "bad gateway"),
+            "{:?}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn success_body_is_decoded() {
+        let value: serde_json::Value = decode(response(200, r#"{"balance": "1.00"}"#))
+            .await
+            .unwrap();
+        assert_eq!(value["balance"], "1.00");
+
+        let err: Error = decode::<u32>(response(200, "not json")).await.unwrap_err();
+        assert!(matches!(err, Error::Decode(_)), "{:?}", err);
+    }
+}
@@ -1,13 +1,17 @@
 pub mod account;
+mod client;
+mod error;
 pub mod storage;
 pub mod transaction;
 
-use crate::invoker::http::account::{AccountInvoke, AccountInvoker};
-use crate::invoker::http::storage::{StorageInvoke, StorageInvoker};
-use crate::invoker::http::transaction::{TransactionInvoke, TransactionInvoker};
-use reqwest::Client;
+use crate::builder::Retry;
+use crate::error::Error;
+use crate::invoker::http::account::AccountInvoker;
+use crate::invoker::http::storage::StorageInvoker;
+use crate::invoker::http::transaction::TransactionInvoker;
+use reqwest::{Client, RequestBuilder, Response, Url};
 
-/// Общая структура объектов вызова хендлеров сервера.
+/// Общая структура объектов вызова хендлеров сервера. Создается через `ClientBuilder::build_http`.
 #[derive(Default)]
 pub struct HttpInvoker<A, T, S>
 where
@@ -23,16 +27,58 @@ where
     pub storage: S,
 }
 
-impl HttpInvoker<AccountInvoke, TransactionInvoke, StorageInvoke> {
+/// Http клиент сервера: пул соединений, адрес сервера и политика повторов.
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    url: Url,
+    retry: Retry,
+}
+
+impl HttpClient {
     /// Конструктор.
-    pub fn new() -> Self {
-        // Клиент для создания TCP соединения
-        let client: Client = Client::builder().build().unwrap();
-
-        Self {
-            account: AccountInvoke::new(client.clone()),
-            transaction: TransactionInvoke::new(client.clone()),
-            storage: StorageInvoke::new(client),
+    pub(crate) fn new(client: Client, url: &str, retry: Retry) -> Result<Self, Error> {
+        let mut url: Url =
+            Url::parse(url).map_err(|err| Error::Config(format!("url '{}': {}", url, err)))?;
+        // путь ендпоинта добавляется к адресу сервера
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Ok(Self { client, url, retry })
+    }
+
+    /// Полный адрес ендпоинта.
+    pub(crate) fn url(&self, path: &str) -> Result<Url, Error> {
+        self.url
+            .join(path)
+            .map_err(|err| Error::Config(format!("path '{}': {}", path, err)))
+    }
+
+    /// GET запрос.
+    pub(crate) fn get(&self, path: &str) -> Result<RequestBuilder, Error> {
+        Ok(self.client.get(self.url(path)?))
+    }
+
+    /// POST запрос.
+    pub(crate) fn post(&self, path: &str) -> Result<RequestBuilder, Error> {
+        Ok(self.client.post(self.url(path)?))
+    }
+
+    /// Отправка запроса. Запрос, не дошедший до сервера (ошибка соединения), повторяется по политике.
+    pub(crate) async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let mut attempt: u32 = 0;
+        loop {
+            let Some(next) = request.try_clone() else {
+                return Ok(request.send().await?);
+            };
+            match next.send().await {
+                Err(err) if err.is_connect() && attempt < self.retry.retries => {
+                    tokio::time::sleep(self.retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                resp => return Ok(resp?),
+            }
         }
     }
 }
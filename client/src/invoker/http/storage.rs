@@ -1,17 +1,20 @@
 use crate::entities::Account;
-use crate::invoker::URL;
-use reqwest::{Client, Response};
+use crate::error::Error;
+use crate::invoker::http::error::decode;
+use crate::invoker::http::HttpClient;
+use crate::schemas::request::HistoryQuery;
+use crate::schemas::response::HistoryPage;
+use reqwest::{RequestBuilder, Response};
 use std::collections::HashMap;
-use std::path::Path;
 
 /// Структура объекта вызова для работы с БД.
 pub struct StorageInvoke {
-    client: Client,
+    client: HttpClient,
 }
 
 impl StorageInvoke {
     /// Конструктор.
-    pub fn new(client: Client) -> Self {
+    pub fn new(client: HttpClient) -> Self {
         Self { client }
     }
 }
@@ -20,28 +23,42 @@ impl StorageInvoke {
 #[trait_variant::make(Send)]
 pub trait StorageInvoker {
     /// Запрос всей БД (все счета и их транзакции).
-    async fn history(&self) -> Result<HashMap<u32, Account>, Box<dyn std::error::Error>>;
+    async fn history(&self) -> Result<HashMap<u32, Account>, Error>;
+    /// Страница истории всех счетов.
+    async fn transactions(&self, query: &HistoryQuery) -> Result<HistoryPage, Error>;
     /// Backup БД из реплики.
     /// Реплика создается и хранится на стороне сервера (server/src/backup/backup.json).
-    async fn backup(&self) -> Result<HashMap<String, String>, Box<dyn std::error::Error>>;
+    async fn backup(&self) -> Result<HashMap<String, String>, Error>;
 }
 
 impl StorageInvoker for StorageInvoke {
-    async fn history(&self) -> Result<HashMap<u32, Account>, Box<dyn std::error::Error>> {
+    async fn history(&self) -> Result<HashMap<u32, Account>, Error> {
         // путь
-        let path: String = Path::new(URL).join("storage/history").display().to_string();
+        let path: &str = "storage/history";
         // запрос
-        let resp: Response = self.client.get(path).send().await?;
+        let request: RequestBuilder = self.client.get(path)?;
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<HashMap<u32, Account>>().await?)
+        decode::<HashMap<u32, Account>>(resp).await
     }
 
-    async fn backup(&self) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    async fn transactions(&self, query: &HistoryQuery) -> Result<HistoryPage, Error> {
         // путь
-        let path: String = Path::new(URL).join("storage/backup").display().to_string();
+        let path: &str = "storage/transactions";
         // запрос
-        let resp: Response = self.client.post(path).send().await?;
+        let request: RequestBuilder = self.client.get(path)?.query(query);
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<HashMap<String, String>>().await?)
+        decode::<HistoryPage>(resp).await
+    }
+
+    async fn backup(&self) -> Result<HashMap<String, String>, Error> {
+        // путь
+        let path: &str = "storage/backup";
+        // запрос
+        let request: RequestBuilder = self.client.post(path)?;
+        let resp: Response = self.client.send(request).await?;
+        // парсинг ответа
+        decode::<HashMap<String, String>>(resp).await
     }
 }
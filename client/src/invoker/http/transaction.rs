@@ -1,16 +1,18 @@
-use crate::entities::Transaction;
-use crate::invoker::URL;
-use reqwest::{Client, Response};
+use crate::entities::{AccountKey, Transaction};
+use crate::error::Error;
+use crate::invoker::http::error::decode;
+use crate::invoker::http::HttpClient;
+use reqwest::{RequestBuilder, Response};
 use std::path::Path;
 
 /// Структура объекта вызова для работы с транзакциями.
 pub struct TransactionInvoke {
-    client: Client,
+    client: HttpClient,
 }
 
 impl TransactionInvoke {
     /// Конструктор.
-    pub fn new(client: Client) -> Self {
+    pub fn new(client: HttpClient) -> Self {
         Self { client }
     }
 }
@@ -21,27 +23,27 @@ pub trait TransactionInvoker {
     /// Получение транзакции по ID.
     async fn transaction(
         &self,
-        account_id: u32,
+        account: impl Into<AccountKey> + Send,
         transaction_id: u32,
-    ) -> Result<Transaction, Box<dyn std::error::Error>>;
+    ) -> Result<Transaction, Error>;
 }
 
 impl TransactionInvoker for TransactionInvoke {
     async fn transaction(
         &self,
-        account_id: u32,
+        account: impl Into<AccountKey> + Send,
         transaction_id: u32,
-    ) -> Result<Transaction, Box<dyn std::error::Error>> {
+    ) -> Result<Transaction, Error> {
         // путь
-        let path: String = Path::new(URL)
-            .join("transaction")
-            .join(account_id.to_string())
+        let path: String = Path::new("transaction")
+            .join(account.into().to_string())
             .join(transaction_id.to_string())
             .display()
             .to_string();
         // запрос
-        let resp: Response = self.client.get(path).send().await?;
+        let request: RequestBuilder = self.client.get(&path)?;
+        let resp: Response = self.client.send(request).await?;
         // парсинг ответа
-        Ok(resp.json::<Transaction>().await?)
+        decode::<Transaction>(resp).await
     }
 }
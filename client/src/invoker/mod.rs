@@ -1,5 +1,2 @@
 pub mod grpc;
 pub mod http;
-
-/// URL сервера.
-pub const URL: &str = "http://localhost:8080";
@@ -1,3 +1,12 @@
+pub mod builder;
+pub mod client;
 pub mod entities;
+pub mod error;
+pub mod history;
 pub mod invoker;
 pub mod schemas;
+
+pub use builder::ClientBuilder;
+pub use client::{BankClient, Client, Protocol};
+pub use error::Error;
+pub use history::History;
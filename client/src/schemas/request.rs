@@ -1,20 +1,30 @@
+use crate::entities::{AccountKey, Money, Order};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Структура для парсинга ответа перевода со счета на счет.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransferRequest {
-    pub account_from: u32,
-    pub account_to: u32,
-    pub transfer_value: f64,
+    pub account_from: AccountKey,
+    pub account_to: AccountKey,
+    pub transfer_value: Money,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
 }
 
 impl TransferRequest {
     /// Конструктор.
-    pub fn new(account_from: u32, account_to: u32, transfer_value: f64) -> Self {
+    pub fn new(
+        account_from: AccountKey,
+        account_to: AccountKey,
+        transfer_value: Money,
+        reference: Option<&str>,
+    ) -> Self {
         Self {
             account_from,
             account_to,
             transfer_value,
+            reference: reference.map(str::to_string),
         }
     }
 }
@@ -22,16 +32,116 @@ impl TransferRequest {
 /// Структура запроса изменения баланма счета.
 #[derive(Debug, Serialize)]
 pub struct ChangeBalanceRequest {
-    pub account_id: u32,
-    pub transaction_value: f64,
+    pub account_id: AccountKey,
+    pub transaction_value: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
 }
 
 impl ChangeBalanceRequest {
     /// Конструктор
-    pub fn new(account_id: u32, transaction_value: f64) -> Self {
+    pub fn new(account_id: AccountKey, transaction_value: Money, reference: Option<&str>) -> Self {
         Self {
             account_id,
             transaction_value,
+            reference: reference.map(str::to_string),
         }
     }
 }
+
+/// Структура запроса создания счета.
+#[derive(Debug, Serialize)]
+pub struct NewAccountRequest {
+    pub name: Option<String>,
+}
+
+impl NewAccountRequest {
+    /// Конструктор
+    pub fn new(name: Option<&str>) -> Self {
+        Self {
+            name: name.map(str::to_string),
+        }
+    }
+}
+
+/// Параметры запроса страницы истории: курсор, размер страницы, фильтры и порядок.
+/// Границы диапазонов включаются, кроме `to_time`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HistoryQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    // тип операции (`Replenish`, `Withdraw`, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Money>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_time: Option<DateTime<Utc>>,
+    pub order: Order,
+}
+
+impl HistoryQuery {
+    /// Конструктор запроса первой страницы без фильтров.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Размер страницы (1..=1000, по умолчанию 100).
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Тип операции.
+    pub fn operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = Some(operation.into());
+        self
+    }
+
+    /// Диапазон id транзакций.
+    pub fn ids(mut self, from_id: Option<u32>, to_id: Option<u32>) -> Self {
+        self.from_id = from_id;
+        self.to_id = to_id;
+        self
+    }
+
+    /// Диапазон сумм транзакций.
+    pub fn amounts(mut self, min_amount: Option<Money>, max_amount: Option<Money>) -> Self {
+        self.min_amount = min_amount;
+        self.max_amount = max_amount;
+        self
+    }
+
+    /// Период: с `from_time` включительно до `to_time`.
+    pub fn period(
+        mut self,
+        from_time: Option<DateTime<Utc>>,
+        to_time: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.from_time = from_time;
+        self.to_time = to_time;
+        self
+    }
+
+    /// Порядок выдачи.
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Курсор `next_cursor` предыдущей страницы.
+    pub fn cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+}
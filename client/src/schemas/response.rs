@@ -1,3 +1,4 @@
+use crate::entities::{Money, Status, Transaction};
 use crate::schemas::request::TransferRequest;
 use serde::Deserialize;
 
@@ -6,7 +7,10 @@ use serde::Deserialize;
 pub struct TransactionResponse {
     pub account_id: u32,
     pub transaction_id: u32,
-    pub balance: f64,
+    pub balance: Money,
+    pub operation_id: u64,
+    #[serde(default)]
+    pub fee: Money,
 }
 
 /// Структура ответа успешного перевода.
@@ -14,10 +18,37 @@ pub struct TransactionResponse {
 pub struct TransferResponse {
     pub info: String,
     pub details: TransferRequest,
+    pub operation_id: u64,
+    #[serde(default)]
+    pub fee: Money,
 }
 
 /// Структура ответа баланса счета.
 #[derive(Debug, Deserialize)]
 pub struct BalanceResponse {
-    pub balance: f64,
+    pub balance: Money,
+}
+
+/// Структура ответа изменения статуса счета.
+#[derive(Debug, Deserialize)]
+pub struct StatusResponse {
+    pub account_id: u32,
+    pub status: Status,
+    pub operation_id: u64,
+}
+
+/// Транзакция в истории.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryEntry {
+    pub account_id: u32,
+    pub transaction: Transaction,
+}
+
+/// Структура ответа страницы истории.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryPage {
+    pub transactions: Vec<HistoryEntry>,
+    // курсор следующей страницы, отсутствует на последней странице
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
@@ -0,0 +1,264 @@
+//! Денежная сумма с фиксированной точкой, общая для сервера и клиента банка.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Денежная сумма с фиксированной точкой.
+/// Хранится в минимальных единицах валюты (копейках), масштаб - `Money::SCALE` знака.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    /// Количество знаков после запятой (масштаб валюты).
+    pub const SCALE: u32 = 2;
+    /// Количество минимальных единиц в одной целой.
+    const FACTOR: i64 = 10_i64.pow(Self::SCALE);
+    /// Нулевая сумма.
+    pub const ZERO: Self = Self(0);
+
+    /// Конструктор из минимальных единиц валюты.
+    pub const fn from_minor(minor: i64) -> Self {
+        Self(minor)
+    }
+
+    /// Сумма в минимальных единицах валюты.
+    pub const fn minor(self) -> i64 {
+        self.0
+    }
+
+    /// Конструктор из минимальных единиц с произвольным масштабом (units / 10^scale).
+    /// Возвращает None, если сумму нельзя точно представить в масштабе `Money::SCALE`.
+    pub fn from_scaled(units: i64, scale: u32) -> Option<Self> {
+        if scale <= Self::SCALE {
+            units
+                .checked_mul(10_i64.checked_pow(Self::SCALE - scale)?)
+                .map(Self::from_minor)
+        } else {
+            let divider: i64 = 10_i64.checked_pow(scale - Self::SCALE)?;
+            if units % divider != 0 {
+                return None;
+            }
+            Some(Self::from_minor(units / divider))
+        }
+    }
+
+    /// Нулевая сумма.
+    pub const fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Сумма больше нуля.
+    pub const fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    /// Сложение без переполнения.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+
+    /// Вычитание без переполнения.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.0.checked_sub(other.0).map(Self)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign: &str = if self.0 < 0 { "-" } else { "" };
+        let abs: u64 = self.0.unsigned_abs();
+        let factor: u64 = Self::FACTOR.unsigned_abs();
+        write!(
+            f,
+            "{}{}.{:0width$}",
+            sign,
+            abs / factor,
+            abs % factor,
+            width = Self::SCALE as usize
+        )
+    }
+}
+
+/// Ошибка парсинга денежной суммы.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseMoneyError(String);
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid money amount: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseMoneyError(s.to_string());
+        let value: &str = s.trim();
+        // знак
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, value),
+        };
+        // целая и дробная части
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if int_part.is_empty()
+            || frac_part.len() > Self::SCALE as usize
+            || !int_part.chars().all(|c| c.is_ascii_digit())
+            || !frac_part.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(err());
+        }
+        // дробная часть, дополненная нулями до масштаба
+        let frac: i64 = format!("{:0<width$}", frac_part, width = Self::SCALE as usize)
+            .parse()
+            .map_err(|_| err())?;
+        let minor: i64 = int_part
+            .parse::<i64>()
+            .ok()
+            .and_then(|int| int.checked_mul(Self::FACTOR))
+            .and_then(|int| int.checked_add(frac))
+            .ok_or_else(err)?;
+
+        Ok(Self(if negative { -minor } else { minor }))
+    }
+}
+
+/// Сериализация в строку с фиксированным количеством знаков ("1.00").
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Десериализация из строки ("1.00") или из json-числа (1, 0.1).
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+/// Visitor для десериализации денежной суммы.
+struct MoneyVisitor;
+
+impl<'de> Visitor<'de> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a money amount with at most {} decimal places",
+            Money::SCALE
+        )
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Money::from_scaled(v, 0).ok_or_else(|| E::custom(ParseMoneyError(v.to_string())))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        i64::try_from(v)
+            .ok()
+            .and_then(|v| Money::from_scaled(v, 0))
+            .ok_or_else(|| E::custom(ParseMoneyError(v.to_string())))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        // кратчайшее десятичное представление числа (0.1 -> "0.1")
+        v.to_string().parse().map_err(E::custom)
+    }
+}
+
+/// Схема для swagger: сумма передается строкой.
+#[cfg(feature = "utoipa")]
+impl<'s> utoipa::ToSchema<'s> for Money {
+    fn schema() -> (&'s str, utoipa::openapi::RefOr<utoipa::openapi::Schema>) {
+        (
+            "Money",
+            utoipa::openapi::ObjectBuilder::new()
+                .schema_type(utoipa::openapi::SchemaType::String)
+                .description(Some(
+                    "Fixed-point money amount with 2 decimal places. \
+                    Accepted as a string or a json number.",
+                ))
+                .example(Some(serde_json::json!("100.50")))
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ten_tenths_make_one() {
+        let tenth: Money = serde_json::from_str("0.1").unwrap();
+        let sum: Money = (0..10).fold(Money::ZERO, |sum, _| sum.checked_add(tenth).unwrap());
+
+        assert_eq!(sum, Money::from_minor(100));
+        assert_eq!(sum.to_string(), "1.00");
+    }
+
+    #[test]
+    fn parses_and_formats() {
+        assert_eq!("100.5".parse(), Ok(Money::from_minor(10_050)));
+        assert_eq!("-0.05".parse(), Ok(Money::from_minor(-5)));
+        assert_eq!(" 7 ".parse(), Ok(Money::from_minor(700)));
+        assert_eq!(Money::from_minor(-1).to_string(), "-0.01");
+        assert_eq!(Money::from_minor(123_456).to_string(), "1234.56");
+    }
+
+    #[test]
+    fn rejects_more_than_two_decimal_places() {
+        assert!("0.001".parse::<Money>().is_err());
+        assert!("1.234".parse::<Money>().is_err());
+        assert!(serde_json::from_str::<Money>("0.001").is_err());
+        assert!(serde_json::from_str::<Money>("\"10.555\"").is_err());
+        // из gRPC: масштаб больше двух знаков допустим, только если сумма точная
+        assert_eq!(Money::from_scaled(1_230, 3), Some(Money::from_minor(123)));
+        assert_eq!(Money::from_scaled(1_234, 3), None);
+    }
+
+    #[test]
+    fn rejects_malformed_amounts() {
+        for text in ["", ".5", "1,00", "+1", "1e3", "--1", "1.-5"] {
+            assert!(text.parse::<Money>().is_err(), "'{}' is accepted", text);
+        }
+    }
+
+    #[test]
+    fn detects_overflow() {
+        let max: Money = Money::from_minor(i64::MAX);
+
+        assert_eq!(max.checked_add(Money::from_minor(1)), None);
+        assert_eq!(
+            Money::from_minor(i64::MIN).checked_sub(Money::from_minor(1)),
+            None
+        );
+        assert!("92233720368547758.08".parse::<Money>().is_err());
+        assert_eq!(
+            "92233720368547758.07".parse::<Money>(),
+            Ok(Money::from_minor(i64::MAX))
+        );
+        assert_eq!(Money::from_scaled(i64::MAX, 0), None);
+        assert!(serde_json::from_str::<Money>(&u64::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn serializes_as_string() {
+        let amount: Money = Money::from_minor(1_000);
+
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "\"10.00\"");
+        assert_eq!(serde_json::from_str::<Money>("10").unwrap(), amount);
+        assert_eq!(serde_json::from_str::<Money>("\"10\"").unwrap(), amount);
+    }
+}
@@ -0,0 +1,340 @@
+//! Пропускная способность записи с репликацией на диск: прежняя схема против текущей.
+//!
+//! Прежняя схема (исходная версия сервиса): бд под одной блокировкой `RwLock`, каждый запрос,
+//! включая чтения, берет ее на запись, после каждой операции записи вся бд сериализуется
+//! в backup.json под той же блокировкой.
+//! Текущая схема: бд с WAL и снимками (`CacheImpl::with_backup`), блокировки счетов,
+//! групповой сброс WAL на диск без блокировки бд.
+//!
+//! Запуск: `cargo bench -p server`. Параметры - переменные окружения:
+//! `BENCH_THREADS` (через запятую, по умолчанию 1,2,4,8), `BENCH_OPERATIONS` (операций на поток, 2000),
+//! `BENCH_ACCOUNTS` (100), `BENCH_READS` (доля чтений баланса в процентах, 50).
+
+use serde::Serialize;
+use server::adapter::storage::cache::CacheImpl;
+use server::adapter::storage::{Storage, StorageState, Storages};
+use server::domain::entities::account::{Account, AccountKey};
+use server::domain::entities::money::Money;
+use server::domain::entities::transaction::Operation::{
+    Replenish, TransferDecrease, TransferIncrease,
+};
+use server::domain::entities::transaction::{Transaction, TransferRequest};
+use server::domain::usecases::account;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Начальный баланс счета нагрузки (в копейках): переводы не упираются в овердрафт.
+const INITIAL_MINOR: i64 = 100_000_000;
+
+/// Параметры нагрузки.
+#[derive(Debug, Clone)]
+struct Params {
+    threads: Vec<usize>,
+    operations: usize,
+    accounts: u32,
+    reads: u64,
+}
+
+impl Params {
+    /// Параметры из переменных окружения.
+    fn from_env() -> Result<Self, Box<dyn Error>> {
+        let threads: Vec<usize> = match env::var("BENCH_THREADS") {
+            Ok(list) => list
+                .split(',')
+                .map(|n| n.trim().parse())
+                .collect::<Result<_, _>>()?,
+            Err(_) => vec![1, 2, 4, 8],
+        };
+
+        Ok(Self {
+            threads,
+            operations: var("BENCH_OPERATIONS", 2000)?,
+            accounts: var("BENCH_ACCOUNTS", 100)?,
+            reads: var::<u64>("BENCH_READS", 50)?.min(100),
+        })
+    }
+}
+
+/// Числовая переменная окружения со значением по умолчанию.
+fn var<T>(name: &str, default: T) -> Result<T, Box<dyn Error>>
+where
+    T: std::str::FromStr,
+    T::Err: Error + 'static,
+{
+    match env::var(name) {
+        Ok(value) => Ok(value.trim().parse()?),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Операции банка под нагрузкой.
+trait Bank: Send + Sync {
+    /// Баланс счета.
+    fn balance(&self, id: u32) -> Result<Money, Box<dyn Error>>;
+    /// Перевод со счета на счет.
+    fn transfer(&self, from: u32, to: u32, value: Money) -> Result<(), Box<dyn Error>>;
+    /// Сумма балансов всех счетов.
+    fn total(&self) -> Result<Money, Box<dyn Error>>;
+}
+
+/// Прежняя схема: одна блокировка бд на запись для каждого запроса,
+/// после операции записи - сериализация всей бд в backup.json (`fs::write`, как в исходной версии).
+struct Global {
+    db: RwLock<HashMap<u32, Account>>,
+    path: PathBuf,
+}
+
+impl Global {
+    /// Банк из `accounts` счетов с начальным балансом.
+    fn new(dir: &Path, accounts: u32) -> Self {
+        let db: HashMap<u32, Account> = (1..=accounts)
+            .map(|id| {
+                let mut account: Account = Account::new();
+                account.id = id;
+                account.balance = Money::from_minor(INITIAL_MINOR);
+                account.transactions.push(Transaction::new(
+                    0,
+                    Replenish,
+                    Money::ZERO,
+                    account.balance,
+                    account.balance,
+                ));
+                (id, account)
+            })
+            .collect();
+
+        Self {
+            db: RwLock::new(db),
+            path: dir.join("backup.json"),
+        }
+    }
+
+    /// Реплика всей бд в backup.json (json с отступами, без fsync).
+    fn store(&self, db: &HashMap<u32, Account>) -> Result<(), Box<dyn Error>> {
+        let mut buf: Vec<u8> = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        db.serialize(&mut ser)?;
+        fs::write(&self.path, buf)?;
+
+        Ok(())
+    }
+}
+
+impl Bank for Global {
+    fn balance(&self, id: u32) -> Result<Money, Box<dyn Error>> {
+        let db = self.db.write().unwrap_or_else(PoisonError::into_inner);
+        Ok(db.get(&id).ok_or("account not exists")?.balance)
+    }
+
+    fn transfer(&self, from: u32, to: u32, value: Money) -> Result<(), Box<dyn Error>> {
+        let mut db = self.db.write().unwrap_or_else(PoisonError::into_inner);
+        if from == to {
+            return Err("self transfer".into());
+        }
+        for (id, operation, counterparty) in
+            [(from, TransferDecrease, to), (to, TransferIncrease, from)]
+        {
+            let account: &mut Account = db.get_mut(&id).ok_or("account not exists")?;
+            let current: Money = match operation {
+                TransferDecrease if account.balance < value => return Err("overdraft".into()),
+                TransferDecrease => account.balance.checked_sub(value),
+                _ => account.balance.checked_add(value),
+            }
+            .ok_or("balance overflow")?;
+            let mut tx: Transaction = Transaction::new(
+                account.transactions.len() as u32,
+                operation,
+                account.balance,
+                value,
+                current,
+            );
+            tx.counterparty = Some(counterparty);
+            account.transactions.push(tx);
+            account.balance = current;
+        }
+        self.store(&db)
+    }
+
+    fn total(&self) -> Result<Money, Box<dyn Error>> {
+        let db = self.db.read().unwrap_or_else(PoisonError::into_inner);
+        sum(db.values())
+    }
+}
+
+/// Текущая схема: сервисная бд с WAL и снимками, usecases сервиса.
+struct Current {
+    storage: StorageState,
+}
+
+impl Current {
+    /// Банк из `accounts` счетов с начальным балансом.
+    fn new(dir: &Path, accounts: u32) -> Result<Self, Box<dyn Error>> {
+        let storage: StorageState = Arc::new(Storage::new(Box::new(CacheImpl::with_backup(dir))));
+        for _ in 0..accounts {
+            let id: u32 = account::new_account(Arc::clone(&storage), None)?.account_id;
+            account::change_acc_balance(
+                &storage,
+                Money::from_minor(INITIAL_MINOR),
+                id.into(),
+                Replenish,
+                None,
+                None,
+            )?;
+        }
+
+        Ok(Self { storage })
+    }
+}
+
+impl Bank for Current {
+    fn balance(&self, id: u32) -> Result<Money, Box<dyn Error>> {
+        Ok(account::balance(&self.storage, id.into(), None)?.balance)
+    }
+
+    fn transfer(&self, from: u32, to: u32, value: Money) -> Result<(), Box<dyn Error>> {
+        let payload = TransferRequest {
+            account_from: AccountKey::Id(from),
+            account_to: AccountKey::Id(to),
+            transfer_value: value,
+            reference: None,
+        };
+        account::transfer(&self.storage, payload, None)?;
+
+        Ok(())
+    }
+
+    fn total(&self) -> Result<Money, Box<dyn Error>> {
+        sum(self.storage.db()?.get_accounts().values())
+    }
+}
+
+/// Сумма балансов счетов.
+fn sum<'a>(mut accounts: impl Iterator<Item = &'a Account>) -> Result<Money, Box<dyn Error>> {
+    accounts.try_fold(Money::ZERO, |sum, acc| {
+        sum.checked_add(acc.balance)
+            .ok_or_else(|| "balance overflow".into())
+    })
+}
+
+/// Результат прогона нагрузки.
+#[derive(Debug)]
+struct Run {
+    operations: usize,
+    failed: usize,
+    elapsed: Duration,
+}
+
+impl Run {
+    /// Пропускная способность, операций в секунду.
+    fn throughput(&self) -> f64 {
+        self.operations as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Прогон нагрузки в `threads` потоках: чтения баланса и переводы между случайными счетами.
+/// После прогона проверяется, что переводы не изменили сумму балансов банка.
+fn load(bank: Arc<dyn Bank>, params: &Params, threads: usize) -> Result<Run, Box<dyn Error>> {
+    let total: Money = bank.total()?;
+    let started: Instant = Instant::now();
+    let workers: Vec<_> = (0..threads)
+        .map(|n| {
+            let bank: Arc<dyn Bank> = Arc::clone(&bank);
+            let params: Params = params.clone();
+            thread::spawn(move || {
+                let mut rng: XorShift = XorShift::new(n as u64 + 1);
+                let mut failed: usize = 0;
+                for _ in 0..params.operations {
+                    let from: u32 = 1 + rng.below(u64::from(params.accounts)) as u32;
+                    let result: Result<(), Box<dyn Error>> = if rng.below(100) < params.reads {
+                        bank.balance(from).map(|_| ())
+                    } else {
+                        let to: u32 = 1 + rng.below(u64::from(params.accounts)) as u32;
+                        let value: Money = Money::from_minor(1 + rng.below(100) as i64);
+                        bank.transfer(from, to, value)
+                    };
+                    // перевод самому себе при случайном выборе счетов
+                    if result.is_err() {
+                        failed += 1;
+                    }
+                }
+                failed
+            })
+        })
+        .collect();
+    let mut failed: usize = 0;
+    for worker in workers {
+        failed += worker.join().map_err(|_| "bench worker panicked")?;
+    }
+    let elapsed: Duration = started.elapsed();
+    if bank.total()? != total {
+        return Err("total balance changed".into());
+    }
+
+    Ok(Run {
+        operations: threads * params.operations,
+        failed,
+        elapsed,
+    })
+}
+
+/// Детерминированный генератор нагрузки (xorshift64).
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    /// Случайное число в диапазоне 0..bound.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound.max(1)
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let params: Params = Params::from_env()?;
+    println!(
+        "locking: accounts={}, operations={} per thread, reads={}%",
+        params.accounts, params.operations, params.reads
+    );
+    for &threads in &params.threads {
+        // новый банк на каждый прогон: размер реплики прежней схемы растет с числом операций
+        let global_dir = tempfile::tempdir()?;
+        let global: Run = load(
+            Arc::new(Global::new(global_dir.path(), params.accounts)),
+            &params,
+            threads,
+        )?;
+        let current_dir = tempfile::tempdir()?;
+        let current: Run = load(
+            Arc::new(Current::new(current_dir.path(), params.accounts)?),
+            &params,
+            threads,
+        )?;
+        for (name, run) in [("global", &global), ("current", &current)] {
+            println!(
+                "locking/{name}: threads={threads}, operations={}, failed={}, elapsed={}ms, throughput={:.0} ops/s",
+                run.operations,
+                run.failed,
+                run.elapsed.as_millis(),
+                run.throughput()
+            );
+        }
+        println!(
+            "locking threads={threads}: current/global = {:.2}x",
+            current.throughput() / global.throughput()
+        );
+    }
+
+    Ok(())
+}
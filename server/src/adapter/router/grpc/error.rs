@@ -0,0 +1,117 @@
+use crate::adapter::router::grpc::proto;
+use crate::domain::errors::AppError;
+use prost::Message;
+use tonic::codegen::Bytes;
+use tonic::{Code, Status};
+
+/// Домен ошибок сервиса в ErrorInfo.
+pub const ERROR_DOMAIN: &str = "bank";
+
+/// Тип ErrorInfo в деталях ответа (совместим с google.rpc.ErrorInfo).
+pub const ERROR_INFO_TYPE: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+/// gRPC код ошибки сервиса.
+fn code(err: &AppError) -> Code {
+    match err {
+        AppError::AccountNotExists(_)
+        | AppError::TransactionNotExists(_, _)
+        | AppError::OperationNotExists(_) => Code::NotFound,
+        AppError::AccountNameExists(_) => Code::AlreadyExists,
+        AppError::InvalidRequest(_)
+        | AppError::PayloadTooLarge(_)
+        | AppError::UnsupportedMediaType(_)
+        | AppError::InvalidBody(_, _)
+        | AppError::InvalidAccountName(_)
+        | AppError::InvalidReference(_)
+        | AppError::InvalidIdempotencyKey(_)
+        | AppError::InvalidHistoryQuery(_)
+        | AppError::ZeroValueTransaction
+        | AppError::InvalidAmount(_)
+        | AppError::SelfTransfer => Code::InvalidArgument,
+        AppError::Overdraft
+        | AppError::AccountClosed(_)
+        | AppError::AccountFrozen(_)
+        | AppError::NonZeroBalance(_)
+        | AppError::StatusUnchanged(_, _)
+        | AppError::SystemAccount(_)
+        | AppError::IdempotencyKeyReused(_)
+        | AppError::EmptyDb
+        | AppError::EmptyBackupFile
+        | AppError::InvalidBackupFile
+        | AppError::BackupLoadFile => Code::FailedPrecondition,
+        AppError::BalanceOverflow => Code::OutOfRange,
+        AppError::ReplayMismatch(_) => Code::DataLoss,
+        AppError::StorageFailure(_) => Code::Internal,
+        AppError::Recovering | AppError::StorageUnavailable(_) => Code::Unavailable,
+    }
+}
+
+/// Перекладка ошибки сервиса в gRPC статус с деталями ErrorInfo.
+impl From<AppError> for Status {
+    fn from(err: AppError) -> Self {
+        let code: Code = code(&err);
+        let message: String = err.to_string();
+        // машиночитаемые детали ошибки
+        let info = proto::ErrorInfo {
+            reason: err.reason().to_string(),
+            domain: ERROR_DOMAIN.to_string(),
+            metadata: err
+                .metadata()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        };
+        let details = proto::ErrorStatus {
+            code: code as i32,
+            message: message.clone(),
+            details: vec![prost_types::Any {
+                type_url: ERROR_INFO_TYPE.to_string(),
+                value: info.encode_to_vec(),
+            }],
+        };
+
+        Status::with_details(code, message, Bytes::from(details.encode_to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_code_follows_error() {
+        let cases: Vec<(AppError, Code)> = vec![
+            (AppError::AccountNotExists("1".to_string()), Code::NotFound),
+            (
+                AppError::AccountNameExists("savings".to_string()),
+                Code::AlreadyExists,
+            ),
+            (AppError::SelfTransfer, Code::InvalidArgument),
+            (AppError::Overdraft, Code::FailedPrecondition),
+            (
+                AppError::SystemAccount("1".to_string()),
+                Code::FailedPrecondition,
+            ),
+            (AppError::BalanceOverflow, Code::OutOfRange),
+            (AppError::ReplayMismatch(String::new()), Code::DataLoss),
+            (AppError::Recovering, Code::Unavailable),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(Status::from(err).code(), expected);
+        }
+    }
+
+    #[test]
+    fn details_carry_error_info() {
+        let status: Status = AppError::AccountFrozen("savings".to_string()).into();
+
+        let details = proto::ErrorStatus::decode(status.details()).unwrap();
+        assert_eq!(details.code, Code::FailedPrecondition as i32);
+        assert_eq!(details.message, "account: 'savings' is frozen");
+        assert_eq!(details.details[0].type_url, ERROR_INFO_TYPE);
+        let info = proto::ErrorInfo::decode(details.details[0].value.as_slice()).unwrap();
+        assert_eq!(info.reason, "ACCOUNT_FROZEN");
+        assert_eq!(info.domain, ERROR_DOMAIN);
+        assert_eq!(info.metadata["account"], "savings");
+    }
+}
@@ -1,19 +1,324 @@
+mod error;
+
 use crate::adapter::router::grpc::proto::bank_server::Bank;
-use crate::adapter::storage::StorageState;
-use crate::domain::entities::account::{Account, BalanceResponse};
+use crate::adapter::router::grpc::proto::CommandType;
+use crate::adapter::storage::{StorageState, Storages};
+use crate::domain::entities::account::{Account, AccountKey, BalanceResponse, StatusResponse};
+use crate::domain::entities::health::{HealthResponse, HealthStatus};
+use crate::domain::entities::history::{HistoryEntry, HistoryPage, HistoryQuery, Order};
+use crate::domain::entities::idempotency::{IDEMPOTENCY_HEADER, IDEMPOTENCY_KEY_MAX_LEN};
+use crate::domain::entities::journal::{Command, Record, ReplayResponse};
+use crate::domain::entities::money::Money;
 use crate::domain::entities::transaction::Operation::{Replenish, Withdraw};
 use crate::domain::entities::transaction::{
-    Transaction, TransactionResponse, TransferRequest, TransferResponse,
+    Operation, Transaction, TransactionResponse, TransferRequest, TransferResponse,
+};
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::{
+    InvalidAmount, InvalidHistoryQuery, InvalidIdempotencyKey, InvalidRequest,
 };
 use crate::domain::usecases;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use tonic::metadata::MetadataMap;
 use tonic::{Request, Response, Status};
 
 /// сгенерированный gRPC-код.
+#[allow(clippy::enum_variant_names)]
 pub mod proto {
     tonic::include_proto!("bank");
 }
 
+/// Перекладка денежной суммы в gRPC формат.
+impl From<Money> for proto::Money {
+    fn from(value: Money) -> Self {
+        Self {
+            units: value.minor(),
+            scale: Money::SCALE,
+        }
+    }
+}
+
+/// Перекладка времени в gRPC формат.
+fn timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+/// Перекладка ответа совершенной транзакции в gRPC формат.
+impl From<TransactionResponse> for proto::TransactionResponse {
+    fn from(tx: TransactionResponse) -> Self {
+        Self {
+            account_id: tx.account_id,
+            transaction_id: tx.transaction_id,
+            balance_amount: Some(tx.balance.into()),
+            operation_id: tx.operation_id,
+            fee: Some(tx.fee.into()),
+        }
+    }
+}
+
+/// Перекладка деталей перевода в gRPC формат.
+impl From<TransferRequest> for proto::TransferRequest {
+    fn from(req: TransferRequest) -> Self {
+        let (account_from, account_from_name) = account_ref(req.account_from);
+        let (account_to, account_to_name) = account_ref(req.account_to);
+        Self {
+            account_from,
+            account_to,
+            transfer_amount: Some(req.transfer_value.into()),
+            account_from_name,
+            account_to_name,
+            reference: req.reference,
+        }
+    }
+}
+
+/// Перекладка записи журнала операций в gRPC формат.
+impl From<Record> for proto::Record {
+    fn from(record: Record) -> Self {
+        let mut name: Option<String> = None;
+        let mut fee: Money = Money::ZERO;
+        let mut reference: Option<String> = None;
+        let (command, account_id, account_to, value) = match record.command {
+            Command::NewAccount {
+                account_id,
+                name: acc_name,
+            } => {
+                name = acc_name;
+                (
+                    CommandType::NewAccountCommand,
+                    account_id,
+                    None,
+                    Money::ZERO,
+                )
+            }
+            Command::Replenish {
+                account_id,
+                value,
+                fee: op_fee,
+                reference: op_reference,
+            } => {
+                fee = op_fee;
+                reference = op_reference;
+                (CommandType::ReplenishCommand, account_id, None, value)
+            }
+            Command::Withdraw {
+                account_id,
+                value,
+                fee: op_fee,
+                reference: op_reference,
+            } => {
+                fee = op_fee;
+                reference = op_reference;
+                (CommandType::WithdrawCommand, account_id, None, value)
+            }
+            Command::Transfer {
+                account_from,
+                account_to,
+                value,
+                fee: op_fee,
+                reference: op_reference,
+            } => {
+                fee = op_fee;
+                reference = op_reference;
+                (
+                    CommandType::TransferCommand,
+                    account_from,
+                    Some(account_to),
+                    value,
+                )
+            }
+            Command::Close { account_id } => {
+                (CommandType::CloseCommand, account_id, None, Money::ZERO)
+            }
+            Command::Freeze { account_id } => {
+                (CommandType::FreezeCommand, account_id, None, Money::ZERO)
+            }
+            Command::Reopen { account_id } => {
+                (CommandType::ReopenCommand, account_id, None, Money::ZERO)
+            }
+        };
+        Self {
+            id: record.id,
+            command: command as i32,
+            account_id,
+            account_to,
+            value: Some(value.into()),
+            transactions: record
+                .transactions
+                .into_iter()
+                .map(|tx| proto::Trans {
+                    account_id: tx.account_id,
+                    transaction_id: tx.transaction_id,
+                    account_name: None,
+                })
+                .collect(),
+            name,
+            fee: Some(fee.into()),
+            timestamp: Some(timestamp(record.timestamp)),
+            reference,
+        }
+    }
+}
+
+/// Перекладка транзакции в gRPC формат.
+impl From<Transaction> for proto::Transaction {
+    fn from(tx: Transaction) -> Self {
+        Self {
+            id: tx.id,
+            operation: tx.operation as i32,
+            previous_amount: Some(tx.previous.into()),
+            delta_amount: Some(tx.delta.into()),
+            current_amount: Some(tx.current.into()),
+            timestamp: Some(timestamp(tx.timestamp)),
+            reference: tx.reference,
+            counterparty: tx.counterparty,
+        }
+    }
+}
+
+/// Перекладка страницы истории в gRPC формат.
+impl From<HistoryPage> for proto::HistoryPage {
+    fn from(page: HistoryPage) -> Self {
+        Self {
+            transactions: page
+                .transactions
+                .into_iter()
+                .map(|entry: HistoryEntry| proto::HistoryEntry {
+                    account_id: entry.account_id,
+                    transaction: Some(entry.transaction.into()),
+                })
+                .collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+/// Перекладка счета в gRPC формат.
+impl From<Account> for proto::AccountResponse {
+    fn from(acc: Account) -> Self {
+        Self {
+            id: acc.id,
+            status: acc.status as i32,
+            balance_amount: Some(acc.balance.into()),
+            transaction: acc.transactions.into_iter().map(Into::into).collect(),
+            name: acc.name,
+        }
+    }
+}
+
+/// Перекладка ответа изменения статуса счета в gRPC формат.
+impl From<StatusResponse> for proto::StatusResponse {
+    fn from(resp: StatusResponse) -> Self {
+        Self {
+            account_id: resp.account_id,
+            status: resp.status as i32,
+            operation_id: resp.operation_id,
+        }
+    }
+}
+
+/// Парсинг денежной суммы из gRPC запроса.
+fn money(value: Option<&proto::Money>) -> Result<Money, AppError> {
+    let value: proto::Money = value.cloned().unwrap_or_default();
+    Money::from_scaled(value.units, value.scale)
+        .ok_or_else(|| InvalidAmount(format!("{}e-{}", value.units, value.scale)))
+}
+
+/// Время из gRPC запроса.
+fn time(value: Option<&prost_types::Timestamp>) -> Result<Option<DateTime<Utc>>, AppError> {
+    value
+        .map(|ts| {
+            u32::try_from(ts.nanos)
+                .ok()
+                .and_then(|nanos| DateTime::from_timestamp(ts.seconds, nanos))
+                .ok_or_else(|| InvalidRequest(format!("invalid timestamp: {}", ts)))
+        })
+        .transpose()
+}
+
+/// Тип операции из gRPC запроса.
+fn operation(value: i32) -> Result<Operation, AppError> {
+    match proto::Operation::try_from(value) {
+        Ok(proto::Operation::Registration) => Ok(Operation::Registration),
+        Ok(proto::Operation::Replenish) => Ok(Operation::Replenish),
+        Ok(proto::Operation::Withdraw) => Ok(Operation::Withdraw),
+        Ok(proto::Operation::TransferIncrease) => Ok(Operation::TransferIncrease),
+        Ok(proto::Operation::TransferDecrease) => Ok(Operation::TransferDecrease),
+        Ok(proto::Operation::Commission) => Ok(Operation::Commission),
+        Ok(proto::Operation::CommissionIncome) => Ok(Operation::CommissionIncome),
+        Err(_) => Err(InvalidHistoryQuery(format!("unknown operation: {}", value))),
+    }
+}
+
+/// Параметры страницы истории из gRPC запроса.
+fn history_query(input: Option<&proto::HistoryQuery>) -> Result<HistoryQuery, AppError> {
+    let input: proto::HistoryQuery = input.cloned().unwrap_or_default();
+    let order: Order = match proto::Order::try_from(input.order) {
+        Ok(proto::Order::Asc) => Order::Asc,
+        Ok(proto::Order::Desc) => Order::Desc,
+        Err(_) => {
+            return Err(InvalidHistoryQuery(format!(
+                "unknown order: {}",
+                input.order
+            )))
+        }
+    };
+
+    Ok(HistoryQuery {
+        cursor: input.cursor,
+        limit: input.limit.map(|limit| limit as usize),
+        operation: input.operation.map(operation).transpose()?,
+        from_id: input.from_id,
+        to_id: input.to_id,
+        min_amount: input
+            .min_amount
+            .as_ref()
+            .map(|value| money(Some(value)))
+            .transpose()?,
+        max_amount: input
+            .max_amount
+            .as_ref()
+            .map(|value| money(Some(value)))
+            .transpose()?,
+        from_time: time(input.from_time.as_ref())?,
+        to_time: time(input.to_time.as_ref())?,
+        order,
+    })
+}
+
+/// Ключ идемпотентности из метаданных gRPC запроса.
+fn idempotency_key(metadata: &MetadataMap) -> Result<Option<String>, AppError> {
+    metadata
+        .get(IDEMPOTENCY_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| InvalidIdempotencyKey(IDEMPOTENCY_KEY_MAX_LEN))
+        })
+        .transpose()
+}
+
+/// Ключ поиска счета из gRPC запроса: имя, если задано, иначе - id.
+fn account_key(account_id: u32, name: Option<&String>) -> AccountKey {
+    match name {
+        Some(name) => AccountKey::Name(name.clone()),
+        None => AccountKey::Id(account_id),
+    }
+}
+
+/// Перекладка ключа счета в пару (id, имя) gRPC формата.
+fn account_ref(key: AccountKey) -> (u32, Option<String>) {
+    match key {
+        AccountKey::Id(id) => (id, None),
+        AccountKey::Name(name) => (0, Some(name)),
+    }
+}
+
 /// gRPC приложение.
 #[derive(Debug, Default)]
 pub struct BankService {
@@ -26,16 +331,17 @@ impl Bank for BankService {
     /// Создание нового счета.
     async fn new_account(
         &self,
-        _: Request<()>,
+        request: Request<proto::NewAccountRequest>,
     ) -> Result<Response<proto::TransactionResponse>, Status> {
+        // запрос
+        let input: proto::NewAccountRequest = request.into_inner();
         // создание счета
-        let tx: TransactionResponse = usecases::account::new_account(self.state.clone());
+        let tx: TransactionResponse = self
+            .state
+            .blocking(move |state| usecases::account::new_account(state, input.name))
+            .await?;
         // gRPC ответ
-        let resp = proto::TransactionResponse {
-            account_id: tx.account_id,
-            transaction_id: tx.transaction_id,
-            balance: tx.balance as f32,
-        };
+        let resp: proto::TransactionResponse = tx.into();
 
         Ok(Response::new(resp))
     }
@@ -47,22 +353,22 @@ impl Bank for BankService {
     ) -> Result<Response<proto::TransactionResponse>, Status> {
         // запрос
         let input: &proto::TransactionRequest = request.get_ref();
+        // сумма транзакции
+        let value: Money = money(input.transaction_amount.as_ref())?;
         // изменение баланса счета
-        let tx: TransactionResponse = match usecases::account::change_acc_balance(
-            &self.state,
-            input.transaction_value as f64,
-            input.account_id,
-            Replenish,
-        ) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
+        let account: AccountKey = account_key(input.account_id, input.account_name.as_ref());
+        let reference: Option<String> = input.reference.clone();
+        let key: Option<String> = idempotency_key(request.metadata())?;
+        let tx: TransactionResponse = self
+            .state
+            .blocking(move |state| {
+                usecases::account::change_acc_balance(
+                    &state, value, account, Replenish, reference, key,
+                )
+            })
+            .await?;
         // gRPC ответ
-        let resp = proto::TransactionResponse {
-            account_id: tx.account_id,
-            transaction_id: tx.transaction_id,
-            balance: tx.balance as f32,
-        };
+        let resp: proto::TransactionResponse = tx.into();
 
         Ok(Response::new(resp))
     }
@@ -74,22 +380,22 @@ impl Bank for BankService {
     ) -> Result<Response<proto::TransactionResponse>, Status> {
         // запрос
         let input: &proto::TransactionRequest = request.get_ref();
+        // сумма транзакции
+        let value: Money = money(input.transaction_amount.as_ref())?;
         // изменение баланса счета
-        let tx: TransactionResponse = match usecases::account::change_acc_balance(
-            &self.state,
-            input.transaction_value as f64,
-            input.account_id,
-            Withdraw,
-        ) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
+        let account: AccountKey = account_key(input.account_id, input.account_name.as_ref());
+        let reference: Option<String> = input.reference.clone();
+        let key: Option<String> = idempotency_key(request.metadata())?;
+        let tx: TransactionResponse = self
+            .state
+            .blocking(move |state| {
+                usecases::account::change_acc_balance(
+                    &state, value, account, Withdraw, reference, key,
+                )
+            })
+            .await?;
         // gRPC ответ
-        let resp = proto::TransactionResponse {
-            account_id: tx.account_id,
-            transaction_id: tx.transaction_id,
-            balance: tx.balance as f32,
-        };
+        let resp: proto::TransactionResponse = tx.into();
 
         Ok(Response::new(resp))
     }
@@ -103,23 +409,24 @@ impl Bank for BankService {
         let input: &proto::TransferRequest = request.get_ref();
         // перекладка в schema
         let req = TransferRequest {
-            account_from: input.account_from,
-            account_to: input.account_to,
-            transfer_value: input.transfer_value as f64,
+            account_from: account_key(input.account_from, input.account_from_name.as_ref()),
+            account_to: account_key(input.account_to, input.account_to_name.as_ref()),
+            transfer_value: money(input.transfer_amount.as_ref())?,
+            reference: input.reference.clone(),
         };
         // перевод
-        let tx: TransferResponse = match usecases::account::transfer(&self.state, req) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
+        // ключ идемпотентности
+        let key: Option<String> = idempotency_key(request.metadata())?;
+        let tx: TransferResponse = self
+            .state
+            .blocking(move |state| usecases::account::transfer(&state, req, key))
+            .await?;
         // gRPC ответ
         let resp = proto::TransferResponse {
             info: tx.info,
-            details: Some(proto::TransferRequest {
-                account_from: tx.details.account_from,
-                account_to: tx.details.account_to,
-                transfer_value: tx.details.transfer_value as f32,
-            }),
+            details: Some(tx.details.into()),
+            operation_id: tx.operation_id,
+            fee: Some(tx.fee.into()),
         };
 
         Ok(Response::new(resp))
@@ -128,18 +435,22 @@ impl Bank for BankService {
     /// Баланс счета.
     async fn balance(
         &self,
-        request: Request<proto::AccountId>,
+        request: Request<proto::AccountQuery>,
     ) -> Result<Response<proto::BalanceResponse>, Status> {
         // запрос
-        let input: &proto::AccountId = request.get_ref();
+        let input: &proto::AccountQuery = request.get_ref();
+        // ключ счета
+        let account: AccountKey = account_key(input.account_id, input.name.as_ref());
+        // момент времени
+        let as_of: Option<DateTime<Utc>> = time(input.as_of.as_ref())?;
         // баланс
-        let tx: BalanceResponse = match usecases::account::balance(&self.state, input.account_id) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
+        let tx: BalanceResponse = self
+            .state
+            .blocking(move |state| usecases::account::balance(&state, account, as_of))
+            .await?;
         // gRPC ответ
         let resp = proto::BalanceResponse {
-            balance: tx.balance as f32,
+            balance_amount: Some(tx.balance.into()),
         };
 
         Ok(Response::new(resp))
@@ -148,39 +459,79 @@ impl Bank for BankService {
     /// Получение счета.
     async fn account(
         &self,
-        request: Request<proto::AccountId>,
+        request: Request<proto::AccountQuery>,
     ) -> Result<Response<proto::AccountResponse>, Status> {
         // запрос
-        let input: &proto::AccountId = request.get_ref();
-        // баланс
-        let tx: Account = match usecases::account::account(&self.state, input.account_id) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
-        // вектор для переладки в proto::Transaction
-        let mut grpc_tx_vec: Vec<proto::Transaction> = Vec::with_capacity(tx.transactions.len());
-        // наполнение вектора
-        for trans in tx.transactions {
-            let t = proto::Transaction {
-                id: trans.id,
-                operation: trans.operation as i32,
-                previous: trans.previous as f32,
-                delta: trans.delta as f32,
-                current: trans.current as f32,
-            };
-            grpc_tx_vec.push(t)
-        }
+        let input: &proto::AccountQuery = request.get_ref();
+        // ключ счета
+        let account: AccountKey = account_key(input.account_id, input.name.as_ref());
+        // момент времени
+        let as_of: Option<DateTime<Utc>> = time(input.as_of.as_ref())?;
+        // счет
+        let tx: Account = self
+            .state
+            .blocking(move |state| usecases::account::account(&state, account, as_of))
+            .await?;
         // gRPC ответ
-        let resp = proto::AccountResponse {
-            id: tx.id,
-            status: tx.status as i32,
-            balance: tx.balance as f32,
-            transaction: grpc_tx_vec,
-        };
+        let resp: proto::AccountResponse = tx.into();
 
         Ok(Response::new(resp))
     }
 
+    /// Закрытие счета.
+    async fn close(
+        &self,
+        request: Request<proto::AccountId>,
+    ) -> Result<Response<proto::StatusResponse>, Status> {
+        // запрос
+        let input: &proto::AccountId = request.get_ref();
+        // ключ счета
+        let account: AccountKey = account_key(input.account_id, input.name.as_ref());
+        // изменение статуса счета
+        let resp: StatusResponse = self
+            .state
+            .blocking(move |state| usecases::account::close(&state, account))
+            .await?;
+
+        Ok(Response::new(resp.into()))
+    }
+
+    /// Заморозка счета.
+    async fn freeze(
+        &self,
+        request: Request<proto::AccountId>,
+    ) -> Result<Response<proto::StatusResponse>, Status> {
+        // запрос
+        let input: &proto::AccountId = request.get_ref();
+        // ключ счета
+        let account: AccountKey = account_key(input.account_id, input.name.as_ref());
+        // изменение статуса счета
+        let resp: StatusResponse = self
+            .state
+            .blocking(move |state| usecases::account::freeze(&state, account))
+            .await?;
+
+        Ok(Response::new(resp.into()))
+    }
+
+    /// Повторное открытие (разморозка) счета.
+    async fn reopen(
+        &self,
+        request: Request<proto::AccountId>,
+    ) -> Result<Response<proto::StatusResponse>, Status> {
+        // запрос
+        let input: &proto::AccountId = request.get_ref();
+        // ключ счета
+        let account: AccountKey = account_key(input.account_id, input.name.as_ref());
+        // изменение статуса счета
+        let resp: StatusResponse = self
+            .state
+            .blocking(move |state| usecases::account::reopen(&state, account))
+            .await?;
+
+        Ok(Response::new(resp.into()))
+    }
+
     /// Получение транзакции по id.
     async fn get_transaction(
         &self,
@@ -189,67 +540,221 @@ impl Bank for BankService {
         // запрос
         let input: &proto::Trans = request.get_ref();
         // транзакция
-        let tx: Transaction = match usecases::transaction::transaction(
-            &self.state,
-            input.account_id,
-            input.transaction_id,
-        ) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
+        let account: AccountKey = account_key(input.account_id, input.account_name.as_ref());
+        let transaction_id: u32 = input.transaction_id;
+        let tx: Transaction = self
+            .state
+            .blocking(move |state| {
+                usecases::transaction::transaction(&state, account, transaction_id)
+            })
+            .await?;
         // gRPC ответ
-        let resp = proto::Transaction {
-            id: tx.id,
-            operation: tx.operation as i32,
-            previous: tx.previous as f32,
-            delta: tx.delta as f32,
-            current: tx.current as f32,
-        };
+        let resp: proto::Transaction = tx.into();
 
         Ok(Response::new(resp))
     }
 
     /// получение всех транзакций бд в разбивке по счетам.
     async fn history(&self, _: Request<()>) -> Result<Response<proto::HistoryResponse>, Status> {
-        let db = match usecases::storage::history(&self.state) {
-            Ok(tx) => tx,
-            Err(err) => return Err(Status::aborted(err.to_string())),
-        };
+        let db = self
+            .state
+            .blocking(|state| usecases::storage::history(&state))
+            .await?;
         // мапа для перекладки в grpc формат
         let mut grpc_map = HashMap::<u32, proto::AccountResponse>::new();
 
         for (acc_id, acc_val) in db {
-            // вектор для переладки в proto::Transaction
-            let mut grpc_tx_vec: Vec<proto::Transaction> =
-                Vec::with_capacity(acc_val.transactions.len());
-            // наполнение вектора
-            for trans in acc_val.transactions {
-                let t = proto::Transaction {
-                    id: trans.id,
-                    operation: trans.operation as i32,
-                    previous: trans.previous as f32,
-                    delta: trans.delta as f32,
-                    current: trans.current as f32,
-                };
-                grpc_tx_vec.push(t)
-            }
-            // перекладка счета
-            let grpc_acc = proto::AccountResponse {
-                id: acc_val.id,
-                status: acc_val.status as i32,
-                balance: acc_val.balance as f32,
-                transaction: grpc_tx_vec,
-            };
-            // добавление в grpc map
-            grpc_map.insert(acc_id, grpc_acc);
+            // перекладка счета и добавление в grpc map
+            grpc_map.insert(acc_id, acc_val.into());
         }
 
         Ok(Response::new(proto::HistoryResponse { response: grpc_map }))
     }
 
+    /// Страница истории счета.
+    async fn account_history(
+        &self,
+        request: Request<proto::AccountHistoryRequest>,
+    ) -> Result<Response<proto::HistoryPage>, Status> {
+        // запрос
+        let input: &proto::AccountHistoryRequest = request.get_ref();
+        // ключ счета
+        let account_id: proto::AccountId = input.account.clone().unwrap_or_default();
+        let account: AccountKey = account_key(account_id.account_id, account_id.name.as_ref());
+        // фильтры и курсор
+        let query: HistoryQuery = history_query(input.query.as_ref())?;
+        // страница истории
+        let page: HistoryPage = self
+            .state
+            .blocking(move |state| usecases::history::account_history(&state, account, query))
+            .await?;
+
+        Ok(Response::new(page.into()))
+    }
+
+    /// Страница истории всех счетов.
+    async fn transactions(
+        &self,
+        request: Request<proto::HistoryQuery>,
+    ) -> Result<Response<proto::HistoryPage>, Status> {
+        // фильтры и курсор
+        let query: HistoryQuery = history_query(Some(request.get_ref()))?;
+        // страница истории
+        let page: HistoryPage = self
+            .state
+            .blocking(move |state| usecases::history::transactions(&state, query))
+            .await?;
+
+        Ok(Response::new(page.into()))
+    }
+
+    /// Получение журнала всех операций в порядке выполнения.
+    async fn journal(&self, _: Request<()>) -> Result<Response<proto::JournalResponse>, Status> {
+        // журнал
+        let journal: Vec<Record> = self
+            .state
+            .blocking(|state| usecases::journal::journal(&state))
+            .await?;
+        // gRPC ответ
+        let resp = proto::JournalResponse {
+            records: journal.into_iter().map(Into::into).collect(),
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    /// Получение операции по id.
+    async fn get_operation(
+        &self,
+        request: Request<proto::OperationId>,
+    ) -> Result<Response<proto::Record>, Status> {
+        // запрос
+        let input: &proto::OperationId = request.get_ref();
+        // операция
+        let operation_id: u64 = input.operation_id;
+        let record: Record = self
+            .state
+            .blocking(move |state| usecases::journal::operation(&state, operation_id))
+            .await?;
+
+        Ok(Response::new(record.into()))
+    }
+
+    /// Получение операций счета в порядке выполнения.
+    async fn account_journal(
+        &self,
+        request: Request<proto::AccountId>,
+    ) -> Result<Response<proto::JournalResponse>, Status> {
+        // запрос
+        let input: &proto::AccountId = request.get_ref();
+        // ключ счета
+        let account: AccountKey = account_key(input.account_id, input.name.as_ref());
+        // операции счета
+        let journal: Vec<Record> = self
+            .state
+            .blocking(move |state| usecases::journal::account_journal(&state, account))
+            .await?;
+        // gRPC ответ
+        let resp = proto::JournalResponse {
+            records: journal.into_iter().map(Into::into).collect(),
+        };
+
+        Ok(Response::new(resp))
+    }
+
     /// Восстановление бд
     async fn backup(&self, _: Request<()>) -> Result<Response<()>, Status> {
-        usecases::storage::backup(&self.state).unwrap();
+        self.state
+            .blocking(|state| usecases::storage::backup(&state))
+            .await?;
         Ok(Response::new(()))
     }
+
+    /// Восстановление бд по журналу операций в новом экземпляре банка и сверка с текущей бд.
+    async fn replay(&self, _: Request<()>) -> Result<Response<proto::ReplayResponse>, Status> {
+        let report: ReplayResponse = self
+            .state
+            .blocking(|state| usecases::replay::rebuild(&state))
+            .await?;
+        // gRPC ответ
+        let resp = proto::ReplayResponse {
+            operations: report.operations as u64,
+            accounts: report.accounts as u64,
+            transactions: report.transactions as u64,
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    /// Состояние сервиса: бд работает, восстанавливается при старте или закрыта после сбоя.
+    async fn health(&self, _: Request<()>) -> Result<Response<proto::HealthResponse>, Status> {
+        let health: HealthResponse = self
+            .state
+            .blocking(|state| Ok(usecases::storage::health(&state)))
+            .await?;
+        // gRPC ответ
+        let status: proto::HealthStatus = match health.status {
+            HealthStatus::Ok => proto::HealthStatus::Ok,
+            HealthStatus::Recovering => proto::HealthStatus::Recovering,
+            HealthStatus::Failed => proto::HealthStatus::Failed,
+        };
+        let recovery = health.recovery.map(|report| proto::RecoveryReport {
+            accounts: report.accounts as u64,
+            transactions: report.transactions as u64,
+            operations: report.operations as u64,
+            elapsed_ms: report.elapsed_ms,
+        });
+        let resp = proto::HealthResponse {
+            status: status.into(),
+            reason: health.reason,
+            recovery,
+        };
+
+        Ok(Response::new(resp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    /// Запрос пополнения прежней версии с суммой float.
+    #[derive(Clone, PartialEq, Message)]
+    struct FloatTransactionRequest {
+        #[prost(uint32, tag = "1")]
+        account_id: u32,
+        #[prost(float, tag = "2")]
+        transaction_value: f32,
+    }
+
+    /// Ответ баланса прежней версии с суммой float.
+    #[derive(Clone, PartialEq, Message)]
+    struct FloatBalanceResponse {
+        #[prost(float, tag = "1")]
+        balance: f32,
+    }
+
+    #[test]
+    fn float_amounts_are_not_misread() {
+        // запрос старого клиента: суммы нет, пополнение на 0 отклоняется
+        let legacy = FloatTransactionRequest {
+            account_id: 1,
+            transaction_value: 12.5,
+        };
+        let request = proto::TransactionRequest::decode(legacy.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(request.account_id, 1);
+        assert_eq!(request.transaction_amount, None);
+        assert_eq!(
+            money(request.transaction_amount.as_ref()).unwrap(),
+            Money::ZERO
+        );
+
+        // ответ для старого клиента: баланс не читается из байтов Money
+        let response = proto::BalanceResponse {
+            balance_amount: Some(Money::from_minor(1250).into()),
+        };
+        let legacy = FloatBalanceResponse::decode(response.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(legacy.balance, 0.0);
+    }
 }
@@ -1,152 +1,413 @@
-use crate::adapter::storage::StorageState;
-use crate::domain::entities::account::{Account, BalanceResponse};
+use crate::adapter::router::http::handlers::{JsonBody, OptionalJson, Unavailable};
+use crate::adapter::storage::{StorageState, Storages};
+use crate::domain::entities::account::{
+    Account, AccountKey, AsOfQuery, BalanceResponse, NewAccountRequest, StatusResponse,
+};
+use crate::domain::entities::history::{HistoryPage, HistoryQuery};
+use crate::domain::entities::idempotency::{IDEMPOTENCY_HEADER, IDEMPOTENCY_KEY_MAX_LEN};
 use crate::domain::entities::transaction::{
     Operation::{Replenish, Withdraw},
-    TransactionRequest, TransactionResponse, TransferRequest, TransferResponse,
+    TransactionRequest, TransactionResponse, TransferRequest, TransferResponse, REFERENCE_MAX_LEN,
 };
 use crate::domain::errors::AppError::{
-    AccountNotExists, Overdraft, SelfTransfer, ZeroValueTransaction,
+    AccountClosed, AccountFrozen, AccountNameExists, AccountNotExists, BalanceOverflow,
+    IdempotencyKeyReused, InvalidAccountName, InvalidBody, InvalidHistoryQuery,
+    InvalidIdempotencyKey, InvalidReference, NonZeroBalance, Overdraft, SelfTransfer,
+    StatusUnchanged, StorageFailure, SystemAccount, UnsupportedMediaType, ZeroValueTransaction,
 };
 use crate::domain::errors::{AppError, Result};
 use crate::domain::usecases;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use axum::Json;
 
 #[utoipa::path(
 post,
 path = "/account/new",
+request_body(content = Option<NewAccountRequest>, description = "Optional unique account name"),
 responses(
 (status = 200, description = "Account created successfully", body = TransactionResponse),
+Unavailable,
+(status = 400, description = "Invalid account name or malformed request body", body = ErrorResponse, example = json!(
+InvalidAccountName(String::from("42")).example()
+)),
+(status = 409, description = "Account name already exists", body = ErrorResponse, example = json!(
+AccountNameExists(String::from("savings")).example()
+)),
+(status = 415, description = "Request body is not json", body = ErrorResponse, example = json!(
+UnsupportedMediaType(String::from("expected request with `Content-Type: application/json`")).example()
+)),
+(status = 422, description = "Request body does not match the schema", body = ErrorResponse, example = json!(
+InvalidBody(Some(String::from("name")), String::from("invalid type: integer `123`, expected a string")).example()
+)),
+(status = 500, description = "Storage error", body = ErrorResponse, example = json!(
+StorageFailure(String::from("database is locked")).example()
+)),
 )
 )]
 /// Создание нового счета
-pub async fn new_account(State(state): State<StorageState>) -> Json<TransactionResponse> {
-    Json(usecases::account::new_account(state))
+pub async fn new_account(
+    State(state): State<StorageState>,
+    OptionalJson(payload): OptionalJson<NewAccountRequest>,
+) -> Result<Json<TransactionResponse>, AppError> {
+    // имя счета (тело запроса необязательно)
+    let name: Option<String> = payload.and_then(|req| req.name);
+
+    state
+        .blocking(move |state| usecases::account::new_account(state, name))
+        .await
+        .map(Json)
 }
 
 #[utoipa::path(
 post,
 path = "/account/replenish",
 request_body = TransactionRequest,
+params(
+("Idempotency-Key" = Option<String>, Header, description = "Repeated key within the window returns the original response")
+),
 responses(
 (status = 200, description = "Account replenished successfully", body = TransactionResponse),
-(status = 400, description = "Errors", body = AppError, example = json!(
+Unavailable,
+(status = 400, description = "Invalid request", body = ErrorResponse, example = json!(
 [
-{"error1": ZeroValueTransaction.to_string()},
-{"error2": Overdraft.to_string()}
+ZeroValueTransaction.example(),
+InvalidReference(REFERENCE_MAX_LEN).example(),
+InvalidIdempotencyKey(IDEMPOTENCY_KEY_MAX_LEN).example()
 ]
 )),
-(status = 404, description = "Account not found", body = AppError, example = json!(
-{"error": AccountNotExists(String::from("account_№n")).to_string()}
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example()
+)),
+(status = 409, description = "Account is frozen or closed", body = ErrorResponse, example = json!(
+[
+AccountFrozen(String::from("account_№n")).example(),
+AccountClosed(String::from("account_№n")).example()
+]
+)),
+(status = 415, description = "Request body is not json", body = ErrorResponse, example = json!(
+UnsupportedMediaType(String::from("expected request with `Content-Type: application/json`")).example()
+)),
+(status = 422, description = "Operation rejected or request body does not match the schema", body = ErrorResponse, example = json!(
+[
+InvalidBody(Some(String::from("transaction_value")), String::from("missing field `transaction_value`")).example(),
+Overdraft.example(),
+BalanceOverflow.example(),
+IdempotencyKeyReused(String::from("key")).example()
+]
 )),
 ))]
 /// Пополнение счета
 pub async fn replenish(
     State(state): State<StorageState>,
-    Json(payload): Json<TransactionRequest>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<TransactionRequest>,
 ) -> Result<Json<TransactionResponse>, AppError> {
-    let res: Result<Json<TransactionResponse>, AppError> = usecases::account::change_acc_balance(
-        &state,
-        payload.transaction_value,
-        payload.account_id,
-        Replenish,
-    )
-    .map(Json);
-    // для себя, запомнить
-    // map применяет функцию (экстактор Json в данном случае) к OK значению из change_acc_balance
-
-    res
+    let key: Option<String> = idempotency_key(&headers)?;
+    state
+        .blocking(move |state| {
+            usecases::account::change_acc_balance(
+                &state,
+                payload.transaction_value,
+                payload.account_id,
+                Replenish,
+                payload.reference,
+                key,
+            )
+        })
+        .await
+        .map(Json)
 }
 
 #[utoipa::path(
 post,
 path = "/account/withdraw",
 request_body = TransactionRequest,
+params(
+("Idempotency-Key" = Option<String>, Header, description = "Repeated key within the window returns the original response")
+),
 responses(
 (status = 200, description = "Account withdrawed successfully", body = TransactionResponse),
-(status = 400, description = "Errors", body = AppError, example = json ! (
+Unavailable,
+(status = 400, description = "Invalid request", body = ErrorResponse, example = json!(
 [
-{"error1": ZeroValueTransaction.to_string()},
-{"error2": Overdraft.to_string()}
+ZeroValueTransaction.example(),
+InvalidReference(REFERENCE_MAX_LEN).example(),
+InvalidIdempotencyKey(IDEMPOTENCY_KEY_MAX_LEN).example()
 ]
 )),
-(status = 404, description = "Account not found", body = AppError, example = json!(
-{"error": AccountNotExists(String::from("account_№n")).to_string()}
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example()
+)),
+(status = 409, description = "Account is frozen, closed or a system account", body = ErrorResponse, example = json!(
+[
+AccountFrozen(String::from("account_№n")).example(),
+AccountClosed(String::from("account_№n")).example(),
+SystemAccount(String::from("account_№n")).example()
+]
+)),
+(status = 415, description = "Request body is not json", body = ErrorResponse, example = json!(
+UnsupportedMediaType(String::from("expected request with `Content-Type: application/json`")).example()
+)),
+(status = 422, description = "Operation rejected or request body does not match the schema", body = ErrorResponse, example = json!(
+[
+InvalidBody(Some(String::from("transaction_value")), String::from("missing field `transaction_value`")).example(),
+Overdraft.example(),
+BalanceOverflow.example(),
+IdempotencyKeyReused(String::from("key")).example()
+]
 )),
 ))]
 /// Списание со счета
 pub async fn withdraw(
     State(state): State<StorageState>,
-    Json(payload): Json<TransactionRequest>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<TransactionRequest>,
 ) -> Result<Json<TransactionResponse>, AppError> {
-    usecases::account::change_acc_balance(
-        &state,
-        payload.transaction_value,
-        payload.account_id,
-        Withdraw,
-    )
-    .map(Json)
+    let key: Option<String> = idempotency_key(&headers)?;
+    state
+        .blocking(move |state| {
+            usecases::account::change_acc_balance(
+                &state,
+                payload.transaction_value,
+                payload.account_id,
+                Withdraw,
+                payload.reference,
+                key,
+            )
+        })
+        .await
+        .map(Json)
 }
 
 #[utoipa::path(
 post,
 path = "/account/transfer",
 request_body = TransferRequest,
+params(
+("Idempotency-Key" = Option<String>, Header, description = "Repeated key within the window returns the original response")
+),
 responses(
 (status = 200, description = "Transfered successfully", body = TransferResponse),
-(status = 400, description = "Errors", body = AppError, example = json ! (
+Unavailable,
+(status = 400, description = "Invalid request", body = ErrorResponse, example = json!(
 [
-{"error1": ZeroValueTransaction.to_string()},
-{"error2": SelfTransfer.to_string()},
-{"error3": Overdraft.to_string()}
+ZeroValueTransaction.example(),
+SelfTransfer.example(),
+InvalidReference(REFERENCE_MAX_LEN).example(),
+InvalidIdempotencyKey(IDEMPOTENCY_KEY_MAX_LEN).example()
 ]
 )),
-(status = 404, description = "Account not found", body = AppError, example = json!(
-{"error": AccountNotExists(String::from("account_№n")).to_string()}
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example()
+)),
+(status = 409, description = "Account is frozen, closed or a system account", body = ErrorResponse, example = json!(
+[
+AccountFrozen(String::from("account_№n")).example(),
+AccountClosed(String::from("account_№n")).example(),
+SystemAccount(String::from("account_№n")).example()
+]
+)),
+(status = 415, description = "Request body is not json", body = ErrorResponse, example = json!(
+UnsupportedMediaType(String::from("expected request with `Content-Type: application/json`")).example()
+)),
+(status = 422, description = "Operation rejected or request body does not match the schema", body = ErrorResponse, example = json!(
+[
+InvalidBody(Some(String::from("transaction_value")), String::from("missing field `transaction_value`")).example(),
+Overdraft.example(),
+BalanceOverflow.example(),
+IdempotencyKeyReused(String::from("key")).example()
+]
 )),
 ))]
 /// Перевод со счета на счет
 pub async fn transfer(
     State(state): State<StorageState>,
-    Json(payload): Json<TransferRequest>,
+    headers: HeaderMap,
+    JsonBody(payload): JsonBody<TransferRequest>,
 ) -> Result<Json<TransferResponse>, AppError> {
-    usecases::account::transfer(&state, payload).map(Json)
+    let key: Option<String> = idempotency_key(&headers)?;
+    state
+        .blocking(move |state| usecases::account::transfer(&state, payload, key))
+        .await
+        .map(Json)
+}
+
+/// Ключ идемпотентности из заголовка запроса.
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>> {
+    headers
+        .get(IDEMPOTENCY_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| InvalidIdempotencyKey(IDEMPOTENCY_KEY_MAX_LEN))
+        })
+        .transpose()
 }
 
 #[utoipa::path(
 get,
 path = "/account/balance/{account}",
 params(
-("account" = String, Path, description = "account name")
+("account" = String, Path, description = "account id or name"),
+AsOfQuery
 ),
 responses(
-(status = 200, description = "Got balance successfully", body = BalanceResponse),
-(status = 404, description = "Account not found", body = AppError, example = json!(
-{"error": AccountNotExists(String::from("account_№n")).to_string()})),
+(status = 200, description = "Got balance successfully (current or as of the given time)", body = BalanceResponse),
+Unavailable,
+(status = 404, description = "Account not found or opened after the given time", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
 ))]
 /// Баланс счета
 pub async fn balance(
     State(state): State<StorageState>,
-    Path(account_id): Path<u32>,
+    Path(account): Path<AccountKey>,
+    Query(query): Query<AsOfQuery>,
 ) -> Result<Json<BalanceResponse>, AppError> {
-    usecases::account::balance(&state, account_id).map(Json)
+    state
+        .blocking(move |state| usecases::account::balance(&state, account, query.as_of))
+        .await
+        .map(Json)
 }
 
 #[utoipa::path(
 get,
 path = "/account/{account}",
 params(
-("account" = String, Path, description = "account name")
+("account" = String, Path, description = "account id or name"),
+AsOfQuery
 ),
 responses(
-(status = 200, description = "Got account successfully", body = Account),
-(status = 404, description = "Account not found", body = AppError, example = json!(
-{"error": AccountNotExists(String::from("account_№n")).to_string()})),
+(status = 200, description = "Got account successfully (current or as of the given time)", body = Account),
+Unavailable,
+(status = 404, description = "Account not found or opened after the given time", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
 ))]
 /// Получение счета
 pub async fn account(
     State(state): State<StorageState>,
-    Path(account_id): Path<u32>,
+    Path(account): Path<AccountKey>,
+    Query(query): Query<AsOfQuery>,
 ) -> Result<Json<Account>, AppError> {
-    usecases::account::account(&state, account_id).map(Json)
+    state
+        .blocking(move |state| usecases::account::account(&state, account, query.as_of))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+get,
+path = "/account/{account}/history",
+params(
+("account" = String, Path, description = "account id or name"),
+HistoryQuery
+),
+responses(
+(status = 200, description = "Got account history page successfully", body = HistoryPage),
+Unavailable,
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
+(status = 400, description = "Invalid cursor or page size", body = ErrorResponse, example = json!(
+InvalidHistoryQuery(String::from("limit must be 1 to 1000, got 0")).example())),
+))]
+/// Страница истории счета: фильтры, порядок и курсор следующей страницы
+pub async fn history(
+    State(state): State<StorageState>,
+    Path(account): Path<AccountKey>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, AppError> {
+    state
+        .blocking(move |state| usecases::history::account_history(&state, account, query))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+post,
+path = "/account/{account}/close",
+params(
+("account" = String, Path, description = "account id or name")
+),
+responses(
+(status = 200, description = "Account closed successfully", body = StatusResponse),
+Unavailable,
+(status = 409, description = "Account can't be closed", body = ErrorResponse, example = json!(
+[
+NonZeroBalance(String::from("account_№n")).example(),
+StatusUnchanged(String::from("account_№n"), String::from("Closed")).example(),
+SystemAccount(String::from("account_№n")).example()
+]
+)),
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
+))]
+/// Закрытие счета
+pub async fn close(
+    State(state): State<StorageState>,
+    Path(account): Path<AccountKey>,
+) -> Result<Json<StatusResponse>, AppError> {
+    state
+        .blocking(move |state| usecases::account::close(&state, account))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+post,
+path = "/account/{account}/freeze",
+params(
+("account" = String, Path, description = "account id or name")
+),
+responses(
+(status = 200, description = "Account frozen successfully", body = StatusResponse),
+Unavailable,
+(status = 409, description = "Account can't be frozen", body = ErrorResponse, example = json!(
+[
+AccountClosed(String::from("account_№n")).example(),
+StatusUnchanged(String::from("account_№n"), String::from("Frozen")).example(),
+SystemAccount(String::from("account_№n")).example()
+]
+)),
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
+))]
+/// Заморозка счета
+pub async fn freeze(
+    State(state): State<StorageState>,
+    Path(account): Path<AccountKey>,
+) -> Result<Json<StatusResponse>, AppError> {
+    state
+        .blocking(move |state| usecases::account::freeze(&state, account))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+post,
+path = "/account/{account}/reopen",
+params(
+("account" = String, Path, description = "account id or name")
+),
+responses(
+(status = 200, description = "Account reopened successfully", body = StatusResponse),
+Unavailable,
+(status = 409, description = "Account is already opened or a system account", body = ErrorResponse, example = json!(
+[
+StatusUnchanged(String::from("account_№n"), String::from("Opened")).example(),
+SystemAccount(String::from("account_№n")).example()
+]
+)),
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
+))]
+/// Повторное открытие (разморозка) счета
+pub async fn reopen(
+    State(state): State<StorageState>,
+    Path(account): Path<AccountKey>,
+) -> Result<Json<StatusResponse>, AppError> {
+    state
+        .blocking(move |state| usecases::account::reopen(&state, account))
+        .await
+        .map(Json)
 }
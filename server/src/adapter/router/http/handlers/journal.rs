@@ -0,0 +1,71 @@
+use crate::adapter::router::http::handlers::Unavailable;
+use crate::adapter::storage::{StorageState, Storages};
+use crate::domain::entities::account::AccountKey;
+use crate::domain::entities::journal::Record;
+use crate::domain::errors::AppError::{AccountNotExists, OperationNotExists};
+use crate::domain::errors::{AppError, Result};
+use crate::domain::usecases;
+use axum::extract::{Path, State};
+use axum::Json;
+
+#[utoipa::path(
+get,
+path = "/journal",
+responses(
+(status = 200, description = "Got journal successfully", body = [Record]),
+Unavailable,
+)
+)]
+/// Получение журнала всех операций в порядке выполнения
+pub async fn journal(State(state): State<StorageState>) -> Result<Json<Vec<Record>>> {
+    state
+        .blocking(|state| usecases::journal::journal(&state))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+get,
+path = "/journal/{id}",
+params(
+("id" = u64, Path, description = "operation id")
+),
+responses(
+(status = 200, description = "Got operation successfully", body = Record),
+Unavailable,
+(status = 404, description = "Operation not found", body = ErrorResponse, example = json!(
+OperationNotExists(String::from("operation №n")).example())),
+))]
+/// Получение операции по id
+pub async fn operation(
+    State(state): State<StorageState>,
+    Path(operation_id): Path<u64>,
+) -> Result<Json<Record>, AppError> {
+    state
+        .blocking(move |state| usecases::journal::operation(&state, operation_id))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+get,
+path = "/journal/account/{account}",
+params(
+("account" = String, Path, description = "account id or name")
+),
+responses(
+(status = 200, description = "Got account operations successfully", body = [Record]),
+Unavailable,
+(status = 404, description = "Account not found", body = ErrorResponse, example = json!(
+AccountNotExists(String::from("account_№n")).example())),
+))]
+/// Получение операций счета в порядке выполнения
+pub async fn account_journal(
+    State(state): State<StorageState>,
+    Path(account): Path<AccountKey>,
+) -> Result<Json<Vec<Record>>, AppError> {
+    state
+        .blocking(move |state| usecases::journal::account_journal(&state, account))
+        .await
+        .map(Json)
+}
@@ -1,3 +1,134 @@
 pub mod account;
+pub mod journal;
 pub mod storage;
 pub mod transaction;
+
+use crate::domain::errors::AppError::{
+    InvalidBody, InvalidRequest, PayloadTooLarge, Recovering, StorageUnavailable,
+    UnsupportedMediaType,
+};
+use crate::domain::errors::{AppError, ErrorResponse};
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
+use utoipa::IntoResponses;
+
+/// Ответ 503 хендлеров бд: восстановление бд при старте или бд закрыта после сбоя.
+#[derive(IntoResponses)]
+#[response(
+    status = 503,
+    description = "Storage recovery in progress or storage closed after a failure",
+    example = json!([
+        Recovering.example(),
+        StorageUnavailable(String::from("reload after panic failed: backup load file error")).example()
+    ])
+)]
+pub struct Unavailable(pub ErrorResponse);
+
+/// Json тело запроса. В отличие от `axum::Json` ошибки разбора возвращаются ошибками сервиса:
+/// Content-Type не json - 415, синтаксис json - 400, несоответствие схеме - 422 с путем поля
+/// в `fields.field`.
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        json_content_type(request.headers())?;
+        let bytes: Bytes = body(request, state).await?;
+
+        parse(&bytes).map(Self)
+    }
+}
+
+/// Необязательное json тело запроса: пустое тело - `None`, непустое разбирается как `JsonBody`
+/// (ошибки Content-Type и формата тела возвращаются клиенту, а не считаются отсутствием тела).
+pub struct OptionalJson<T>(pub Option<T>);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for OptionalJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json: Result<(), AppError> = json_content_type(request.headers());
+        let bytes: Bytes = body(request, state).await?;
+        if bytes.is_empty() {
+            return Ok(Self(None));
+        }
+        is_json?;
+
+        parse(&bytes).map(|value| Self(Some(value)))
+    }
+}
+
+/// Тело запроса с учетом лимита размера (`DefaultBodyLimit`).
+async fn body<S: Send + Sync>(request: Request, state: &S) -> Result<Bytes, AppError> {
+    Bytes::from_request(request, state)
+        .await
+        .map_err(|rejection| match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => PayloadTooLarge(rejection.body_text()),
+            _ => InvalidRequest(rejection.body_text()),
+        })
+}
+
+/// Проверка Content-Type: `application/json` или `application/*+json`.
+fn json_content_type(headers: &HeaderMap) -> Result<(), AppError> {
+    let mime: Option<String> = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase());
+    match mime.as_deref() {
+        Some("application/json") => Ok(()),
+        Some(mime) if mime.starts_with("application/") && mime.ends_with("+json") => Ok(()),
+        _ => Err(UnsupportedMediaType(
+            "expected request with `Content-Type: application/json`".to_string(),
+        )),
+    }
+}
+
+/// Разбор json тела с путем поля, на котором разбор остановился.
+fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, AppError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value: T = serde_path_to_error::deserialize(&mut deserializer).map_err(body_error)?;
+    // символы после json значения
+    deserializer
+        .end()
+        .map_err(|err| InvalidRequest(err.to_string()))?;
+
+    Ok(value)
+}
+
+/// Ошибка разбора json тела: синтаксис - `InvalidRequest`, несоответствие схеме - `InvalidBody`.
+/// Для отсутствующего и неизвестного поля serde возвращает путь структуры, имя поля берется из сообщения.
+fn body_error(err: serde_path_to_error::Error<serde_json::Error>) -> AppError {
+    let message: String = err.inner().to_string();
+    if err.inner().classify() != Category::Data {
+        return InvalidRequest(message);
+    }
+    let path: Option<String> = Some(err.path().to_string()).filter(|path| path != ".");
+    let name: Option<&str> = ["missing field `", "unknown field `"]
+        .iter()
+        .find_map(|prefix| message.strip_prefix(prefix))
+        .and_then(|rest| rest.split('`').next());
+    let field: Option<String> = match (path, name) {
+        (Some(path), Some(name)) => Some(format!("{}.{}", path, name)),
+        (None, Some(name)) => Some(name.to_string()),
+        (path, None) => path,
+    };
+
+    InvalidBody(field, message)
+}
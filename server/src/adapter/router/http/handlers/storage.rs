@@ -1,11 +1,17 @@
-use crate::adapter::storage::StorageState;
+use crate::adapter::router::http::handlers::Unavailable;
+use crate::adapter::storage::{StorageState, Storages};
 use crate::domain::entities::account::Account;
+use crate::domain::entities::health::{HealthResponse, HealthStatus};
+use crate::domain::entities::history::{HistoryPage, HistoryQuery};
+use crate::domain::entities::journal::ReplayResponse;
 use crate::domain::errors::AppError::{
-    BackupLoadFile, EmptyBackupFile, EmptyDb, InvalidBackupFile,
+    BackupLoadFile, EmptyBackupFile, EmptyDb, InvalidBackupFile, InvalidHistoryQuery,
+    ReplayMismatch,
 };
 use crate::domain::errors::{AppError, Result};
 use crate::domain::usecases;
-use axum::extract::State;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
 use axum::Json;
 use std::collections::HashMap;
 
@@ -14,14 +20,40 @@ get,
 path = "/storage/history",
 responses(
 (status = 200, description = "Got all transactions successfully", body = HashMap<String, Account>),
-(status = 400, description = "Empty db error", body = AppError, example = json!({"error": EmptyDb.to_string()}))
+Unavailable,
+(status = 404, description = "Empty db error", body = ErrorResponse, example = json!(EmptyDb.example()))
 )
 )]
 /// получение всех транзакций бд в разбивке по счетам
 pub async fn history(
     State(state): State<StorageState>,
 ) -> Result<Json<HashMap<u32, Account>>, AppError> {
-    usecases::storage::history(&state).map(Json)
+    state
+        .blocking(|state| usecases::storage::history(&state))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+get,
+path = "/storage/transactions",
+params(HistoryQuery),
+responses(
+(status = 200, description = "Got history page of all accounts successfully", body = HistoryPage),
+Unavailable,
+(status = 400, description = "Invalid cursor or page size", body = ErrorResponse, example = json!(
+InvalidHistoryQuery(String::from("invalid cursor: 'abc'")).example()))
+)
+)]
+/// Страница истории всех счетов: по id счета, затем по id транзакции
+pub async fn transactions(
+    State(state): State<StorageState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryPage>, AppError> {
+    state
+        .blocking(move |state| usecases::history::transactions(&state, query))
+        .await
+        .map(Json)
 }
 
 #[utoipa::path(
@@ -29,11 +61,12 @@ post,
 path = "/storage/backup",
 responses(
 (status = 200, description = "Backup successfully", body = HashMap<String, String>, example = json!({"info": "successfully backup"})),
-(status = 400, description = "Backup load file errors", body = AppError, example = json ! (
+Unavailable,
+(status = 500, description = "Backup load file errors", body = ErrorResponse, example = json!(
 [
-{"error1": BackupLoadFile.to_string()},
-{"error2": EmptyBackupFile.to_string()},
-{"error3": InvalidBackupFile.to_string()}
+BackupLoadFile.example(),
+EmptyBackupFile.example(),
+InvalidBackupFile.example()
 ]
 )),
 )
@@ -42,10 +75,61 @@ responses(
 pub async fn backup(
     State(state): State<StorageState>,
 ) -> Result<Json<HashMap<String, String>>, AppError> {
-    usecases::storage::backup(&state)?;
+    state
+        .blocking(|state| usecases::storage::backup(&state))
+        .await?;
     // 200
     Ok(Json(HashMap::from([(
         "info".to_string(),
         "successfully backup".to_string(),
     )])))
 }
+
+#[utoipa::path(
+post,
+path = "/storage/replay",
+responses(
+(status = 200, description = "Db rebuilt from journal and verified successfully", body = ReplayResponse),
+Unavailable,
+(status = 500, description = "Rebuilt db differs from the current one", body = ErrorResponse, example = json!(
+ReplayMismatch(String::from("account '1' transactions differ")).example()
+)),
+)
+)]
+/// Восстановление бд по журналу операций в новом экземпляре банка и сверка с текущей бд
+pub async fn replay(State(state): State<StorageState>) -> Result<Json<ReplayResponse>, AppError> {
+    state
+        .blocking(|state| usecases::replay::rebuild(&state))
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+get,
+path = "/health",
+responses(
+(status = 200, description = "Storage is available", body = HealthResponse, example = json!(
+{"status": "ok", "recovery": {"accounts": 1000, "transactions": 25000, "operations": 26000, "elapsed_ms": 120}}
+)),
+(status = 503, description = "Storage recovery in progress or storage closed after a failure", body = HealthResponse, example = json!(
+[
+{"status": "recovering"},
+{"status": "failed", "reason": "reload after panic failed: in-memory storage without backup has no stored state"}
+]
+)),
+)
+)]
+/// Состояние сервиса: бд работает, восстанавливается при старте или закрыта после сбоя
+pub async fn health(
+    State(state): State<StorageState>,
+) -> Result<(StatusCode, Json<HealthResponse>), AppError> {
+    let health: HealthResponse = state
+        .blocking(|state| Ok(usecases::storage::health(&state)))
+        .await?;
+    let status: StatusCode = match health.status {
+        HealthStatus::Ok => StatusCode::OK,
+        HealthStatus::Recovering | HealthStatus::Failed => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    Ok((status, Json(health)))
+}
@@ -1,4 +1,6 @@
-use crate::adapter::storage::StorageState;
+use crate::adapter::router::http::handlers::Unavailable;
+use crate::adapter::storage::{StorageState, Storages};
+use crate::domain::entities::account::AccountKey;
 use crate::domain::entities::transaction::Transaction;
 use crate::domain::errors::AppError::{AccountNotExists, TransactionNotExists};
 use crate::domain::errors::{AppError, Result};
@@ -10,21 +12,25 @@ use axum::Json;
 get,
 path = "/transaction/{account}/{id}",
 params(
-("account" = String, Path, description = "account name"),
+("account" = String, Path, description = "account id or name"),
 ("id" = u32, Path, description = "transaction id")
 ),
 responses(
 (status = 200, description = "Got transaction successfully", body = [Transaction]),
-(status = 404, description = "Errors", body = AppError, example = json ! (
+Unavailable,
+(status = 404, description = "Errors", body = ErrorResponse, example = json!(
 [
-{"error1": AccountNotExists(String::from("account_№n")).to_string()},
-{"error2": TransactionNotExists(String::from("account №n"), String::from("transaction №n")).to_string()},
+AccountNotExists(String::from("account_№n")).example(),
+TransactionNotExists(String::from("account №n"), String::from("transaction №n")).example(),
 ]
 ))))]
 /// Получение транзакции по id
 pub async fn transaction(
     State(state): State<StorageState>,
-    Path((account_id, transaction_id)): Path<(u32, u32)>,
+    Path((account, transaction_id)): Path<(AccountKey, u32)>,
 ) -> Result<Json<Transaction>, AppError> {
-    usecases::transaction::transaction(&state, account_id, transaction_id).map(Json)
+    state
+        .blocking(move |state| usecases::transaction::transaction(&state, account, transaction_id))
+        .await
+        .map(Json)
 }
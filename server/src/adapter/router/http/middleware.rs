@@ -1,14 +1,49 @@
+use crate::domain::errors::AppError::{
+    InvalidBody, InvalidRequest, PayloadTooLarge, UnsupportedMediaType,
+};
+use crate::domain::errors::{AppError, REQUEST_ID};
+use axum::body::to_bytes;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::IntoResponse;
 use axum::{extract::Request, middleware::Next, response::Response};
 use tracing::{error, info};
+use uuid::Uuid;
+
+/// Заголовок с id запроса.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Максимальная длина id запроса, переданного клиентом.
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+/// Максимальный размер текста ошибки извлечения запроса.
+const REJECTION_MAX_LEN: usize = 64 * 1024;
 
 /// Middleware.
 pub async fn middleware(request: Request, next: Next) -> Response {
     // ендпоит
     let path = &request.uri().path().to_string();
+    // id запроса: переданный клиентом или новый
+    let request_id: String = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= REQUEST_ID_MAX_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
     // замер времени
     let start = std::time::Instant::now();
-    // вызов хендлера
-    let response = next.run(request).await;
+    // вызов хендлера в контексте id запроса
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), async move {
+            let response = next.run(request).await;
+            envelope(response).await
+        })
+        .await;
+    // id запроса в ответе
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
     // статус ответа хендлера
     let status = response.status();
     // замер времени
@@ -16,15 +51,42 @@ pub async fn middleware(request: Request, next: Next) -> Response {
     // логирование ответа хендлера
     if response.status().is_success() {
         info!(
-            "path={}, status=Success, status_code={}, duration={} μs",
-            path, status, end
+            "path={}, request_id={}, status=Success, status_code={}, duration={} μs",
+            path, request_id, status, end
         );
     } else {
         error!(
-            "path={}, status=Error, status_code={}, duration={} μs",
-            path, status, end
+            "path={}, request_id={}, status=Error, status_code={}, duration={} μs",
+            path, request_id, status, end
         )
     }
     // ответ декоратора
     response
 }
+
+/// Перекладка текстовых ошибок извлечения запроса (json, path, размер тела) в формат ErrorResponse.
+/// Статус ответа сохраняется: слишком большое тело - 413, Content-Type - 415, данные тела - 422,
+/// остальные - 400. Json тела хендлеров разбираются `JsonBody`, здесь - прочие извлечения axum.
+async fn envelope(response: Response) -> Response {
+    let is_text: bool = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"text/plain"));
+    if !response.status().is_client_error() || !is_text {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let message: String = match to_bytes(body, REJECTION_MAX_LEN).await {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => parts.status.to_string(),
+    };
+
+    let err: AppError = match parts.status {
+        StatusCode::PAYLOAD_TOO_LARGE => PayloadTooLarge(message),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => UnsupportedMediaType(message),
+        StatusCode::UNPROCESSABLE_ENTITY => InvalidBody(None, message),
+        _ => InvalidRequest(message),
+    };
+
+    err.into_response()
+}
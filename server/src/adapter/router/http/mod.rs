@@ -2,25 +2,38 @@ pub mod handlers;
 mod middleware;
 
 use crate::adapter::router::http::handlers::account::{
-    account, balance, new_account, replenish, transfer, withdraw,
+    account, balance, close, freeze, history as account_history, new_account, reopen, replenish,
+    transfer, withdraw,
+};
+use crate::adapter::router::http::handlers::journal::{account_journal, journal, operation};
+use crate::adapter::router::http::handlers::storage::{
+    backup, health, history, replay, transactions,
 };
-use crate::adapter::router::http::handlers::storage::{backup, history};
 use crate::adapter::router::http::handlers::transaction::transaction;
-use crate::adapter::router::http::handlers::{account, storage, transaction};
+use crate::adapter::router::http::handlers::{account, journal, storage, transaction};
 use crate::adapter::router::http::middleware::middleware;
 use crate::adapter::storage::Storage;
-use crate::domain::entities::account::{Account, BalanceResponse, Status};
+use crate::domain::entities::account::{
+    Account, AccountKey, BalanceResponse, NewAccountRequest, Status, StatusResponse,
+};
+use crate::domain::entities::health::{HealthResponse, HealthStatus, RecoveryReport};
+use crate::domain::entities::history::{HistoryEntry, HistoryPage, Order};
+use crate::domain::entities::journal::{Command, Record, ReplayResponse, TransactionRef};
+use crate::domain::entities::money::Money;
 use crate::domain::entities::transaction::{
     Operation, Transaction, TransactionRequest, TransactionResponse,
 };
 use crate::domain::entities::transaction::{TransferRequest, TransferResponse};
-use crate::domain::errors::AppError;
+use crate::domain::errors::{ErrorBody, ErrorResponse};
+use axum::extract::DefaultBodyLimit;
 use axum::Router;
 use axum::{
     middleware::{self as middle},
     routing::{get, post},
 };
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::timeout::TimeoutLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
@@ -33,13 +46,24 @@ account::withdraw,
 account::transfer,
 account::balance,
 account::account,
+account::history,
+account::close,
+account::freeze,
+account::reopen,
 transaction::transaction,
+journal::journal,
+journal::operation,
+journal::account_journal,
 storage::history,
-storage::backup
+storage::transactions,
+storage::backup,
+storage::replay,
+storage::health
 ),
 components(
-schemas(Account, Status, TransactionResponse, Transaction, Operation, BalanceResponse,
-TransactionRequest, AppError, TransferRequest, TransferResponse, Transaction)
+schemas(Account, AccountKey, NewAccountRequest, Status, StatusResponse, Money, TransactionResponse, Transaction, Operation, BalanceResponse,
+TransactionRequest, TransferRequest, TransferResponse, Transaction, Record, Command,
+TransactionRef, ReplayResponse, HistoryEntry, HistoryPage, Order, HealthResponse, HealthStatus, RecoveryReport, ErrorResponse, ErrorBody)
 ),
 tags(
 (name = "Bank service", description = "The service emulates banking transactions workflow")
@@ -48,14 +72,23 @@ tags(
 pub struct ApiDoc;
 
 /// Создание роутера и регистрация хендлеров.
-pub async fn router(shared_state: Arc<RwLock<Storage>>) -> Router {
+/// `timeout` - таймаут обработки запроса, `max_body_bytes` - максимальный размер тела запроса.
+pub async fn router(
+    shared_state: Arc<Storage>,
+    timeout: Duration,
+    max_body_bytes: usize,
+) -> Router {
     Router::new()
         // хендлеры счета
         .nest("/account", account_registration(&shared_state))
         // хендлеры транзакций
         .nest("/transaction", transaction_registration(&shared_state))
+        // хендлеры журнала операций
+        .nest("/journal", journal_registration(&shared_state))
         // хендлеры бд
         .nest("/storage", storage_registration(&shared_state))
+        // состояние сервиса
+        .route("/health", get(health).with_state(Arc::clone(&shared_state)))
         // swagger
         .merge(SwaggerUi::new("/swagger").url("/api-docs/openapi.json", ApiDoc::openapi()))
         // .layer((
@@ -65,12 +98,16 @@ pub async fn router(shared_state: Arc<RwLock<Storage>>) -> Router {
         //     // Graceful shutdown
         //     TimeoutLayer::new(Duration::from_secs(5)),
         // ))
+        // таймаут обработки запроса (408)
+        .layer(TimeoutLayer::new(timeout))
+        // ограничение размера тела запроса (413)
+        .layer(DefaultBodyLimit::max(max_body_bytes))
         // кастомный middleware
         .layer(middle::from_fn(middleware))
 }
 
 /// Регистрация хендлеров работы со счетом.
-pub fn account_registration(shared_state: &Arc<RwLock<Storage>>) -> Router {
+pub fn account_registration(shared_state: &Arc<Storage>) -> Router {
     Router::new()
         .route("/new", post(new_account))
         .route("/replenish", post(replenish))
@@ -78,20 +115,294 @@ pub fn account_registration(shared_state: &Arc<RwLock<Storage>>) -> Router {
         .route("/transfer", post(transfer))
         .route("/balance/:account", get(balance))
         .route("/:account", get(account))
+        .route("/:account/history", get(account_history))
+        .route("/:account/close", post(close))
+        .route("/:account/freeze", post(freeze))
+        .route("/:account/reopen", post(reopen))
         .with_state(Arc::clone(shared_state))
 }
 
 /// Регистрация хендлеров работы со транзакциями.
-pub fn transaction_registration(shared_state: &Arc<RwLock<Storage>>) -> Router {
+pub fn transaction_registration(shared_state: &Arc<Storage>) -> Router {
     Router::new()
         .route("/:account/:id", get(transaction))
         .with_state(Arc::clone(shared_state))
 }
 
+/// Регистрация хендлеров работы с журналом операций.
+pub fn journal_registration(shared_state: &Arc<Storage>) -> Router {
+    Router::new()
+        .route("/", get(journal))
+        .route("/:id", get(operation))
+        .route("/account/:account", get(account_journal))
+        .with_state(Arc::clone(shared_state))
+}
+
 /// Регистрация хендлеров работы с БД.
-pub fn storage_registration(shared_state: &Arc<RwLock<Storage>>) -> Router {
+pub fn storage_registration(shared_state: &Arc<Storage>) -> Router {
     Router::new()
         .route("/history", get(history))
+        .route("/transactions", get(transactions))
         .route("/backup", post(backup))
+        .route("/replay", post(replay))
         .with_state(Arc::clone(shared_state))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::storage::Storages;
+    use crate::domain::usecases;
+    use axum::body::{to_bytes, Body};
+    use axum::http::header::CONTENT_TYPE;
+    use axum::http::{Request, StatusCode};
+    use serde_json::Value;
+    use std::sync::mpsc;
+    use std::thread;
+    use tower::ServiceExt;
+
+    /// Запрос к роутеру с лимитом тела 64 байта: статус и тело ответа.
+    async fn call(request: Request<Body>) -> (StatusCode, Value) {
+        send(Arc::new(Storage::default()), request).await
+    }
+
+    /// Запрос к роутеру над заданным 'state'.
+    async fn send(state: Arc<Storage>, request: Request<Body>) -> (StatusCode, Value) {
+        let app: Router = router(state, Duration::from_secs(5), 64).await;
+        let response = app.oneshot(request).await.unwrap();
+        let status: StatusCode = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    /// POST запрос с json телом.
+    fn post(uri: &str, content_type: Option<&str>, body: &str) -> Request<Body> {
+        let mut builder = Request::post(uri);
+        if let Some(content_type) = content_type {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_body_is_unprocessable() {
+        // отсутствующее поле и поле неверного типа: 422 с путем поля
+        for (body, field) in [
+            (r#"{"account_id": 1}"#, "transaction_value"),
+            (
+                r#"{"account_id": 1, "transaction_value": "1.00", "reference": 5}"#,
+                "reference",
+            ),
+        ] {
+            let (status, body) =
+                call(post("/account/replenish", Some("application/json"), body)).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(body["error"]["code"], "INVALID_BODY");
+            assert_eq!(body["error"]["fields"]["field"], field);
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_bad_request() {
+        for body in ["{", r#"{"account_id": 1, "transaction_value": "1.00"} {}"#] {
+            let (status, body) =
+                call(post("/account/replenish", Some("application/json"), body)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["error"]["code"], "INVALID_REQUEST");
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_content_type_is_unsupported_media_type() {
+        for content_type in [None, Some("text/plain")] {
+            let (status, body) = call(post("/account/replenish", content_type, "{}")).await;
+            assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+            assert_eq!(body["error"]["code"], "UNSUPPORTED_MEDIA_TYPE");
+        }
+    }
+
+    #[tokio::test]
+    async fn large_body_is_payload_too_large() {
+        let body: String = format!(
+            r#"{{"account_id": 1, "transaction_value": "1.00", "reference": "{}"}}"#,
+            "a".repeat(100)
+        );
+        let (status, body) =
+            call(post("/account/replenish", Some("application/json"), &body)).await;
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+    }
+
+    #[tokio::test]
+    async fn service_errors_keep_their_status() {
+        let request = Request::get("/account/savings")
+            .body(Body::empty())
+            .unwrap();
+        let (status, body) = call(request).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["code"], "ACCOUNT_NOT_FOUND");
+        assert_eq!(body["error"]["message"], "account: 'savings' not found");
+        assert_eq!(body["error"]["fields"]["account"], "savings");
+    }
+
+    #[tokio::test]
+    async fn malformed_new_account_body_creates_nothing() {
+        let state: Arc<Storage> = Arc::new(Storage::default());
+        // некорректный тип поля, синтаксис json, тело без Content-Type
+        let requests = [
+            (
+                post("/account/new", Some("application/json"), r#"{"name": 123}"#),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "INVALID_BODY",
+            ),
+            (
+                post("/account/new", Some("application/json"), "{"),
+                StatusCode::BAD_REQUEST,
+                "INVALID_REQUEST",
+            ),
+            (
+                post("/account/new", None, r#"{"name": "savings"}"#),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "UNSUPPORTED_MEDIA_TYPE",
+            ),
+        ];
+        for (request, expected, code) in requests {
+            let (status, body) = send(Arc::clone(&state), request).await;
+            assert_eq!(status, expected);
+            assert_eq!(body["error"]["code"], code);
+        }
+        assert!(state.db().unwrap().get_accounts().is_empty());
+
+        // пустое тело - счет без имени
+        let (status, body) = send(Arc::clone(&state), post("/account/new", None, "")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["account_id"], 1);
+        let (status, _) = send(
+            Arc::clone(&state),
+            post(
+                "/account/new",
+                Some("application/json"),
+                r#"{"name": "savings"}"#,
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(state.db().unwrap().find_account("savings"), Some(2));
+    }
+
+    #[tokio::test]
+    async fn health_reports_recovery() {
+        let state: Arc<Storage> = Arc::new(Storage::default());
+        state.set_recovering(true);
+        let (status, body) = send(
+            Arc::clone(&state),
+            Request::get("/health").body(Body::empty()).unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, serde_json::json!({"status": "recovering"}));
+
+        state.set_recovering(false);
+        state.set_recovery(RecoveryReport {
+            accounts: 2,
+            transactions: 5,
+            operations: 7,
+            elapsed_ms: Some(3),
+        });
+        let (status, body) =
+            send(state, Request::get("/health").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+        assert_eq!(
+            body["recovery"],
+            serde_json::json!({"accounts": 2, "transactions": 5, "operations": 7, "elapsed_ms": 3})
+        );
+    }
+
+    #[tokio::test]
+    async fn balance_as_of_query() {
+        let state: Arc<Storage> = Arc::new(Storage::default());
+        state.set_clock(Some("2024-01-01T10:00:00Z".parse().unwrap()));
+        let acc_id: u32 = usecases::account::new_account(Arc::clone(&state), None)
+            .unwrap()
+            .account_id;
+        state.set_clock(Some("2024-01-02T10:00:00Z".parse().unwrap()));
+        usecases::account::change_acc_balance(
+            &state,
+            "7.00".parse().unwrap(),
+            acc_id.into(),
+            Operation::Replenish,
+            None,
+            None,
+        )
+        .unwrap();
+        let get = |uri: String| Request::get(uri).body(Body::empty()).unwrap();
+
+        let uri: String = format!("/account/balance/{}?as_of=2024-01-01T23:59:59Z", acc_id);
+        let (status, body) = send(Arc::clone(&state), get(uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["balance"], "0.00");
+
+        let (_, body) = send(
+            Arc::clone(&state),
+            get(format!("/account/balance/{}", acc_id)),
+        )
+        .await;
+        assert_eq!(body["balance"], "7.00");
+
+        let uri: String = format!("/account/balance/{}?as_of=yesterday", acc_id);
+        let (status, body) = send(state, get(uri)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "INVALID_REQUEST");
+    }
+
+    #[test]
+    fn storage_handlers_document_unavailable() {
+        let doc: Value = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        for path in ["/journal", "/account/{account}", "/account/transfer"] {
+            let operation = doc["paths"][path]
+                .as_object()
+                .and_then(|item| item.values().next())
+                .unwrap();
+            let unavailable = &operation["responses"]["503"];
+            assert_eq!(
+                unavailable["content"]["application/json"]["schema"]["$ref"],
+                "#/components/schemas/ErrorResponse"
+            );
+            assert_eq!(
+                unavailable["content"]["application/json"]["example"][0]["error"]["code"],
+                "RECOVERING"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn waiting_for_storage_does_not_block_runtime() {
+        let state: Arc<Storage> = Arc::new(Storage::default());
+        // бд занята другим потоком (например, медленным диском)
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let holder: Arc<Storage> = Arc::clone(&state);
+        let worker = thread::spawn(move || {
+            let _db = holder.db_mut().unwrap();
+            locked_tx.send(()).unwrap();
+            let _ = release_rx.recv_timeout(Duration::from_secs(2));
+        });
+        locked_rx.recv().unwrap();
+
+        let request = tokio::spawn(send(
+            Arc::clone(&state),
+            Request::get("/account/1").body(Body::empty()).unwrap(),
+        ));
+        // единственный поток runtime теста не занят ожиданием бд
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!request.is_finished());
+
+        release_tx.send(()).unwrap();
+        let (status, _) = request.await.unwrap();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        worker.join().unwrap();
+    }
+}
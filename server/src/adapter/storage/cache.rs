@@ -1,96 +1,927 @@
+use crate::adapter::storage::wal::{self, AppendError, Segment, Wal, WalEntry};
 use crate::domain::entities::account::Account;
+use crate::domain::entities::idempotency::IdempotencyRecord;
+use crate::domain::entities::journal::Record;
 use crate::domain::errors::AppError;
-use crate::domain::errors::AppError::{BackupLoadFile, EmptyBackupFile, InvalidBackupFile};
-use serde::Serialize;
+use crate::domain::errors::AppError::{
+    AccountNameExists, AccountNotExists, BackupLoadFile, EmptyBackupFile, InvalidBackupFile,
+    StorageFailure, StorageUnavailable,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::warn;
 
-/// Путь к backup.json для бэкапа db.
+/// Директория backup.json по умолчанию.
 pub const PATH: &str = "server/backup";
+/// Имя файла реплики.
+const BACKUP_FILE: &str = "backup.json";
+/// Число хранимых предыдущих реплик по умолчанию.
+pub const BACKUP_KEEP: usize = 3;
+/// Число операций между снимками backup.json по умолчанию.
+pub const SNAPSHOT_EVERY: u64 = 1000;
 
 /// Структура db (in-memory).
 #[derive(Debug, Default)]
 pub struct CacheImpl {
     pub id: u32,
     pub cache: HashMap<u32, Account>,
+    // индекс имен счетов: имя -> id счета
+    pub names: HashMap<String, u32>,
+    // журнал операций в порядке выполнения
+    pub journal: Vec<Record>,
+    // индекс журнала по счетам: id счета -> позиции записей в журнале
+    pub index: HashMap<u32, Vec<usize>>,
+    // результаты операций по ключам идемпотентности
+    pub idempotency: HashMap<String, IdempotencyRecord>,
+    // директория backup.json и WAL, None - бд без репликации (восстановление из журнала, backup выключен)
+    pub backup_dir: Option<PathBuf>,
+    // число хранимых предыдущих реплик backup.json.1..keep
+    pub backup_keep: usize,
+    // число операций между снимками backup.json
+    pub snapshot_every: u64,
+    // число операций журнала в последнем снимке (или в последней попытке снимка)
+    snapshot_op: AtomicU64,
+    // снимок сериализован и еще записывается
+    snapshotting: Arc<AtomicBool>,
+    // журнал предзаписи (None - бд без репликации)
+    wal: Option<Arc<Wal>>,
+    // последние занятые id операции и счета: записанные, но еще не примененные операции
+    reserved: Mutex<Sequence>,
 }
 
-/// Трейт бд
+/// Id записываемой операции, id нового счета (для операции открытия счета) и время операции.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sequence {
+    pub operation_id: u64,
+    pub account_id: u32,
+    // заполняется `Storages::commit` под той же резервацией, что и id операции
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Операция, записанная в журнал: до `sync` может быть не сброшена на диск,
+/// до `Cache::apply` не видна в бд.
+#[derive(Debug)]
+pub struct Pending {
+    pub entry: WalEntry,
+    // журнал и позиция конца записи в нем
+    wal: Option<(Arc<Wal>, u64)>,
+}
+
+impl Pending {
+    /// Операция, уже сохраненная на диске (sql-транзакция) или бд без репликации.
+    pub fn new(entry: WalEntry) -> Self {
+        Self { entry, wal: None }
+    }
+
+    /// Id операции.
+    pub fn operation_id(&self) -> u64 {
+        self.entry.operation_id()
+    }
+
+    /// Сброс записи на диск. Сброс групповой: один fsync на все записи, дописанные к этому моменту.
+    /// Ошибка - содержимое журнала на диске неизвестно, бд закрывается до перезапуска (`Storages::commit`).
+    pub fn sync(&self) -> Result<(), AppError> {
+        match &self.wal {
+            Some((wal, end)) => wal
+                .sync(*end)
+                .map_err(|err| StorageUnavailable(err.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Формат файла backup.json: счета и журнал операций.
+#[derive(Default, Deserialize)]
+struct BackupFile {
+    accounts: HashMap<u32, Account>,
+    journal: Vec<Record>,
+    #[serde(default)]
+    idempotency: HashMap<String, IdempotencyRecord>,
+}
+
+/// Снимок бд, сериализованный под разделяемой блокировкой.
+/// Запись файла и ротация WAL выполняются без блокировки бд.
+#[derive(Debug)]
+pub struct Snapshot {
+    dir: PathBuf,
+    keep: usize,
+    payload: Vec<u8>,
+    // журнал: после записи снимка начинается новый сегмент
+    wal: Option<Arc<Wal>>,
+    // флаг записи снимка бд, снимается по окончании записи
+    running: Arc<AtomicBool>,
+}
+
+impl Snapshot {
+    /// Запись снимка в backup.json (временный файл, fsync, атомарное переименование)
+    /// с ротацией предыдущих снимков и сегментов WAL.
+    pub fn store(&self) -> Result<(), AppError> {
+        // запись во временный файл со сбросом на диск
+        let tmp: PathBuf = self.dir.join(format!("{}.tmp", BACKUP_FILE));
+        write_synced(&tmp, &self.payload).map_err(backup_err)?;
+        // ротация предыдущих реплик
+        rotate(&self.dir, self.keep).map_err(backup_err)?;
+        // атомарная замена backup.json: после сбоя остается старая или новая реплика целиком
+        fs::rename(&tmp, backup_path(&self.dir, 0)).map_err(backup_err)?;
+        sync_dir(&self.dir).map_err(backup_err)?;
+        // новый сегмент WAL; операции, дописанные после сериализации снимка,
+        // остаются в предыдущем сегменте и повторяются при восстановлении
+        if let Some(wal) = &self.wal {
+            wal.rotate(self.keep).map_err(backup_err)?;
+        }
+        sync_dir(&self.dir).map_err(backup_err)
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl CacheImpl {
+    /// Конструктор in-memory бд без репликации в backup.json.
+    pub fn ephemeral() -> Self {
+        Self::default()
+    }
+
+    /// Конструктор in-memory бд с репликацией в директорию `dir`:
+    /// журнал предзаписи wal.jsonl и периодические снимки backup.json.
+    pub fn with_backup(dir: impl Into<PathBuf>) -> Self {
+        let dir: PathBuf = dir.into();
+        Self {
+            wal: Some(Arc::new(Wal::new(&dir))),
+            backup_dir: Some(dir),
+            backup_keep: BACKUP_KEEP,
+            snapshot_every: SNAPSHOT_EVERY,
+            ..Default::default()
+        }
+    }
+
+    /// Число хранимых предыдущих реплик (0 - без ротации).
+    pub fn with_rotation(mut self, keep: usize) -> Self {
+        self.backup_keep = keep;
+        self
+    }
+
+    /// Число операций между снимками backup.json.
+    pub fn with_snapshots(mut self, every: u64) -> Self {
+        self.snapshot_every = every;
+        self
+    }
+
+    /// Журнал предзаписи (тесты).
+    #[cfg(test)]
+    pub fn wal(&self) -> Option<Arc<Wal>> {
+        self.wal.clone()
+    }
+
+    /// Начало новой реплики без восстановления: пустой снимок backup.json,
+    /// сохраненные снимки и WAL сдвигаются в ротацию (удаляются без нее).
+    pub fn discard_backup(&mut self) -> Result<(), AppError> {
+        let Some(dir) = self.backup_dir.clone() else {
+            return Ok(());
+        };
+        match self.prepare(&dir)? {
+            Some(snapshot) => snapshot.store(),
+            None => Ok(()),
+        }
+    }
+
+    /// Добавление готовой записи в журнал и индекс.
+    fn push_record(&mut self, record: Record) {
+        for acc_id in record.accounts() {
+            self.index
+                .entry(acc_id)
+                .or_default()
+                .push(self.journal.len());
+        }
+        self.journal.push(record);
+    }
+
+    /// Запись операции в журнал: `build` получает id операции и нового счета,
+    /// `write` сохраняет операцию (WAL, sql-транзакция). Операции нумеруются и пишутся по одной,
+    /// ошибка записи не занимает id. Вызывается под разделяемой блокировкой бд.
+    pub fn append_with(
+        &self,
+        build: &mut dyn FnMut(Sequence) -> Result<WalEntry, AppError>,
+        write: &mut dyn FnMut(&WalEntry) -> Result<(), AppError>,
+    ) -> Result<WalEntry, AppError> {
+        let mut reserved = self.reserved.lock().unwrap_or_else(PoisonError::into_inner);
+        let seq = Sequence {
+            operation_id: reserved.operation_id.max(self.journal.len() as u64) + 1,
+            account_id: reserved.account_id.max(self.id) + 1,
+            ..Sequence::default()
+        };
+        let entry: WalEntry = build(seq)?;
+        self.check(&entry)?;
+        write(&entry)?;
+        reserved.operation_id = seq.operation_id;
+        if let WalEntry::Account { account, .. } = &entry {
+            reserved.account_id = reserved.account_id.max(account.id);
+        }
+
+        Ok(entry)
+    }
+
+    /// Проверка операции по бд до записи: счета операции существуют, имя нового счета свободно.
+    fn check(&self, entry: &WalEntry) -> Result<(), AppError> {
+        match entry {
+            WalEntry::Account { account, .. } => match &account.name {
+                Some(name) if self.names.contains_key(name) => Err(AccountNameExists(name.clone())),
+                _ => Ok(()),
+            },
+            WalEntry::Commit { entries, .. } => match entries
+                .iter()
+                .find(|(acc_id, _)| !self.cache.contains_key(acc_id))
+            {
+                Some((acc_id, _)) => Err(AccountNotExists(acc_id.to_string())),
+                None => Ok(()),
+            },
+            WalEntry::Status { account_id, .. } if !self.cache.contains_key(account_id) => {
+                Err(AccountNotExists(account_id.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Изменение бд в памяти по записи WAL: фиксация операции и повтор WAL при восстановлении.
+    fn apply_entry(&mut self, entry: WalEntry) {
+        match entry {
+            WalEntry::Account { account, record } => {
+                self.id = self.id.max(account.id);
+                if let Some(name) = &account.name {
+                    self.names.insert(name.clone(), account.id);
+                }
+                self.cache.insert(account.id, account);
+                self.push_record(record);
+            }
+            WalEntry::Commit {
+                entries,
+                record,
+                idempotency,
+                expired,
+            } => {
+                for (acc_id, tx) in entries {
+                    if let Some(account) = self.cache.get_mut(&acc_id) {
+                        account.balance = tx.current;
+                        account.transactions.push(tx);
+                    }
+                }
+                self.push_record(record);
+                if let Some(saved) = idempotency {
+                    self.keep_idempotency(saved, expired);
+                }
+            }
+            WalEntry::Status {
+                account_id,
+                status,
+                record,
+            } => {
+                if let Some(account) = self.cache.get_mut(&account_id) {
+                    account.status = status;
+                }
+                self.push_record(record);
+            }
+            WalEntry::Idempotency { record, .. } => {
+                self.idempotency.insert(record.key.clone(), record);
+            }
+        }
+    }
+
+    /// Сохранение ответа по ключу идемпотентности с удалением ключей раньше `expired`.
+    fn keep_idempotency(&mut self, saved: IdempotencyRecord, expired: Option<DateTime<Utc>>) {
+        if let Some(expired) = expired {
+            self.idempotency
+                .retain(|_, record| record.timestamp >= expired);
+        }
+        self.idempotency.insert(saved.key.clone(), saved);
+    }
+
+    /// Повтор сегментов WAL от самого старого до текущего поверх загруженного снимка.
+    /// Операции, уже вошедшие в снимок, пропускаются; повтор останавливается на разрыве номеров
+    /// операций или на поврежденной записи. Возвращает число повторенных операций.
+    fn replay_wal(&mut self, dir: &Path) -> Result<u64, AppError> {
+        // операции снимка (ключи идемпотентности после него еще не сохранены)
+        let base_op: u64 = self.journal.len() as u64;
+        let mut replayed: u64 = 0;
+        for n in (0..=self.backup_keep + 1).rev() {
+            let path: PathBuf = wal::segment_path(dir, n);
+            let segment: Segment = wal::read(&path).map_err(load_err)?;
+            for entry in segment.entries {
+                let op_id: u64 = entry.operation_id();
+                match entry {
+                    WalEntry::Idempotency { .. } if op_id <= base_op => {}
+                    WalEntry::Idempotency { .. } => self.apply_entry(entry),
+                    _ if op_id < self.next_operation_id() => {}
+                    _ if op_id == self.next_operation_id() => {
+                        self.apply_entry(entry);
+                        replayed += 1;
+                    }
+                    _ => {
+                        warn!(
+                            "WAL '{}' stopped at gap: operation {} expected, {} found",
+                            path.display(),
+                            self.next_operation_id(),
+                            op_id
+                        );
+                        return Ok(replayed);
+                    }
+                }
+            }
+            if segment.torn {
+                warn!(
+                    "WAL '{}' is torn after {} bytes",
+                    path.display(),
+                    segment.valid_len
+                );
+                // новые записи дописываются в текущий сегмент: оборванный конец отбрасывается
+                if n > 0 {
+                    return Ok(replayed);
+                }
+                wal::truncate(&path, segment.valid_len).map_err(load_err)?;
+            }
+        }
+
+        Ok(replayed)
+    }
+
+    /// Сериализация бд в снимок. None - предыдущий снимок еще записывается.
+    /// Очередь следующего снимка отсчитывается от этого, даже если его запись не удастся.
+    fn prepare(&self, dir: &Path) -> Result<Option<Snapshot>, AppError> {
+        if self.snapshotting.swap(true, Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let mut snapshot = Snapshot {
+            dir: dir.to_path_buf(),
+            keep: self.backup_keep,
+            payload: Vec::new(),
+            wal: self.wal.clone(),
+            running: Arc::clone(&self.snapshotting),
+        };
+        self.snapshot_op
+            .store(self.journal.len() as u64, Ordering::SeqCst);
+        // Получение данных из мапы и преобразование к строке с json отступами (4).
+        let obj = json!({
+            "accounts": &self.cache,
+            "journal": &self.journal,
+            "idempotency": &self.idempotency,
+        });
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+        let mut ser = serde_json::Serializer::with_formatter(&mut snapshot.payload, formatter);
+        obj.serialize(&mut ser).map_err(backup_err)?;
+
+        Ok(Some(snapshot))
+    }
+
+    /// Замена данных бд с перестроением индекса журнала.
+    pub fn restore(&mut self, accounts: HashMap<u32, Account>, journal: Vec<Record>) {
+        self.id = accounts.keys().max().copied().unwrap_or_default();
+        self.names = accounts
+            .values()
+            .filter_map(|acc| acc.name.clone().map(|name| (name, acc.id)))
+            .collect();
+        self.cache = accounts;
+        self.index = HashMap::new();
+        for (pos, record) in journal.iter().enumerate() {
+            for acc_id in record.accounts() {
+                self.index.entry(acc_id).or_default().push(pos);
+            }
+        }
+        self.journal = journal;
+    }
+}
+
+/// Трейт бд.
+/// Синхронный: методы записи и backup блокируют поток на I/O,
+/// из async кода вызываются через `Storages::blocking`.
 pub trait Cache {
-    /// Создание нового счета.
-    fn create_account(&mut self, account: Account) -> u32;
     /// Проверка наличия счета
-    fn check_key(&mut self, acc_id: u32) -> bool;
-    /// Получение счета для изменения баланса и добавления транзакций.
-    fn get_mut_account(&mut self, acc_id: u32) -> &mut Account;
+    fn check_key(&self, acc_id: u32) -> bool;
+    /// Поиск id счета по имени.
+    fn find_account(&self, name: &str) -> Option<u32>;
+    /// Id следующей операции журнала.
+    fn next_operation_id(&self) -> u64;
+    /// Запись операции в журнал (WAL без сброса на диск, sql-транзакция) под разделяемой
+    /// блокировкой бд: `build` получает id операции и нового счета и возвращает изменения бд.
+    /// Проверяет, что счета операции есть в бд и имя нового счета свободно.
+    /// Ошибка записи - операция не сохранена.
+    fn append(
+        &self,
+        build: &mut dyn FnMut(Sequence) -> Result<WalEntry, AppError>,
+    ) -> Result<Pending, AppError>;
+    /// Изменение бд в памяти записанной операцией (эксклюзивная блокировка).
+    /// Операции применяются в порядке id, после `Pending::sync`.
+    fn apply(&mut self, pending: Pending);
     /// Получение счета.
-    fn get_account(&self, acc_id: u32) -> &Account;
+    fn get_account(&self, acc_id: u32) -> Result<&Account, AppError>;
     /// Получение всех счетов.
     fn get_accounts(&self) -> &HashMap<u32, Account>;
-    /// Репликация бд в файл backup.json.
-    fn backup_store(&mut self);
-    /// Восстановление бд из файла backup.json.
+    /// Получение журнала операций.
+    fn get_journal(&self) -> &[Record];
+    /// Получение операций счета по индексу журнала (O(N) от числа операций счета).
+    fn get_account_journal(&self, acc_id: u32) -> Vec<&Record>;
+    /// Поиск результата операции по ключу идемпотентности.
+    fn find_idempotency(&self, key: &str) -> Option<&IdempotencyRecord>;
+    /// Периодический снимок бд (операции сохраняются в WAL при фиксации): сериализация
+    /// под разделяемой блокировкой, None - снимок не нужен. Файл пишется `Snapshot::store`
+    /// без блокировки бд, предыдущие реплики сохраняются в backup.json.1..keep.
+    fn snapshot(&self) -> Result<Option<Snapshot>, AppError>;
+    /// Восстановление бд из файла backup.json, при его повреждении - из предыдущих реплик.
     fn backup_load(&mut self) -> Result<(), AppError>;
+    /// Перечитывание бд из последнего сохраненного состояния после сбоя (паника под блокировкой).
+    /// Ошибка - данные в памяти восстановить нельзя.
+    fn reload(&mut self) -> Result<(), AppError>;
 }
 
 impl Cache for CacheImpl {
-    fn create_account(&mut self, mut account: Account) -> u32 {
-        // инкрементирование номера счета
-        self.id += 1;
-        // добавление id счета
-        account.id = self.id;
-        // добавление в кэш
-        self.cache.insert(self.id, account);
+    fn check_key(&self, acc_id: u32) -> bool {
+        self.cache.contains_key(&acc_id)
+    }
 
-        self.id
+    fn find_account(&self, name: &str) -> Option<u32> {
+        self.names.get(name).copied()
     }
 
-    fn check_key(&mut self, acc_id: u32) -> bool {
-        self.cache.contains_key(&acc_id)
+    fn next_operation_id(&self) -> u64 {
+        self.journal.len() as u64 + 1
     }
 
-    fn get_mut_account(&mut self, acc_id: u32) -> &mut Account {
-        self.cache.get_mut(&acc_id).unwrap()
+    fn append(
+        &self,
+        build: &mut dyn FnMut(Sequence) -> Result<WalEntry, AppError>,
+    ) -> Result<Pending, AppError> {
+        // позиция конца записи в WAL
+        let mut end: Option<u64> = None;
+        let entry: WalEntry = self.append_with(build, &mut |entry| {
+            if let Some(wal) = &self.wal {
+                end = Some(wal.append(entry).map_err(wal_err)?);
+            }
+            Ok(())
+        })?;
+
+        Ok(Pending {
+            entry,
+            wal: self.wal.clone().zip(end),
+        })
+    }
+
+    fn apply(&mut self, pending: Pending) {
+        self.apply_entry(pending.entry);
     }
 
-    fn get_account(&self, acc_id: u32) -> &Account {
-        self.cache.get(&acc_id).unwrap()
+    fn get_account(&self, acc_id: u32) -> Result<&Account, AppError> {
+        self.cache
+            .get(&acc_id)
+            .ok_or_else(|| AccountNotExists(acc_id.to_string()))
     }
 
     fn get_accounts(&self) -> &HashMap<u32, Account> {
         &self.cache
     }
 
-    fn backup_store(&mut self) {
-        // Получение данных из мапы и преобразование к строке с json отступами (4).
-        let obj = json!(&self.cache);
-        let mut buf = Vec::new();
-        let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
-        let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
-        obj.serialize(&mut ser).unwrap();
-        let payload: String = String::from_utf8(buf).unwrap();
+    fn get_journal(&self) -> &[Record] {
+        &self.journal
+    }
+
+    fn get_account_journal(&self, acc_id: u32) -> Vec<&Record> {
+        self.index
+            .get(&acc_id)
+            .map(|positions| positions.iter().map(|pos| &self.journal[*pos]).collect())
+            .unwrap_or_default()
+    }
 
-        // запись в файл для бэкапа
-        if let Err(err) = fs::write(Path::new(&PATH).join("backup.json"), payload) {
-            panic!("file backup.json write err: {}", err);
+    fn find_idempotency(&self, key: &str) -> Option<&IdempotencyRecord> {
+        self.idempotency.get(key)
+    }
+
+    fn snapshot(&self) -> Result<Option<Snapshot>, AppError> {
+        let Some(dir) = &self.backup_dir else {
+            return Ok(None);
+        };
+        // операции уже в WAL, периодический снимок бд
+        let done: u64 = self.snapshot_op.load(Ordering::SeqCst);
+        if (self.journal.len() as u64).saturating_sub(done) < self.snapshot_every {
+            return Ok(None);
         }
+        self.prepare(dir)
     }
 
     fn backup_load(&mut self) -> Result<(), AppError> {
-        // чтение файла backup.json
-        let backup_payload: String =
-            fs::read_to_string(Path::new(&PATH).join("backup.json")).map_err(|_| BackupLoadFile)?;
-        // проверка на пустую реплику
-        if backup_payload.is_empty() {
-            return Err(EmptyBackupFile);
-        }
-        // backup
-        let backup_bd: HashMap<u32, Account> =
-            serde_json::from_str(&backup_payload).map_err(|_| InvalidBackupFile)?;
-        self.cache = backup_bd;
+        let dir: PathBuf = self.backup_dir.clone().ok_or(BackupLoadFile)?;
+        // снимок backup.json, при ошибке - предыдущие реплики от новых к старым
+        let mut last_err: Option<AppError> = None;
+        let mut snapshot: Option<BackupFile> = None;
+        for n in 0..=self.backup_keep {
+            let path: PathBuf = backup_path(&dir, n);
+            match read_backup(&path) {
+                Ok(backup_bd) => {
+                    if n > 0 {
+                        warn!("backup restored from previous replica '{}'", path.display());
+                    }
+                    snapshot = Some(backup_bd);
+                    break;
+                }
+                // отсутствующие реплики пропускаются (до первого снимка есть только WAL)
+                Err(BackupLoadFile) => {}
+                Err(err) => {
+                    warn!("backup replica '{}' skipped: {}", path.display(), err);
+                    last_err.get_or_insert(err);
+                }
+            }
+        }
+        // без снимка - повтор WAL с пустой бд
+        let backup_bd: BackupFile = match (snapshot, last_err) {
+            (Some(snapshot), _) => snapshot,
+            (None, Some(err)) => return Err(err),
+            (None, None) => BackupFile::default(),
+        };
+        let has_snapshot: bool = !backup_bd.journal.is_empty() || !backup_bd.accounts.is_empty();
+        self.restore(backup_bd.accounts, backup_bd.journal);
+        self.idempotency = backup_bd.idempotency;
+        *self.snapshot_op.get_mut() = self.journal.len() as u64;
+        // записанные, но не примененные операции уже загружены из WAL
+        *self
+            .reserved
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Sequence::default();
+        if let Some(wal) = &self.wal {
+            wal.reset();
+        }
+        // хвост журнала после снимка
+        let replayed: u64 = self.replay_wal(&dir)?;
+        if !has_snapshot && replayed == 0 {
+            return Err(BackupLoadFile);
+        }
 
         Ok(())
     }
+
+    fn reload(&mut self) -> Result<(), AppError> {
+        if self.backup_dir.is_none() {
+            return Err(StorageUnavailable(
+                "in-memory storage without backup has no stored state".to_string(),
+            ));
+        }
+        match self.backup_load() {
+            // ничего не сохранено: загрузка уже сбросила бд в пустую
+            Err(BackupLoadFile) => Ok(()),
+            result => result,
+        }
+    }
+}
+
+/// Путь к реплике: 0 - backup.json, n - n-я предыдущая реплика backup.json.n.
+fn backup_path(dir: &Path, n: usize) -> PathBuf {
+    match n {
+        0 => dir.join(BACKUP_FILE),
+        n => dir.join(format!("{}.{}", BACKUP_FILE, n)),
+    }
+}
+
+/// Чтение и разбор файла реплики.
+fn read_backup(path: &Path) -> Result<BackupFile, AppError> {
+    let backup_payload: String = fs::read_to_string(path).map_err(|_| BackupLoadFile)?;
+    // проверка на пустую реплику
+    if backup_payload.is_empty() {
+        return Err(EmptyBackupFile);
+    }
+    match serde_json::from_str(&backup_payload) {
+        Ok(backup_bd) => Ok(backup_bd),
+        // старый формат файла - только счета, без журнала
+        Err(_) => Ok(BackupFile {
+            accounts: serde_json::from_str(&backup_payload).map_err(|_| InvalidBackupFile)?,
+            journal: Vec::new(),
+            idempotency: HashMap::new(),
+        }),
+    }
+}
+
+/// Запись файла со сбросом данных на диск (fsync).
+fn write_synced(path: &Path, payload: &[u8]) -> io::Result<()> {
+    let mut file: File = File::create(path)?;
+    file.write_all(payload)?;
+    file.sync_all()
+}
+
+/// Сдвиг реплик: backup.json.n -> backup.json.n+1, самая старая (keep) перезаписывается.
+/// Текущий backup.json остается на месте (жесткая ссылка), чтобы замена была атомарной.
+fn rotate(dir: &Path, keep: usize) -> io::Result<()> {
+    if keep == 0 || !backup_path(dir, 0).exists() {
+        return Ok(());
+    }
+    for n in (1..keep).rev() {
+        let from: PathBuf = backup_path(dir, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(dir, n + 1))?;
+        }
+    }
+    let prev: PathBuf = backup_path(dir, 1);
+    if prev.exists() {
+        fs::remove_file(&prev)?;
+    }
+    // файловые системы без жестких ссылок - копия
+    fs::hard_link(backup_path(dir, 0), &prev)
+        .or_else(|_| fs::copy(backup_path(dir, 0), &prev).map(|_| ()))
+}
+
+/// Сброс на диск записи директории (переименования файлов).
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Сброс на диск записи директории (на этой платформе не поддерживается).
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Ошибка записи снимка или WAL.
+fn backup_err(err: impl std::fmt::Display) -> AppError {
+    StorageFailure(format!("backup write error: {}", err))
+}
+
+/// Ошибка дозаписи WAL: ошибка записи - операция не сохранена,
+/// ошибка сброса на диск - содержимое WAL неизвестно, запись закрыта.
+fn wal_err(err: AppendError) -> AppError {
+    match err {
+        AppendError::Write(_) => backup_err(err),
+        AppendError::Sync(_) | AppendError::Broken(_) => StorageUnavailable(err.to_string()),
+    }
+}
+
+/// Ошибка чтения WAL.
+fn load_err(err: io::Error) -> AppError {
+    StorageFailure(format!("WAL read error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::account::Status;
+    use crate::domain::entities::journal::Command;
+    use crate::domain::entities::money::Money;
+    use crate::domain::entities::transaction::{Operation, Transaction};
+    use std::fs::OpenOptions;
+
+    /// Фиксация операции: запись в WAL, сброс на диск и изменение бд в памяти.
+    fn commit(db: &mut CacheImpl, mut build: impl FnMut(Sequence) -> WalEntry) -> u64 {
+        let pending: Pending = db.append(&mut |seq| Ok(build(seq))).unwrap();
+        pending.sync().unwrap();
+        let op_id: u64 = pending.operation_id();
+        db.apply(pending);
+        op_id
+    }
+
+    /// Новый счет.
+    fn open(db: &mut CacheImpl, name: Option<&str>) -> u32 {
+        let mut acc_id: u32 = 0;
+        commit(db, |seq| {
+            acc_id = seq.account_id;
+            let account = Account {
+                id: seq.account_id,
+                name: name.map(str::to_string),
+                ..Account::new()
+            };
+            WalEntry::account(account, seq.operation_id, Utc::now())
+        });
+        acc_id
+    }
+
+    /// Пополнение счета на `minor` копеек с ключом идемпотентности.
+    fn deposit(db: &mut CacheImpl, acc_id: u32, minor: i64, key: Option<&str>) -> u64 {
+        let account: &Account = db.get_account(acc_id).unwrap();
+        let tx = Transaction::new(
+            account.transactions.len() as u32,
+            Operation::Replenish,
+            account.balance,
+            Money::from_minor(minor),
+            Money::from_minor(account.balance.minor() + minor),
+        );
+        let command = Command::Replenish {
+            account_id: acc_id,
+            value: tx.delta,
+            fee: Money::ZERO,
+            reference: None,
+        };
+        let now: DateTime<Utc> = Utc::now();
+        let saved = key.map(|key| {
+            let record = IdempotencyRecord::new(key.into(), "{}".into(), "{}".into(), now);
+            (record, now - chrono::TimeDelta::hours(1))
+        });
+        commit(db, |seq| {
+            WalEntry::commit(
+                seq.operation_id,
+                command.clone(),
+                vec![(acc_id, tx.clone())],
+                now,
+                saved.clone(),
+            )
+        })
+    }
+
+    /// Периодический снимок после операции.
+    fn backup(db: &CacheImpl) {
+        if let Some(snapshot) = db.snapshot().unwrap() {
+            snapshot.store().unwrap();
+        }
+    }
+
+    /// Бд, загруженная из директории реплики.
+    fn load(dir: &Path, snapshot_every: u64) -> CacheImpl {
+        let mut db: CacheImpl = CacheImpl::with_backup(dir).with_snapshots(snapshot_every);
+        db.backup_load().unwrap();
+        db
+    }
+
+    /// Счета, журнал и ключи идемпотентности бд.
+    fn state(
+        db: &CacheImpl,
+    ) -> (
+        HashMap<u32, Account>,
+        Vec<Record>,
+        HashMap<String, IdempotencyRecord>,
+    ) {
+        (db.cache.clone(), db.journal.clone(), db.idempotency.clone())
+    }
+
+    #[test]
+    fn restart_recovers_snapshot_and_wal_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path()).with_snapshots(3);
+        let alice: u32 = open(&mut db, Some("alice"));
+        let bob: u32 = open(&mut db, None);
+        deposit(&mut db, alice, 1000, Some("key-1"));
+        backup(&db);
+        deposit(&mut db, bob, 250, None);
+        commit(&mut db, |seq| {
+            let command = Command::Freeze { account_id: bob };
+            WalEntry::status(seq.operation_id, command, bob, Status::Frozen, Utc::now())
+        });
+        backup(&db);
+
+        // снимок после 3-й операции, остальные - в WAL
+        assert!(backup_path(dir.path(), 0).exists());
+        let tail: Segment = wal::read(&wal::segment_path(dir.path(), 0)).unwrap();
+        assert_eq!(tail.entries.len(), 2);
+
+        let restored: CacheImpl = load(dir.path(), 3);
+        assert_eq!(state(&restored), state(&db));
+        assert_eq!(restored.find_account("alice"), Some(alice));
+        assert_eq!(restored.next_operation_id(), 6);
+    }
+
+    #[test]
+    fn restart_without_snapshot_replays_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path());
+        let acc_id: u32 = open(&mut db, None);
+        deposit(&mut db, acc_id, 500, Some("key-1"));
+
+        assert!(!backup_path(dir.path(), 0).exists());
+        assert_eq!(state(&load(dir.path(), SNAPSHOT_EVERY)), state(&db));
+    }
+
+    #[test]
+    fn torn_wal_tail_is_cut_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path());
+        let acc_id: u32 = open(&mut db, None);
+        deposit(&mut db, acc_id, 500, None);
+        let path: PathBuf = wal::segment_path(dir.path(), 0);
+        let valid_len: u64 = fs::metadata(&path).unwrap().len();
+        // сбой посреди дозаписи следующей операции
+        let mut file: File = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"kind":"commit","entries":[[1,{"id""#)
+            .unwrap();
+        drop(file);
+
+        let mut restored: CacheImpl = load(dir.path(), SNAPSHOT_EVERY);
+        assert_eq!(state(&restored), state(&db));
+        assert_eq!(fs::metadata(&path).unwrap().len(), valid_len);
+
+        // новые операции дописываются после корректного начала
+        deposit(&mut restored, acc_id, 100, None);
+        let reloaded: CacheImpl = load(dir.path(), SNAPSHOT_EVERY);
+        assert_eq!(state(&reloaded), state(&restored));
+        assert_eq!(reloaded.cache[&acc_id].balance, Money::from_minor(600));
+    }
+
+    #[test]
+    fn failed_wal_write_leaves_memory_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        // на месте сегмента WAL - директория: дозапись невозможна
+        fs::create_dir(wal::segment_path(dir.path(), 0)).unwrap();
+        let db: CacheImpl = CacheImpl::with_backup(dir.path());
+
+        let created = db.append(&mut |seq| {
+            Ok(WalEntry::account(
+                Account::new(),
+                seq.operation_id,
+                Utc::now(),
+            ))
+        });
+
+        assert!(matches!(created, Err(StorageFailure(_))));
+        assert!(db.cache.is_empty());
+        assert!(db.journal.is_empty());
+        assert_eq!(db.id, 0);
+    }
+
+    #[test]
+    fn snapshots_rotate_with_wal_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path())
+            .with_rotation(2)
+            .with_snapshots(1);
+        let acc_id: u32 = open(&mut db, None);
+        backup(&db);
+        for minor in 1..=5 {
+            deposit(&mut db, acc_id, minor, None);
+            backup(&db);
+        }
+
+        // 2 предыдущие реплики и 3 предыдущих сегмента WAL
+        assert!(backup_path(dir.path(), 2).exists());
+        assert!(!backup_path(dir.path(), 3).exists());
+        assert!(wal::segment_path(dir.path(), 3).exists());
+        assert!(!wal::segment_path(dir.path(), 4).exists());
+        assert!(!dir.path().join("backup.json.tmp").exists());
+
+        // поврежденный backup.json: предыдущая реплика и операции после нее из WAL
+        fs::write(backup_path(dir.path(), 0), b"{").unwrap();
+        let mut restored: CacheImpl = CacheImpl::with_backup(dir.path()).with_rotation(2);
+        restored.backup_load().unwrap();
+        assert_eq!(state(&restored), state(&db));
+    }
+
+    #[test]
+    fn operations_during_snapshot_write_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path())
+            .with_rotation(0)
+            .with_snapshots(1);
+        let acc_id: u32 = open(&mut db, None);
+        let snapshot: Snapshot = db.snapshot().unwrap().unwrap();
+        // операция фиксируется, пока снимок пишется без блокировки бд
+        deposit(&mut db, acc_id, 700, Some("key-1"));
+        assert!(db.snapshot().unwrap().is_none());
+        snapshot.store().unwrap();
+        drop(snapshot);
+
+        // операция не вошла в снимок и осталась в предыдущем сегменте WAL
+        let previous: Segment = wal::read(&wal::segment_path(dir.path(), 1)).unwrap();
+        assert_eq!(previous.entries.len(), 2);
+        let mut restored: CacheImpl = CacheImpl::with_backup(dir.path()).with_rotation(0);
+        restored.backup_load().unwrap();
+        assert_eq!(state(&restored), state(&db));
+    }
+
+    #[test]
+    fn failed_snapshot_keeps_operations_in_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path()).with_snapshots(1);
+        let acc_id: u32 = open(&mut db, None);
+        // на месте временного файла - директория: снимок не записывается
+        let tmp: PathBuf = dir.path().join("backup.json.tmp");
+        fs::create_dir(&tmp).unwrap();
+        let snapshot: Snapshot = db.snapshot().unwrap().unwrap();
+        assert!(matches!(snapshot.store(), Err(StorageFailure(_))));
+        drop(snapshot);
+        assert!(!backup_path(dir.path(), 0).exists());
+        assert_eq!(state(&load(dir.path(), 1)), state(&db));
+
+        // следующий снимок - через snapshot_every операций
+        assert!(db.snapshot().unwrap().is_none());
+        fs::remove_dir(&tmp).unwrap();
+        deposit(&mut db, acc_id, 300, None);
+        backup(&db);
+        assert!(backup_path(dir.path(), 0).exists());
+        assert_eq!(state(&load(dir.path(), 1)), state(&db));
+    }
+
+    #[test]
+    fn stale_snapshot_tmp_file_is_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path()).with_snapshots(1);
+        let acc_id: u32 = open(&mut db, None);
+        backup(&db);
+        deposit(&mut db, acc_id, 100, None);
+        // сбой до переименования: недописанный временный файл
+        fs::write(dir.path().join("backup.json.tmp"), b"{\"accounts\":").unwrap();
+
+        let mut restored: CacheImpl = load(dir.path(), 1);
+        assert_eq!(state(&restored), state(&db));
+        deposit(&mut restored, acc_id, 100, None);
+        backup(&restored);
+        assert_eq!(state(&load(dir.path(), 1)), state(&restored));
+    }
 }
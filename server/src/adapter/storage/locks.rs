@@ -0,0 +1,86 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Число шардов блокировок по умолчанию.
+pub const LOCK_SHARDS: usize = 64;
+
+/// Ключ блокировки операции.
+#[derive(Debug, Clone, Copy)]
+pub enum LockKey<'a> {
+    /// Счет, по которому проводится операция.
+    Account(u32),
+    /// Ключ идемпотентности: повтор запроса ждет окончания исходного.
+    Idempotency(&'a str),
+    /// Имя нового счета: счета с одним именем открываются по очереди.
+    Name(&'a str),
+}
+
+/// Шардированные блокировки счетов: счет блокируется мьютексом шарда `id % shards`.
+/// Операции по счетам разных шардов выполняются параллельно.
+#[derive(Debug)]
+pub struct Locks {
+    shards: Vec<Mutex<()>>,
+}
+
+/// Захваченные блокировки шардов, освобождаются при drop.
+#[derive(Debug)]
+pub struct LockGuard<'a> {
+    _guards: Vec<MutexGuard<'a, ()>>,
+}
+
+impl Locks {
+    /// Конструктор таблицы из `shards` блокировок (не меньше одной).
+    pub fn new(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    /// Номер шарда ключа.
+    fn shard(&self, key: &LockKey) -> usize {
+        match key {
+            LockKey::Account(id) => *id as usize % self.shards.len(),
+            LockKey::Idempotency(key) | LockKey::Name(key) => {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+                hasher.finish() as usize % self.shards.len()
+            }
+        }
+    }
+
+    /// Блокировка шардов ключей операции.
+    /// Шарды захватываются строго по возрастанию номера: операции с общими счетами
+    /// (перевод A->B и B->A) берут блокировки в одном порядке, взаимная блокировка невозможна.
+    pub fn lock(&self, keys: &[LockKey]) -> LockGuard<'_> {
+        let mut shards: Vec<usize> = keys.iter().map(|key| self.shard(key)).collect();
+        shards.sort_unstable();
+        shards.dedup();
+        self.acquire(shards)
+    }
+
+    /// Блокировка всех шардов (замена всей бд).
+    pub fn lock_all(&self) -> LockGuard<'_> {
+        self.acquire(0..self.shards.len())
+    }
+
+    fn acquire(&self, shards: impl IntoIterator<Item = usize>) -> LockGuard<'_> {
+        LockGuard {
+            // мьютекс шарда не защищает данных, отравление после паники не важно
+            _guards: shards
+                .into_iter()
+                .map(|n| {
+                    self.shards[n]
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Default for Locks {
+    fn default() -> Self {
+        Self::new(LOCK_SHARDS)
+    }
+}
@@ -1,29 +1,445 @@
 pub mod cache;
+pub mod locks;
+pub mod sqlite;
+pub mod wal;
 
-use crate::adapter::storage::cache::{Cache, CacheImpl};
-use std::sync::{Arc, RwLock};
+use crate::adapter::storage::cache::{Cache, CacheImpl, Pending, Sequence, Snapshot};
+use crate::adapter::storage::locks::{LockGuard, LockKey, Locks};
+use crate::adapter::storage::wal::WalEntry;
+use crate::domain::entities::fee::FeeSchedule;
+use crate::domain::entities::health::{HealthResponse, HealthStatus, RecoveryReport};
+use crate::domain::entities::idempotency::IDEMPOTENCY_WINDOW_SECS;
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::{StorageFailure, StorageUnavailable};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+use tokio::task;
+use tracing::{error, warn};
+
+/// Проверка очереди применения операций, если уведомление не пришло (бд перечитана после сбоя).
+const APPLY_WAIT: Duration = Duration::from_millis(10);
 
 /// Тип 'state' сервиса.
-pub type StorageState = Arc<RwLock<Storage>>;
+pub type StorageState = Arc<Storage>;
+
+/// Тип реализации бд, выбираемой при старте сервиса.
+pub type DynCache = dyn Cache + Send + Sync;
 
 /// Структура 'state' сервиса.
-#[derive(Debug, Default)]
+/// Чтения берут разделяемую блокировку бд. Запись блокирует шарды своих счетов,
+/// рассчитывает проводки и пишет операцию в WAL под разделяемой блокировкой,
+/// сбрасывает WAL на диск без блокировки бд (групповой fsync) и берет эксклюзивную
+/// только на изменение бд в памяти. Снимок бд пишется без эксклюзивной блокировки.
+/// Паника под эксклюзивной блокировкой (отравление) исправляется перечитыванием бд
+/// из сохраненного состояния, если это невозможно - бд закрывается до перезапуска.
+/// Отказ WAL (ошибка сброса на диск) тоже закрывает бд.
 pub struct Storage {
-    pub db: CacheImpl,
+    pub db: RwLock<Box<DynCache>>,
+    // блокировки счетов операций записи
+    pub locks: Locks,
+    // тарифы комиссий
+    pub fees: FeeSchedule,
+    // фиксированное время операций (повтор журнала), None - текущее время
+    pub clock: RwLock<Option<DateTime<Utc>>>,
+    // окно хранения ключей идемпотентности
+    pub idempotency_window: TimeDelta,
+    // идет восстановление бд при старте: операции записи отклоняются
+    pub recovering: AtomicBool,
+    // причина закрытия бд после сбоя: запросы отклоняются до перезапуска
+    pub failure: RwLock<Option<String>>,
+    // итог загрузки бд при старте
+    pub recovery: RwLock<Option<RecoveryReport>>,
+    // id последней примененной в памяти операции: очередь применения записанных операций
+    pub applied: Mutex<u64>,
+    pub applied_cv: Condvar,
+}
+
+impl Storage {
+    /// Конструктор 'state' с выбранной реализацией бд (без комиссий).
+    pub fn new(db: Box<DynCache>) -> Self {
+        Self {
+            db: RwLock::new(db),
+            locks: Locks::default(),
+            fees: FeeSchedule::default(),
+            clock: RwLock::new(None),
+            idempotency_window: TimeDelta::seconds(IDEMPOTENCY_WINDOW_SECS as i64),
+            recovering: AtomicBool::new(false),
+            failure: RwLock::new(None),
+            recovery: RwLock::new(None),
+            applied: Mutex::new(0),
+            applied_cv: Condvar::new(),
+        }
+    }
+
+    /// Установка тарифов комиссий.
+    pub fn with_fees(mut self, fees: FeeSchedule) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Установка окна хранения ключей идемпотентности.
+    pub fn with_idempotency_window(mut self, window: TimeDelta) -> Self {
+        self.idempotency_window = window;
+        self
+    }
+
+    /// Установка числа шардов блокировок счетов.
+    pub fn with_lock_shards(mut self, shards: usize) -> Self {
+        self.locks = Locks::new(shards);
+        self
+    }
+
+    /// Начало и окончание восстановления бд при старте.
+    pub fn set_recovering(&self, recovering: bool) {
+        self.recovering.store(recovering, Ordering::SeqCst);
+    }
+
+    /// Итог загрузки бд при старте (отдается в проверке состояния).
+    pub fn set_recovery(&self, report: RecoveryReport) {
+        *self
+            .recovery
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(report);
+    }
+
+    /// Изменение бд в памяти записанной операцией в порядке id операций:
+    /// операция ждет, пока применены все предыдущие (их сброс на диск мог закончиться позже).
+    /// Операции, уже загруженные перечитыванием бд после сбоя, пропускаются.
+    fn apply(&self, pending: Pending) -> Result<(), AppError> {
+        let op_id: u64 = pending.operation_id();
+        let mut applied = self.applied.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let mut db = self.db_mut()?;
+            let next: u64 = db.next_operation_id();
+            if next >= op_id {
+                if next == op_id {
+                    db.apply(pending);
+                }
+                *applied = db.next_operation_id() - 1;
+                drop(db);
+                self.applied_cv.notify_all();
+                return Ok(());
+            }
+            *applied = next - 1;
+            drop(db);
+            // очередь проверяется заново и по таймауту: бд могла быть перечитана после сбоя
+            while *applied + 1 < op_id {
+                let (guard, wait) = self
+                    .applied_cv
+                    .wait_timeout(applied, APPLY_WAIT)
+                    .unwrap_or_else(PoisonError::into_inner);
+                applied = guard;
+                if wait.timed_out() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Причина закрытия бд после сбоя.
+    fn failure(&self) -> Option<String> {
+        self.failure
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Ошибка для запросов к закрытой бд.
+    fn closed(&self) -> Result<(), AppError> {
+        match self.failure() {
+            Some(reason) => Err(StorageUnavailable(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Исправление бд после паники под эксклюзивной блокировкой.
+    /// Операция могла изменить данные в памяти частично, поэтому бд перечитывается
+    /// из последнего сохраненного состояния (снимок и WAL, файл SQLite).
+    /// Если перечитать нельзя (in-memory без backup, ошибка загрузки), бд закрывается.
+    fn heal<'a>(
+        &'a self,
+        mut db: RwLockWriteGuard<'a, Box<DynCache>>,
+    ) -> Result<RwLockWriteGuard<'a, Box<DynCache>>, AppError> {
+        // бд могла быть закрыта, пока поток ждал блокировку
+        self.closed()?;
+        warn!("storage lock poisoned by a panic, reloading stored state");
+        match db.reload() {
+            Ok(()) => {
+                self.db.clear_poison();
+                warn!("storage reloaded from stored state");
+                Ok(db)
+            }
+            Err(err) => Err(self.close(format!("reload after panic failed: {}", err))),
+        }
+    }
+
+    /// Закрытие бд до перезапуска: запросы отклоняются, проверка состояния возвращает причину.
+    fn close(&self, reason: String) -> AppError {
+        let mut failure = self.failure.write().unwrap_or_else(PoisonError::into_inner);
+        if failure.is_none() {
+            error!("storage closed until restart: {}", reason);
+            *failure = Some(reason.clone());
+        }
+        StorageUnavailable(reason)
+    }
+
+    /// Ошибка фиксации операции. Отказ журнала (ошибка сброса на диск) закрывает бд:
+    /// содержимое WAL на диске неизвестно, перечитывание могло бы вернуть операции,
+    /// о неудаче которых клиенты уже получили ответ.
+    fn commit_err(&self, err: AppError) -> AppError {
+        match err {
+            StorageUnavailable(reason) => self.close(reason),
+            err => err,
+        }
+    }
+}
+
+/// По умолчанию - in-memory бд.
+impl Default for Storage {
+    fn default() -> Self {
+        Self::new(Box::<CacheImpl>::default())
+    }
+}
+
+impl fmt::Debug for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Storage")
+            .field("fees", &self.fees)
+            .field("idempotency_window", &self.idempotency_window)
+            .field("recovering", &self.recovering)
+            .finish_non_exhaustive()
+    }
 }
 
 /// Трейт для слоя usecases.
+/// Бд синхронная (std блокировки, файловый и SQLite I/O): хендлеры вызывают usecases
+/// через `blocking`, чтобы ожидание блокировок и диска не занимало потоки runtime.
+/// Асинхронный трейт не вводится: rusqlite и fsync все равно блокирующие и ушли бы в тот же
+/// пул потоков, а guard std блокировки нельзя держать через `.await`. Под эксклюзивной
+/// блокировкой нет ввода-вывода, диск ждут только запросы, которым он нужен.
 pub trait Storages {
-    type CacheImpl: Cache;
+    type CacheImpl: Cache + ?Sized;
+
+    /// Бд на чтение (разделяемая блокировка).
+    /// Ошибка - бд закрыта после сбоя.
+    fn db(&self) -> Result<RwLockReadGuard<'_, Box<Self::CacheImpl>>, AppError>;
+
+    /// Бд на запись (эксклюзивная блокировка): только изменение бд в памяти, без ввода-вывода.
+    /// Ошибка - бд закрыта после сбоя.
+    fn db_mut(&self) -> Result<RwLockWriteGuard<'_, Box<Self::CacheImpl>>, AppError>;
+
+    /// Фиксация операции записи (под блокировкой счетов операции): запись в журнал под
+    /// разделяемой блокировкой бд, сброс на диск без блокировок бд (групповой fsync),
+    /// изменение бд в памяти под эксклюзивной блокировкой в порядке id операций.
+    /// `build` получает id операции, нового счета и время операции, возвращает изменения бд
+    /// и ответ операции. Время выдается вместе с id: порядок времени совпадает с порядком операций.
+    fn commit<T, F>(&self, build: F) -> Result<T, AppError>
+    where
+        F: FnOnce(Sequence) -> Result<(WalEntry, T), AppError>;
+
+    /// Периодический снимок бд после операции записи: сериализация под разделяемой
+    /// блокировкой, запись файла - без блокировки бд. Ошибка снимка только логируется:
+    /// операция уже сохранена в WAL, снимок повторится через `snapshot_every` операций.
+    fn backup(&self);
+
+    /// Блокировка счетов и ключа идемпотентности операции записи
+    /// на время от расчета проводок до фиксации.
+    fn lock(&self, keys: &[LockKey]) -> LockGuard<'_>;
 
-    fn db(&mut self) -> &mut Self::CacheImpl;
+    /// Блокировка всех счетов (замена бд).
+    fn lock_all(&self) -> LockGuard<'_>;
+
+    fn fees(&self) -> &FeeSchedule;
+
+    /// Время проведения операции.
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Фиксация времени операций (None - текущее время).
+    fn set_clock(&self, clock: Option<DateTime<Utc>>);
+
+    /// Окно хранения ключей идемпотентности.
+    fn idempotency_window(&self) -> TimeDelta;
+
+    /// Идет восстановление бд при старте.
+    fn recovering(&self) -> bool;
+
+    /// Состояние бд: работает, восстанавливается при старте или закрыта после сбоя.
+    fn health(&self) -> HealthResponse;
+
+    /// Выполнение синхронной работы с бд в пуле блокирующих потоков tokio.
+    /// При отмене запроса (таймаут) начатая операция доводится до конца.
+    fn blocking<T, F>(self: &Arc<Self>, f: F) -> impl Future<Output = Result<T, AppError>> + Send
+    where
+        Self: Send + Sync + 'static,
+        T: Send + 'static,
+        F: FnOnce(Arc<Self>) -> Result<T, AppError> + Send + 'static,
+    {
+        let storage: Arc<Self> = Arc::clone(self);
+        async move {
+            task::spawn_blocking(move || f(storage))
+                .await
+                .map_err(|err| StorageFailure(format!("storage task failed: {}", err)))?
+        }
+    }
 }
 
-/// Имплементация Storages с &mut владением.
+/// Имплементация Storages с внутренними блокировками.
 impl Storages for Storage {
-    type CacheImpl = CacheImpl;
+    type CacheImpl = DynCache;
+
+    fn db(&self) -> Result<RwLockReadGuard<'_, Box<Self::CacheImpl>>, AppError> {
+        self.closed()?;
+        match self.db.read() {
+            Ok(db) => Ok(db),
+            // перечитывание под эксклюзивной блокировкой, затем чтение
+            Err(poisoned) => {
+                drop(poisoned);
+                drop(self.db_mut()?);
+                self.db
+                    .read()
+                    .map_err(|_| StorageUnavailable("storage lock poisoned".to_string()))
+            }
+        }
+    }
+
+    fn db_mut(&self) -> Result<RwLockWriteGuard<'_, Box<Self::CacheImpl>>, AppError> {
+        self.closed()?;
+        match self.db.write() {
+            Ok(db) => Ok(db),
+            Err(poisoned) => self.heal(poisoned.into_inner()),
+        }
+    }
+
+    fn commit<T, F>(&self, build: F) -> Result<T, AppError>
+    where
+        F: FnOnce(Sequence) -> Result<(WalEntry, T), AppError>,
+    {
+        let mut build: Option<F> = Some(build);
+        let mut output: Option<T> = None;
+        let pending: Result<Pending, AppError> = self.db()?.append(&mut |seq| {
+            let build: F = build
+                .take()
+                .ok_or_else(|| StorageFailure("operation is already built".to_string()))?;
+            // `append` вызывает `build` под резервацией id: время берется там же
+            let seq = Sequence {
+                timestamp: self.now(),
+                ..seq
+            };
+            let (entry, out) = build(seq)?;
+            output = Some(out);
+            Ok(entry)
+        });
+        let pending: Pending = pending.map_err(|err| self.commit_err(err))?;
+        pending.sync().map_err(|err| self.commit_err(err))?;
+        self.apply(pending)?;
+
+        output.ok_or_else(|| StorageFailure("operation is not built".to_string()))
+    }
+
+    fn backup(&self) {
+        let snapshot: Result<Option<Snapshot>, AppError> = self.db().and_then(|db| db.snapshot());
+        if let Err(err) = snapshot.and_then(|snapshot| snapshot.map_or(Ok(()), |s| s.store())) {
+            warn!("backup snapshot failed: {}", err);
+        }
+    }
+
+    fn lock(&self, keys: &[LockKey]) -> LockGuard<'_> {
+        self.locks.lock(keys)
+    }
+
+    fn lock_all(&self) -> LockGuard<'_> {
+        self.locks.lock_all()
+    }
+
+    fn fees(&self) -> &FeeSchedule {
+        &self.fees
+    }
+
+    // значение часов не может остаться частично записанным, отравление не важно
+    fn now(&self) -> DateTime<Utc> {
+        self.clock
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .unwrap_or_else(Utc::now)
+    }
+
+    fn set_clock(&self, clock: Option<DateTime<Utc>>) {
+        *self.clock.write().unwrap_or_else(PoisonError::into_inner) = clock;
+    }
+
+    fn idempotency_window(&self) -> TimeDelta {
+        self.idempotency_window
+    }
+
+    fn recovering(&self) -> bool {
+        self.recovering.load(Ordering::SeqCst)
+    }
+
+    fn health(&self) -> HealthResponse {
+        // бд после паники перечитывается сразу, а не при следующем запросе
+        if self.db.is_poisoned() {
+            drop(self.db_mut());
+        }
+        let recovery: Option<RecoveryReport> = self
+            .recovery
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some(reason) = self.failure() {
+            return HealthResponse::new(HealthStatus::Failed, Some(reason)).with_recovery(recovery);
+        }
+        if self.recovering() {
+            return HealthResponse::new(HealthStatus::Recovering, None);
+        }
+
+        HealthResponse::new(HealthStatus::Ok, None).with_recovery(recovery)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::storage::wal::Wal;
+    use crate::domain::entities::money::Money;
+    use crate::domain::entities::transaction::Operation::Replenish;
+    use crate::domain::usecases::account;
+    use std::io;
+
+    #[test]
+    fn wal_sync_failure_closes_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: CacheImpl = CacheImpl::with_backup(dir.path());
+        let wal: Arc<Wal> = cache.wal().unwrap();
+        let storage: Arc<Storage> = Arc::new(Storage::new(Box::new(cache)));
+        let id: u32 = account::new_account(Arc::clone(&storage), None)
+            .unwrap()
+            .account_id;
+
+        wal.fail_next_sync(io::ErrorKind::Other);
+        let deposit = || {
+            account::change_acc_balance(
+                &storage,
+                Money::from_minor(100),
+                id.into(),
+                Replenish,
+                None,
+                None,
+            )
+        };
+        assert!(matches!(deposit(), Err(StorageUnavailable(_))));
 
-    fn db(&mut self) -> &mut Self::CacheImpl {
-        &mut self.db
+        // бд закрыта до перезапуска: запросы отклоняются, состояние - failed с причиной
+        let health: HealthResponse = storage.health();
+        assert_eq!(health.status, HealthStatus::Failed);
+        assert!(health.reason.unwrap().contains("WAL sync error"));
+        assert!(matches!(deposit(), Err(StorageUnavailable(_))));
+        assert!(matches!(
+            account::balance(&storage, id.into(), None),
+            Err(StorageUnavailable(_))
+        ));
     }
 }
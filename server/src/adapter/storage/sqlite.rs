@@ -0,0 +1,685 @@
+use crate::adapter::storage::cache::{Cache, CacheImpl, Pending, Sequence, Snapshot};
+use crate::adapter::storage::wal::WalEntry;
+use crate::domain::entities::account::Account;
+use crate::domain::entities::idempotency::IdempotencyRecord;
+use crate::domain::entities::journal::Record;
+use crate::domain::entities::money::Money;
+use crate::domain::entities::transaction::{Operation, Transaction};
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::StorageFailure;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Схема бд.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS accounts (
+    id      INTEGER PRIMARY KEY,
+    status  TEXT    NOT NULL,
+    balance INTEGER NOT NULL,
+    name    TEXT
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    account_id INTEGER NOT NULL REFERENCES accounts (id),
+    id         INTEGER NOT NULL,
+    operation  TEXT    NOT NULL,
+    previous   INTEGER NOT NULL,
+    delta      INTEGER NOT NULL,
+    current    INTEGER NOT NULL,
+    timestamp  TEXT    NOT NULL DEFAULT '1970-01-01T00:00:00Z',
+    reference  TEXT,
+    counterparty INTEGER,
+    PRIMARY KEY (account_id, id)
+);
+CREATE TABLE IF NOT EXISTS journal (
+    id           INTEGER PRIMARY KEY,
+    command      TEXT    NOT NULL,
+    transactions TEXT    NOT NULL,
+    timestamp    TEXT    NOT NULL DEFAULT '1970-01-01T00:00:00Z'
+);
+CREATE TABLE IF NOT EXISTS journal_index (
+    account_id   INTEGER NOT NULL,
+    operation_id INTEGER NOT NULL REFERENCES journal (id),
+    PRIMARY KEY (account_id, operation_id)
+);
+CREATE TABLE IF NOT EXISTS idempotency (
+    key       TEXT PRIMARY KEY,
+    request   TEXT NOT NULL,
+    response  TEXT NOT NULL,
+    timestamp TEXT NOT NULL
+);
+";
+
+/// Структура db (SQLite).
+/// Все изменения пишутся в SQLite в рамках sql-транзакции, чтение идет из in-memory копии,
+/// которая обновляется только после успешного коммита.
+#[derive(Debug)]
+pub struct SqliteImpl {
+    conn: Mutex<Connection>,
+    memory: CacheImpl,
+}
+
+impl SqliteImpl {
+    /// Открытие (создание) файла бд и загрузка данных в память.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        let conn: Connection = Connection::open(path).map_err(storage_err)?;
+        conn.execute_batch(SCHEMA).map_err(storage_err)?;
+        Self::migrate(&conn).map_err(storage_err)?;
+
+        let mut db = Self {
+            conn: Mutex::new(conn),
+            memory: CacheImpl::default(),
+        };
+        db.backup_load()?;
+
+        Ok(db)
+    }
+
+    /// Соединение с бд.
+    /// После паники с захваченным соединением sql-транзакция уже откачена (drop), соединение исправно.
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Обновление схемы бд, созданной предыдущими версиями сервиса.
+    fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+        // имена счетов
+        add_column(conn, "accounts", "name", "TEXT")?;
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS accounts_name ON accounts (name)",
+            [],
+        )?;
+        // время, назначение и контрагент транзакций
+        add_column(
+            conn,
+            "transactions",
+            "timestamp",
+            "TEXT NOT NULL DEFAULT '1970-01-01T00:00:00Z'",
+        )?;
+        add_column(conn, "transactions", "reference", "TEXT")?;
+        add_column(conn, "transactions", "counterparty", "INTEGER")?;
+        // время операций журнала
+        add_column(
+            conn,
+            "journal",
+            "timestamp",
+            "TEXT NOT NULL DEFAULT '1970-01-01T00:00:00Z'",
+        )?;
+
+        Ok(())
+    }
+
+    /// Чтение журнала операций из SQLite.
+    fn load_journal(conn: &Connection) -> rusqlite::Result<Vec<Record>> {
+        let mut stmt =
+            conn.prepare("SELECT id, command, transactions, timestamp FROM journal ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Record::new(
+                row.get(0)?,
+                from_json(&row.get::<_, String>(1)?)?,
+                from_json(&row.get::<_, String>(2)?)?,
+                row.get(3)?,
+            ))
+        })?;
+
+        rows.collect()
+    }
+
+    /// Чтение ключей идемпотентности из SQLite.
+    fn load_idempotency(conn: &Connection) -> rusqlite::Result<HashMap<String, IdempotencyRecord>> {
+        let mut stmt = conn.prepare("SELECT key, request, response, timestamp FROM idempotency")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(IdempotencyRecord::new(
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+            ))
+        })?;
+
+        rows.map(|row| row.map(|record| (record.key.clone(), record)))
+            .collect()
+    }
+
+    /// Чтение всех счетов и транзакций из SQLite.
+    fn load(conn: &Connection) -> rusqlite::Result<HashMap<u32, Account>> {
+        let mut accounts: HashMap<u32, Account> = HashMap::new();
+        // счета
+        let mut stmt = conn.prepare("SELECT id, name, status, balance FROM accounts")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Account {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                status: from_text(row.get(2)?)?,
+                balance: Money::from_minor(row.get(3)?),
+                transactions: Vec::new(),
+            })
+        })?;
+        for account in rows {
+            let account: Account = account?;
+            accounts.insert(account.id, account);
+        }
+        // транзакции в порядке их проведения
+        let mut stmt = conn.prepare(
+            "SELECT account_id, id, operation, previous, delta, current,
+                    timestamp, reference, counterparty
+             FROM transactions ORDER BY account_id, id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let mut tx = Transaction::new(
+                row.get(1)?,
+                from_text::<Operation>(row.get(2)?)?,
+                Money::from_minor(row.get(3)?),
+                Money::from_minor(row.get(4)?),
+                Money::from_minor(row.get(5)?),
+            );
+            tx.timestamp = row.get(6)?;
+            tx.reference = row.get(7)?;
+            tx.counterparty = row.get(8)?;
+            Ok((row.get::<_, u32>(0)?, tx))
+        })?;
+        for row in rows {
+            let (acc_id, tx) = row?;
+            if let Some(account) = accounts.get_mut(&acc_id) {
+                account.transactions.push(tx);
+            }
+        }
+
+        Ok(accounts)
+    }
+}
+
+impl Cache for SqliteImpl {
+    fn check_key(&self, acc_id: u32) -> bool {
+        self.memory.check_key(acc_id)
+    }
+
+    fn find_account(&self, name: &str) -> Option<u32> {
+        self.memory.find_account(name)
+    }
+
+    fn next_operation_id(&self) -> u64 {
+        self.memory.next_operation_id()
+    }
+
+    fn append(
+        &self,
+        build: &mut dyn FnMut(Sequence) -> Result<WalEntry, AppError>,
+    ) -> Result<Pending, AppError> {
+        // операции нумеруются в памяти и пишутся в SQLite в том же порядке
+        let entry: WalEntry = self.memory.append_with(build, &mut |entry| {
+            let mut conn = self.conn();
+            let sql_tx = conn.transaction().map_err(storage_err)?;
+            write_entry(&sql_tx, entry)?;
+            sql_tx.commit().map_err(storage_err)
+        })?;
+
+        Ok(Pending::new(entry))
+    }
+
+    fn apply(&mut self, pending: Pending) {
+        self.memory.apply(pending);
+    }
+
+    fn get_account(&self, acc_id: u32) -> Result<&Account, AppError> {
+        self.memory.get_account(acc_id)
+    }
+
+    fn get_accounts(&self) -> &HashMap<u32, Account> {
+        self.memory.get_accounts()
+    }
+
+    fn get_journal(&self) -> &[Record] {
+        self.memory.get_journal()
+    }
+
+    fn get_account_journal(&self, acc_id: u32) -> Vec<&Record> {
+        self.memory.get_account_journal(acc_id)
+    }
+
+    fn find_idempotency(&self, key: &str) -> Option<&IdempotencyRecord> {
+        self.memory.find_idempotency(key)
+    }
+
+    fn snapshot(&self) -> Result<Option<Snapshot>, AppError> {
+        // данные уже сохранены в SQLite при коммите
+        Ok(None)
+    }
+
+    fn backup_load(&mut self) -> Result<(), AppError> {
+        let conn = self.conn();
+        let accounts: HashMap<u32, Account> = Self::load(&conn).map_err(storage_err)?;
+        let journal: Vec<Record> = Self::load_journal(&conn).map_err(storage_err)?;
+        let idempotency: HashMap<String, IdempotencyRecord> =
+            Self::load_idempotency(&conn).map_err(storage_err)?;
+        drop(conn);
+        self.memory.restore(accounts, journal);
+        self.memory.idempotency = idempotency;
+
+        Ok(())
+    }
+
+    fn reload(&mut self) -> Result<(), AppError> {
+        // файл бд - последнее сохраненное состояние
+        self.backup_load()
+    }
+}
+
+/// Запись операции в SQLite (в рамках sql-транзакции).
+fn write_entry(conn: &Connection, entry: &WalEntry) -> Result<(), AppError> {
+    match entry {
+        WalEntry::Account { account, record } => {
+            conn.execute(
+                "INSERT INTO accounts (id, name, status, balance) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    account.id,
+                    account.name,
+                    to_text(&account.status),
+                    account.balance.minor()
+                ],
+            )
+            .map_err(storage_err)?;
+            for tx in &account.transactions {
+                insert_tx(conn, account.id, tx).map_err(storage_err)?;
+            }
+            insert_record(conn, record).map_err(storage_err)?;
+        }
+        WalEntry::Commit {
+            entries,
+            record,
+            idempotency,
+            expired,
+        } => {
+            for (acc_id, tx) in entries {
+                let updated: usize = conn
+                    .execute(
+                        "UPDATE accounts SET balance = ?1 WHERE id = ?2",
+                        params![tx.current.minor(), acc_id],
+                    )
+                    .map_err(storage_err)?;
+                // счет не найден - sql-транзакция откатывается при drop
+                if updated == 0 {
+                    return Err(AppError::AccountNotExists(acc_id.to_string()));
+                }
+                insert_tx(conn, *acc_id, tx).map_err(storage_err)?;
+            }
+            insert_record(conn, record).map_err(storage_err)?;
+            // ответ по ключу идемпотентности - в той же sql-транзакции, что и операция
+            if let Some(saved) = idempotency {
+                insert_idempotency(conn, saved, *expired).map_err(storage_err)?;
+            }
+        }
+        WalEntry::Status {
+            account_id,
+            status,
+            record,
+        } => {
+            let updated: usize = conn
+                .execute(
+                    "UPDATE accounts SET status = ?1 WHERE id = ?2",
+                    params![to_text(status), account_id],
+                )
+                .map_err(storage_err)?;
+            // счет не найден - sql-транзакция откатывается при drop
+            if updated == 0 {
+                return Err(AppError::AccountNotExists(account_id.to_string()));
+            }
+            insert_record(conn, record).map_err(storage_err)?;
+        }
+        WalEntry::Idempotency { record, .. } => {
+            insert_idempotency(conn, record, None).map_err(storage_err)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Запись транзакции счета.
+fn insert_tx(conn: &Connection, acc_id: u32, tx: &Transaction) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO transactions
+         (account_id, id, operation, previous, delta, current, timestamp, reference, counterparty)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            acc_id,
+            tx.id,
+            to_text(&tx.operation),
+            tx.previous.minor(),
+            tx.delta.minor(),
+            tx.current.minor(),
+            tx.timestamp,
+            tx.reference,
+            tx.counterparty
+        ],
+    )
+}
+
+/// Запись операции в журнал и индекс журнала по счетам.
+fn insert_record(conn: &Connection, record: &Record) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO journal (id, command, transactions, timestamp) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            record.id,
+            to_json(&record.command)?,
+            to_json(&record.transactions)?,
+            record.timestamp
+        ],
+    )?;
+    for acc_id in record.accounts() {
+        conn.execute(
+            "INSERT INTO journal_index (account_id, operation_id) VALUES (?1, ?2)",
+            params![acc_id, record.id],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Сохранение ответа по ключу идемпотентности с удалением ключей раньше `expired`.
+fn insert_idempotency(
+    conn: &Connection,
+    record: &IdempotencyRecord,
+    expired: Option<DateTime<Utc>>,
+) -> rusqlite::Result<()> {
+    if let Some(expired) = expired {
+        conn.execute(
+            "DELETE FROM idempotency WHERE timestamp < ?1",
+            params![expired],
+        )?;
+    }
+    conn.execute(
+        "INSERT OR REPLACE INTO idempotency (key, request, response, timestamp)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            record.key,
+            record.request,
+            record.response,
+            record.timestamp
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Добавление колонки в таблицу, если ее еще нет.
+fn add_column(conn: &Connection, table: &str, column: &str, decl: &str) -> rusqlite::Result<()> {
+    let exists: bool = conn
+        .prepare(&format!(
+            "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1",
+            table
+        ))?
+        .exists([column])?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Сериализация значения в json для хранения в бд.
+fn to_json<T: Serialize>(value: &T) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))
+}
+
+/// Десериализация значения из json, сохраненного в бд.
+fn from_json<T: DeserializeOwned>(text: &str) -> rusqlite::Result<T> {
+    serde_json::from_str(text).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, err.into())
+    })
+}
+
+/// Перекладка enum в текст для хранения в бд.
+fn to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(text)) => text,
+        _ => String::new(),
+    }
+}
+
+/// Парсинг enum из текста, сохраненного в бд.
+fn from_text<T: DeserializeOwned>(text: String) -> rusqlite::Result<T> {
+    serde_json::from_value(Value::String(text)).map_err(|err| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, err.into())
+    })
+}
+
+/// Ошибка SQLite.
+fn storage_err(err: rusqlite::Error) -> AppError {
+    StorageFailure(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::account::Status;
+    use crate::domain::entities::journal::Command;
+    use crate::domain::entities::transaction::Operation::{Replenish, TransferDecrease};
+    use std::path::PathBuf;
+
+    /// Фиксация операции: запись в SQLite и изменение бд в памяти.
+    fn commit(
+        db: &mut SqliteImpl,
+        mut build: impl FnMut(Sequence) -> WalEntry,
+    ) -> Result<u64, AppError> {
+        let pending: Pending = db.append(&mut |seq| Ok(build(seq)))?;
+        pending.sync()?;
+        let op_id: u64 = pending.operation_id();
+        db.apply(pending);
+        Ok(op_id)
+    }
+
+    /// Новый счет.
+    fn open(db: &mut SqliteImpl, name: Option<&str>) -> u32 {
+        let mut acc_id: u32 = 0;
+        commit(db, |seq| {
+            acc_id = seq.account_id;
+            let mut account = Account {
+                id: seq.account_id,
+                name: name.map(str::to_string),
+                ..Account::new()
+            };
+            account.transactions.push(Transaction::new(
+                0,
+                Operation::default(),
+                Money::ZERO,
+                Money::ZERO,
+                Money::ZERO,
+            ));
+            WalEntry::account(account, seq.operation_id, Utc::now())
+        })
+        .unwrap();
+        acc_id
+    }
+
+    /// Проводка по счету на `minor` копеек.
+    fn posting(db: &SqliteImpl, acc_id: u32, minor: i64, operation: Operation) -> Transaction {
+        let account: &Account = db.get_account(acc_id).unwrap();
+        let current: i64 = match operation {
+            TransferDecrease => account.balance.minor() - minor,
+            _ => account.balance.minor() + minor,
+        };
+        Transaction::new(
+            account.transactions.len() as u32,
+            operation,
+            account.balance,
+            Money::from_minor(minor),
+            Money::from_minor(current),
+        )
+    }
+
+    /// Пополнение счета с ключом идемпотентности.
+    fn deposit(db: &mut SqliteImpl, acc_id: u32, minor: i64, key: Option<&str>) {
+        let tx: Transaction = posting(db, acc_id, minor, Replenish);
+        let command = Command::Replenish {
+            account_id: acc_id,
+            value: tx.delta,
+            fee: Money::ZERO,
+            reference: None,
+        };
+        let now: DateTime<Utc> = Utc::now();
+        let saved = key.map(|key| {
+            let record = IdempotencyRecord::new(key.into(), "{}".into(), "{}".into(), now);
+            (record, now - chrono::TimeDelta::hours(1))
+        });
+        commit(db, |seq| {
+            WalEntry::commit(
+                seq.operation_id,
+                command.clone(),
+                vec![(acc_id, tx.clone())],
+                now,
+                saved.clone(),
+            )
+        })
+        .unwrap();
+    }
+
+    /// Счета, журнал и ключи идемпотентности бд.
+    fn state(db: &SqliteImpl) -> (Vec<Account>, Vec<Record>, Option<IdempotencyRecord>) {
+        let mut accounts: Vec<Account> = db.get_accounts().values().cloned().collect();
+        accounts.sort_by_key(|acc| acc.id);
+        (
+            accounts,
+            db.get_journal().to_vec(),
+            db.find_idempotency("key-1").cloned(),
+        )
+    }
+
+    #[test]
+    fn reopen_loads_stored_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path: PathBuf = dir.path().join("bank.db");
+        let mut db: SqliteImpl = SqliteImpl::open(&path).unwrap();
+        let alice: u32 = open(&mut db, Some("alice"));
+        let bob: u32 = open(&mut db, None);
+        deposit(&mut db, alice, 1000, Some("key-1"));
+        deposit(&mut db, bob, 250, None);
+        commit(&mut db, |seq| {
+            let command = Command::Freeze { account_id: bob };
+            WalEntry::status(seq.operation_id, command, bob, Status::Frozen, Utc::now())
+        })
+        .unwrap();
+        let before = state(&db);
+        drop(db);
+
+        let restored: SqliteImpl = SqliteImpl::open(&path).unwrap();
+        assert_eq!(state(&restored), before);
+        assert_eq!(restored.find_account("alice"), Some(alice));
+        assert_eq!(restored.get_account(bob).unwrap().status, Status::Frozen);
+        assert_eq!(restored.next_operation_id(), 6);
+    }
+
+    #[test]
+    fn old_schema_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path: PathBuf = dir.path().join("bank.db");
+        // файл первой версии: без имен счетов, времени, назначения и контрагента транзакций
+        let conn: Connection = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE accounts (id INTEGER PRIMARY KEY, status TEXT NOT NULL, balance INTEGER NOT NULL);
+             CREATE TABLE transactions (
+                 account_id INTEGER NOT NULL, id INTEGER NOT NULL, operation TEXT NOT NULL,
+                 previous INTEGER NOT NULL, delta INTEGER NOT NULL, current INTEGER NOT NULL,
+                 PRIMARY KEY (account_id, id));
+             CREATE TABLE journal (id INTEGER PRIMARY KEY, command TEXT NOT NULL, transactions TEXT NOT NULL);
+             INSERT INTO accounts VALUES (1, 'Opened', 500);
+             INSERT INTO transactions VALUES (1, 0, 'Registration', 0, 0, 0), (1, 1, 'Replenish', 0, 500, 500);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let mut db: SqliteImpl = SqliteImpl::open(&path).unwrap();
+        let account: &Account = db.get_account(1).unwrap();
+        assert_eq!(account.balance, Money::from_minor(500));
+        assert_eq!(account.name, None);
+        assert_eq!(
+            account.transactions[1].timestamp,
+            DateTime::<Utc>::UNIX_EPOCH
+        );
+        assert_eq!(account.transactions[1].reference, None);
+        // новые колонки пишутся, имена уникальны
+        let carol: u32 = open(&mut db, Some("carol"));
+        assert_eq!(carol, 2);
+        let duplicate = db.append(&mut |seq| {
+            let account = Account {
+                id: seq.account_id,
+                name: Some("carol".to_string()),
+                ..Account::new()
+            };
+            Ok(WalEntry::account(account, seq.operation_id, Utc::now()))
+        });
+        assert!(matches!(duplicate, Err(AppError::AccountNameExists(_))));
+        drop(db);
+
+        // повторный запуск на обновленной схеме
+        let restored: SqliteImpl = SqliteImpl::open(&path).unwrap();
+        assert_eq!(restored.find_account("carol"), Some(carol));
+        assert_eq!(restored.get_account(1).unwrap().transactions.len(), 2);
+    }
+
+    #[test]
+    fn failed_write_rolls_back_sql_transaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path: PathBuf = dir.path().join("bank.db");
+        let mut db: SqliteImpl = SqliteImpl::open(&path).unwrap();
+        let from: u32 = open(&mut db, None);
+        let to: u32 = open(&mut db, None);
+        deposit(&mut db, from, 1000, None);
+        let before = state(&db);
+        // транзакция получателя с тем же id уже есть: вторая проводка перевода не пишется
+        let tx_to: Transaction = posting(&db, to, 300, Operation::TransferIncrease);
+        db.conn()
+            .execute(
+                "INSERT INTO transactions (account_id, id, operation, previous, delta, current)
+                 VALUES (?1, ?2, 'Replenish', 0, 0, 0)",
+                params![to, tx_to.id],
+            )
+            .unwrap();
+
+        let tx_from: Transaction = posting(&db, from, 300, TransferDecrease);
+        let command = Command::Transfer {
+            account_from: from,
+            account_to: to,
+            value: Money::from_minor(300),
+            fee: Money::ZERO,
+            reference: None,
+        };
+        let result = commit(&mut db, |seq| {
+            WalEntry::commit(
+                seq.operation_id,
+                command.clone(),
+                vec![(from, tx_from.clone()), (to, tx_to.clone())],
+                Utc::now(),
+                None,
+            )
+        });
+
+        assert!(matches!(result, Err(StorageFailure(_))));
+        // ни бд в памяти, ни списание отправителя в SQLite не изменены
+        assert_eq!(state(&db), before);
+        let conn = db.conn();
+        let balance: i64 = conn
+            .query_row(
+                "SELECT balance FROM accounts WHERE id = ?1",
+                [from],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(balance, 1000);
+        let operations: u64 = conn
+            .query_row("SELECT COUNT(*) FROM journal", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(operations, 3);
+        drop(conn);
+        // id операции не занят: следующая операция пишется
+        deposit(&mut db, from, 1, None);
+        assert_eq!(db.next_operation_id(), 5);
+    }
+}
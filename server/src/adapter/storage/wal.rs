@@ -0,0 +1,398 @@
+use crate::domain::entities::account::{Account, Status};
+use crate::domain::entities::idempotency::IdempotencyRecord;
+use crate::domain::entities::journal::{Command, Record, TransactionRef};
+use crate::domain::entities::transaction::Transaction;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, PoisonError};
+
+/// Имя файла журнала предзаписи (WAL).
+const WAL_FILE: &str = "wal.jsonl";
+
+/// Запись WAL: изменение бд, внесенное операцией (одна строка json).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WalEntry {
+    /// Новый счет с транзакцией регистрации.
+    Account { account: Account, record: Record },
+    /// Транзакции по счетам и ответ операции по ключу идемпотентности.
+    Commit {
+        entries: Vec<(u32, Transaction)>,
+        record: Record,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        idempotency: Option<IdempotencyRecord>,
+        // ключи, сохраненные раньше, удаляются вместе с записью ключа операции
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expired: Option<DateTime<Utc>>,
+    },
+    /// Изменение статуса счета.
+    Status {
+        account_id: u32,
+        status: Status,
+        record: Record,
+    },
+    /// Результат операции `operation_id` по ключу идемпотентности
+    /// (WAL предыдущих версий, ключ теперь пишется в записи операции).
+    Idempotency {
+        operation_id: u64,
+        record: IdempotencyRecord,
+    },
+}
+
+impl WalEntry {
+    /// Новый счет с транзакцией регистрации.
+    pub fn account(account: Account, operation_id: u64, timestamp: DateTime<Utc>) -> Self {
+        // ссылки на транзакции регистрации
+        let refs: Vec<TransactionRef> = account
+            .transactions
+            .iter()
+            .map(|tx| TransactionRef::new(account.id, tx.id))
+            .collect();
+        let command = Command::NewAccount {
+            account_id: account.id,
+            name: account.name.clone(),
+        };
+        let record = Record::new(operation_id, command, refs, timestamp);
+
+        Self::Account { account, record }
+    }
+
+    /// Транзакции операции и ответ по ключу идемпотентности (ключи раньше `expired` удаляются).
+    pub fn commit(
+        operation_id: u64,
+        command: Command,
+        entries: Vec<(u32, Transaction)>,
+        timestamp: DateTime<Utc>,
+        idempotency: Option<(IdempotencyRecord, DateTime<Utc>)>,
+    ) -> Self {
+        // ссылки на транзакции операции
+        let refs: Vec<TransactionRef> = entries
+            .iter()
+            .map(|(acc_id, tx)| TransactionRef::new(*acc_id, tx.id))
+            .collect();
+        let record = Record::new(operation_id, command, refs, timestamp);
+        let (idempotency, expired) = idempotency.unzip();
+
+        Self::Commit {
+            entries,
+            record,
+            idempotency,
+            expired,
+        }
+    }
+
+    /// Изменение статуса счета.
+    pub fn status(
+        operation_id: u64,
+        command: Command,
+        account_id: u32,
+        status: Status,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        let record = Record::new(operation_id, command, Vec::new(), timestamp);
+
+        Self::Status {
+            account_id,
+            status,
+            record,
+        }
+    }
+
+    /// Id операции журнала, к которой относится запись.
+    pub fn operation_id(&self) -> u64 {
+        match self {
+            Self::Account { record, .. }
+            | Self::Commit { record, .. }
+            | Self::Status { record, .. } => record.id,
+            Self::Idempotency { operation_id, .. } => *operation_id,
+        }
+    }
+}
+
+/// Прочитанный сегмент WAL.
+#[derive(Debug, Default)]
+pub struct Segment {
+    pub entries: Vec<WalEntry>,
+    // размер корректного начала файла в байтах
+    pub valid_len: u64,
+    // файл оборван или поврежден после `valid_len` (сбой во время дозаписи)
+    pub torn: bool,
+}
+
+/// Путь к сегменту WAL: 0 - текущий (wal.jsonl), n - n-й предыдущий сегмент (wal.jsonl.n).
+pub fn segment_path(dir: &Path, n: usize) -> PathBuf {
+    match n {
+        0 => dir.join(WAL_FILE),
+        n => dir.join(format!("{}.{}", WAL_FILE, n)),
+    }
+}
+
+/// Ошибка дозаписи в сегмент.
+#[derive(Debug)]
+pub enum AppendError {
+    /// Запись не удалась, сегмент возвращен к прежнему размеру.
+    Write(io::Error),
+    /// Сброс на диск или откат записи не удался: содержимое сегмента на диске неизвестно.
+    Sync(io::Error),
+    /// Журнал закрыт после предыдущей ошибки сброса на диск.
+    Broken(String),
+}
+
+impl fmt::Display for AppendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Write(err) => write!(f, "WAL write error: {}", err),
+            Self::Sync(err) => write!(f, "WAL sync error: {}", err),
+            Self::Broken(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Журнал предзаписи: дозапись в текущий сегмент и групповой сброс на диск.
+/// Записи дописываются по одной под блокировкой сегмента, сброс на диск (fsync) идет без нее:
+/// один поток сбрасывает все записи, дописанные к этому моменту, остальные ждут его.
+#[derive(Debug)]
+pub struct Wal {
+    dir: PathBuf,
+    head: Mutex<Head>,
+    flush: Mutex<Flush>,
+    flushed: Condvar,
+    // ошибка следующего сброса на диск (тесты)
+    #[cfg(test)]
+    fault: Mutex<Option<io::ErrorKind>>,
+}
+
+/// Текущий сегмент WAL.
+#[derive(Debug, Default)]
+struct Head {
+    // открывается при первой дозаписи (после загрузки оборванный конец уже отрезан)
+    file: Option<File>,
+    // байт дописано с создания журнала во все сегменты (позиция для сброса на диск)
+    written: u64,
+}
+
+/// Состояние сброса на диск.
+#[derive(Debug, Default)]
+struct Flush {
+    // байт сброшено на диск
+    synced: u64,
+    // сброс на диск идет в другом потоке
+    running: bool,
+    // причина отказа: содержимое журнала на диске неизвестно, запись закрыта до перезагрузки
+    broken: Option<String>,
+}
+
+impl Wal {
+    /// Журнал в директории `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            head: Mutex::new(Head::default()),
+            flush: Mutex::new(Flush::default()),
+            flushed: Condvar::new(),
+            #[cfg(test)]
+            fault: Mutex::new(None),
+        }
+    }
+
+    /// Дозапись записи в конец текущего сегмента без сброса на диск.
+    /// Возвращает позицию конца записи для `sync`. Частично дописанная при ошибке строка отрезается.
+    pub fn append(&self, entry: &WalEntry) -> Result<u64, AppendError> {
+        let mut buf: Vec<u8> =
+            serde_json::to_vec(entry).map_err(|err| AppendError::Write(err.into()))?;
+        buf.push(b'\n');
+        let mut head = self.head.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(reason) = self.broken() {
+            return Err(AppendError::Broken(reason));
+        }
+        if head.file.is_none() {
+            let file: File = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(segment_path(&self.dir, 0))
+                .map_err(AppendError::Write)?;
+            head.file = Some(file);
+        }
+        let Some(file) = head.file.as_mut() else {
+            return Err(AppendError::Write(io::ErrorKind::NotFound.into()));
+        };
+        let len: u64 = file.metadata().map_err(AppendError::Write)?.len();
+        if let Err(err) = file.write_all(&buf) {
+            if let Err(err) = file.set_len(len) {
+                return Err(self.fail(AppendError::Sync(err)));
+            }
+            return Err(AppendError::Write(err));
+        }
+        head.written += buf.len() as u64;
+
+        Ok(head.written)
+    }
+
+    /// Сброс на диск записей до позиции `end` (групповой fsync).
+    pub fn sync(&self, end: u64) -> Result<(), AppendError> {
+        let mut flush = self.flush.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if let Some(reason) = &flush.broken {
+                return Err(AppendError::Broken(reason.clone()));
+            }
+            if flush.synced >= end {
+                return Ok(());
+            }
+            if !flush.running {
+                break;
+            }
+            flush = self
+                .flushed
+                .wait(flush)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        // сброс всех записей, дописанных к этому моменту
+        flush.running = true;
+        drop(flush);
+        let synced: io::Result<u64> = self.sync_segment();
+        let mut flush = self.flush.lock().unwrap_or_else(PoisonError::into_inner);
+        flush.running = false;
+        let result: Result<(), AppendError> = match synced {
+            Ok(synced) => {
+                flush.synced = flush.synced.max(synced);
+                Ok(())
+            }
+            Err(err) => {
+                let err = AppendError::Sync(err);
+                flush.broken = Some(err.to_string());
+                Err(err)
+            }
+        };
+        drop(flush);
+        self.flushed.notify_all();
+
+        result
+    }
+
+    /// Сброс текущего сегмента на диск. Возвращает сброшенную позицию.
+    fn sync_segment(&self) -> io::Result<u64> {
+        #[cfg(test)]
+        if let Some(kind) = self
+            .fault
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            return Err(kind.into());
+        }
+        let (file, written): (Option<File>, u64) = {
+            let head = self.head.lock().unwrap_or_else(PoisonError::into_inner);
+            let file: Option<File> = head.file.as_ref().map(File::try_clone).transpose()?;
+            (file, head.written)
+        };
+        if let Some(file) = file {
+            file.sync_data()?;
+        }
+
+        Ok(written)
+    }
+
+    /// Новый сегмент после записи снимка: текущий сегмент сбрасывается на диск
+    /// и сдвигается вместе с предыдущими (см. `rotate`), следующая запись открывает wal.jsonl.
+    pub fn rotate(&self, keep: usize) -> Result<(), AppendError> {
+        let mut head = self.head.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(file) = head.file.take() {
+            file.sync_data()
+                .map_err(|err| self.fail(AppendError::Sync(err)))?;
+        }
+        rotate(&self.dir, keep).map_err(AppendError::Write)
+    }
+
+    /// Причина отказа журнала.
+    pub fn broken(&self) -> Option<String> {
+        self.flush
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .broken
+            .clone()
+    }
+
+    /// Сброс состояния после перечитывания бд: сегмент открывается заново, отказ снимается.
+    pub fn reset(&self) {
+        self.head
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .file = None;
+        self.flush
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .broken = None;
+    }
+
+    /// Ошибка следующего сброса на диск.
+    #[cfg(test)]
+    pub fn fail_next_sync(&self, kind: io::ErrorKind) {
+        *self.fault.lock().unwrap_or_else(PoisonError::into_inner) = Some(kind);
+    }
+
+    /// Закрытие журнала после ошибки сброса на диск.
+    fn fail(&self, err: AppendError) -> AppendError {
+        self.flush
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .broken = Some(err.to_string());
+        err
+    }
+}
+
+/// Чтение сегмента до первой оборванной или некорректной строки.
+/// Отсутствующий файл - пустой сегмент.
+pub fn read(path: &Path) -> io::Result<Segment> {
+    let file: File = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Segment::default()),
+        Err(err) => return Err(err),
+    };
+    let mut reader = BufReader::new(file);
+    let mut segment = Segment::default();
+    let mut line: Vec<u8> = Vec::new();
+    loop {
+        line.clear();
+        let read: usize = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        // строка без перевода строки не дописана до конца
+        let entry: Option<WalEntry> = line
+            .strip_suffix(b"\n")
+            .and_then(|payload| serde_json::from_slice(payload).ok());
+        let Some(entry) = entry else {
+            segment.torn = true;
+            break;
+        };
+        segment.entries.push(entry);
+        segment.valid_len += read as u64;
+    }
+
+    Ok(segment)
+}
+
+/// Отбрасывание оборванного конца сегмента, чтобы новые записи не попали после него.
+pub fn truncate(path: &Path, len: u64) -> io::Result<()> {
+    let file: File = OpenOptions::new().write(true).open(path)?;
+    file.set_len(len)?;
+    file.sync_all()
+}
+
+/// Сдвиг сегментов после записи снимка: wal.jsonl.n -> wal.jsonl.n+1, wal.jsonl -> wal.jsonl.1.
+/// Хранятся `keep + 1` предыдущих сегментов: в них есть все операции после самой старой
+/// реплики backup.json.keep, в том числе дописанные между сериализацией и записью снимка.
+fn rotate(dir: &Path, keep: usize) -> io::Result<()> {
+    for n in (0..=keep).rev() {
+        let from: PathBuf = segment_path(dir, n);
+        if from.exists() {
+            fs::rename(&from, segment_path(dir, n + 1))?;
+        }
+    }
+
+    Ok(())
+}
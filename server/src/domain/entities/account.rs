@@ -1,40 +1,252 @@
+use crate::domain::entities::fee::FEE_ACCOUNT;
+use crate::domain::entities::money::Money;
 use crate::domain::entities::transaction::Transaction;
-use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::InvalidAccountName;
+use chrono::{DateTime, Utc};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+use utoipa::openapi::{
+    KnownFormat, ObjectBuilder, OneOfBuilder, RefOr, Schema, SchemaFormat, SchemaType,
+};
+use utoipa::{IntoParams, ToSchema};
 
 /// Статусы счета.
-#[derive(Debug, Default, Clone, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
 pub enum Status {
     #[default]
     Opened,
     Closed,
+    Frozen,
 }
 
 /// Структура счета.
-#[derive(Debug, Default, Clone, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
 pub struct Account {
     pub id: u32,
+    #[serde(default)]
+    pub name: Option<String>,
     pub status: Status,
-    pub balance: f64,
+    pub balance: Money,
     pub transactions: Vec<Transaction>,
 }
 
 impl Account {
+    /// Максимальная длина имени счета.
+    pub const NAME_MAX_LEN: usize = 64;
+
+    /// Имена, совпадающие со статическими маршрутами `/account/...`.
+    /// Имя системного счета комиссий также зарезервировано.
+    pub const RESERVED_NAMES: [&'static str; 5] =
+        ["new", "replenish", "withdraw", "transfer", "balance"];
+
     /// Открытие счета.
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Системный счет комиссий: списания и изменения статуса запрещены.
+    pub fn is_system(&self) -> bool {
+        self.name.as_deref() == Some(FEE_ACCOUNT)
+    }
+
+    /// Проверка имени счета.
+    /// Имя начинается с буквы или цифры, состоит из букв, цифр и символов `-_.`
+    /// и не может быть числом (не путается с id счета) или зарезервированным именем.
+    pub fn validate_name(name: &str) -> Result<(), AppError> {
+        if !name.starts_with(char::is_alphanumeric)
+            || name.chars().count() > Self::NAME_MAX_LEN
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            || name.parse::<u32>().is_ok()
+            || Self::RESERVED_NAMES.contains(&name)
+            || name == FEE_ACCOUNT
+        {
+            return Err(InvalidAccountName(name.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Ключ поиска счета: id или уникальное имя.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AccountKey {
+    Id(u32),
+    Name(String),
+}
+
+impl From<u32> for AccountKey {
+    fn from(id: u32) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl fmt::Display for AccountKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Парсинг ключа из строки: число - id счета, иначе - имя.
+impl FromStr for AccountKey {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse::<u32>() {
+            Ok(id) => Self::Id(id),
+            Err(_) => Self::Name(s.to_string()),
+        })
+    }
+}
+
+/// Сериализация id числом, имени - строкой.
+impl Serialize for AccountKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Id(id) => serializer.serialize_u32(*id),
+            Self::Name(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+/// Десериализация из json-числа (id) или строки (id или имя).
+impl<'de> Deserialize<'de> for AccountKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AccountKeyVisitor)
+    }
+}
+
+/// Visitor для десериализации ключа счета.
+struct AccountKeyVisitor;
+
+impl<'de> Visitor<'de> for AccountKeyVisitor {
+    type Value = AccountKey;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an account id or an account name")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        u32::try_from(v).map(AccountKey::Id).map_err(E::custom)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        u32::try_from(v).map(AccountKey::Id).map_err(E::custom)
+    }
+}
+
+/// Схема для swagger: id счета или его имя.
+impl<'s> ToSchema<'s> for AccountKey {
+    fn schema() -> (&'s str, RefOr<Schema>) {
+        (
+            "AccountKey",
+            OneOfBuilder::new()
+                .item(
+                    ObjectBuilder::new()
+                        .schema_type(SchemaType::Integer)
+                        .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int32))),
+                )
+                .item(ObjectBuilder::new().schema_type(SchemaType::String))
+                .description(Some("Account id or unique account name."))
+                .example(Some(serde_json::json!(1)))
+                .into(),
+        )
+    }
+}
+
+/// Структура для парсинга параметров нового счета.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct NewAccountRequest {
+    pub name: Option<String>,
+}
+
+/// Параметр запроса состояния счета на момент времени.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AsOfQuery {
+    /// Момент времени (RFC 3339), без параметра - текущее состояние.
+    pub as_of: Option<DateTime<Utc>>,
 }
 
 /// Структура ответа баланса счета.
 #[derive(Serialize, ToSchema)]
 pub struct BalanceResponse {
-    pub balance: f64,
+    pub balance: Money,
 }
 
 impl BalanceResponse {
     /// Конструктор ответа баланса счета.
-    pub fn new(balance: f64) -> Self {
+    pub fn new(balance: Money) -> Self {
         Self { balance }
     }
 }
+
+/// Структура ответа изменения статуса счета.
+#[derive(Serialize, ToSchema)]
+pub struct StatusResponse {
+    pub account_id: u32,
+    pub status: Status,
+    pub operation_id: u64,
+}
+
+impl StatusResponse {
+    /// Конструктор ответа изменения статуса счета.
+    pub fn new(account_id: u32, status: Status, operation_id: u64) -> Self {
+        Self {
+            account_id,
+            status,
+            operation_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_names() {
+        for name in ["savings", "bank.fee", "a", "acc-1_b.c", "счет", "1st"] {
+            assert!(Account::validate_name(name).is_ok(), "{}", name);
+        }
+        assert!(Account::validate_name(&"a".repeat(Account::NAME_MAX_LEN)).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        for name in ["", "42", "-acc", ".acc", "acc/1", "acc 1", "acc?"] {
+            assert!(
+                matches!(Account::validate_name(name), Err(InvalidAccountName(_))),
+                "{}",
+                name
+            );
+        }
+        assert!(Account::validate_name(&"a".repeat(Account::NAME_MAX_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn rejects_route_names() {
+        for name in Account::RESERVED_NAMES {
+            assert!(
+                matches!(Account::validate_name(name), Err(InvalidAccountName(_))),
+                "{}",
+                name
+            );
+        }
+        assert!(Account::validate_name(FEE_ACCOUNT).is_err());
+        // маршруты различают регистр
+        assert!(Account::validate_name("Balance").is_ok());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::domain::entities::money::Money;
+use crate::domain::entities::transaction::Operation;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Имя системного счета, на который зачисляются комиссии.
+pub const FEE_ACCOUNT: &str = "bank.fees";
+
+/// Количество базисных пунктов в 100%.
+const BASIS_POINTS: i128 = 10_000;
+
+/// Тариф комиссии операции: фиксированная часть + процент от суммы,
+/// итог ограничивается минимумом и максимумом.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct FeeRule {
+    // фиксированная часть комиссии
+    pub flat: Money,
+    // процент от суммы в базисных пунктах (1 bp = 0.01%)
+    pub percent_bp: u32,
+    // минимальная комиссия
+    pub min: Money,
+    // максимальная комиссия
+    pub max: Option<Money>,
+}
+
+impl FeeRule {
+    /// Проверка тарифа.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.flat < Money::ZERO || self.min < Money::ZERO {
+            return Err("fee amounts can not be negative".to_string());
+        }
+        if let Some(max) = self.max {
+            if max < self.min {
+                return Err(format!("fee max '{}' is less than min '{}'", max, self.min));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Расчет комиссии для суммы операции.
+    /// Процентная часть округляется до минимальной единицы валюты (половина - вверх).
+    pub fn charge(&self, value: Money) -> Money {
+        let percent: i128 = (i128::from(value.minor()) * i128::from(self.percent_bp)
+            + BASIS_POINTS / 2)
+            / BASIS_POINTS;
+        let fee: i64 = i64::try_from(i128::from(self.flat.minor()) + percent).unwrap_or(i64::MAX);
+        let fee: Money = Money::from_minor(fee).max(self.min);
+
+        match self.max {
+            Some(max) => fee.min(max),
+            None => fee,
+        }
+    }
+}
+
+/// Тарифы комиссий по видам операций. Операции без тарифа проводятся без комиссии.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeSchedule {
+    pub replenish: Option<FeeRule>,
+    pub withdraw: Option<FeeRule>,
+    pub transfer: Option<FeeRule>,
+}
+
+impl FeeSchedule {
+    /// Проверка всех тарифов.
+    pub fn validate(&self) -> Result<(), String> {
+        for (operation, rule) in [
+            ("replenish", &self.replenish),
+            ("withdraw", &self.withdraw),
+            ("transfer", &self.transfer),
+        ] {
+            if let Some(rule) = rule {
+                rule.validate()
+                    .map_err(|err| format!("{} fee: {}", operation, err))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Тариф операции. Для перевода тариф применяется к списанию со счета отправителя.
+    pub fn rule(&self, operation: &Operation) -> Option<&FeeRule> {
+        match operation {
+            Operation::Replenish => self.replenish.as_ref(),
+            Operation::Withdraw => self.withdraw.as_ref(),
+            Operation::TransferDecrease => self.transfer.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Комиссия операции на сумму `value`.
+    /// Комиссия пополнения не превышает зачисляемую сумму: пополнение не уводит счет в минус.
+    pub fn charge(&self, operation: &Operation, value: Money) -> Money {
+        let fee: Money = self
+            .rule(operation)
+            .map(|rule| rule.charge(value))
+            .unwrap_or(Money::ZERO);
+
+        match operation {
+            Operation::Replenish => fee.min(value),
+            _ => fee,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(flat: i64, percent_bp: u32, min: i64, max: Option<i64>) -> FeeRule {
+        FeeRule {
+            flat: Money::from_minor(flat),
+            percent_bp,
+            min: Money::from_minor(min),
+            max: max.map(Money::from_minor),
+        }
+    }
+
+    #[test]
+    fn percent_is_rounded_half_up() {
+        // 1% от 0.49 = 0.0049 -> 0.00, от 0.50 = 0.005 -> 0.01
+        let fee: FeeRule = rule(0, 100, 0, None);
+        assert_eq!(fee.charge(Money::from_minor(49)), Money::ZERO);
+        assert_eq!(fee.charge(Money::from_minor(50)), Money::from_minor(1));
+        assert_eq!(fee.charge(Money::from_minor(149)), Money::from_minor(1));
+        assert_eq!(fee.charge(Money::from_minor(150)), Money::from_minor(2));
+        // фиксированная часть + процент
+        let fee: FeeRule = rule(30, 250, 0, None);
+        assert_eq!(
+            fee.charge(Money::from_minor(10_000)),
+            Money::from_minor(280)
+        );
+    }
+
+    #[test]
+    fn fee_is_clamped_by_min_and_max() {
+        let fee: FeeRule = rule(0, 100, 100, Some(500));
+        assert_eq!(fee.charge(Money::from_minor(1_000)), Money::from_minor(100));
+        assert_eq!(
+            fee.charge(Money::from_minor(20_000)),
+            Money::from_minor(200)
+        );
+        assert_eq!(
+            fee.charge(Money::from_minor(100_000)),
+            Money::from_minor(500)
+        );
+    }
+
+    #[test]
+    fn overflow_is_clamped_to_max_money() {
+        let fee: FeeRule = rule(i64::MAX, BASIS_POINTS as u32, 0, None);
+        assert_eq!(
+            fee.charge(Money::from_minor(i64::MAX)),
+            Money::from_minor(i64::MAX)
+        );
+        let fee: FeeRule = rule(i64::MAX, BASIS_POINTS as u32, 0, Some(1_000));
+        assert_eq!(
+            fee.charge(Money::from_minor(i64::MAX)),
+            Money::from_minor(1_000)
+        );
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(rule(-1, 0, 0, None).validate().is_err());
+        assert!(rule(0, 0, -1, None).validate().is_err());
+        assert!(rule(0, 0, 500, Some(100)).validate().is_err());
+        assert!(rule(0, 0, 100, Some(100)).validate().is_ok());
+        let schedule = FeeSchedule {
+            transfer: Some(rule(0, 0, 500, Some(100))),
+            ..FeeSchedule::default()
+        };
+        assert!(schedule.validate().unwrap_err().starts_with("transfer fee"));
+    }
+
+    #[test]
+    fn tariff_depends_on_operation() {
+        let schedule = FeeSchedule {
+            replenish: Some(rule(10, 0, 0, None)),
+            withdraw: Some(rule(20, 0, 0, None)),
+            transfer: Some(rule(30, 0, 0, None)),
+        };
+        let value: Money = Money::from_minor(1_000);
+        assert_eq!(
+            schedule.charge(&Operation::Replenish, value),
+            Money::from_minor(10)
+        );
+        assert_eq!(
+            schedule.charge(&Operation::Withdraw, value),
+            Money::from_minor(20)
+        );
+        assert_eq!(
+            schedule.charge(&Operation::TransferDecrease, value),
+            Money::from_minor(30)
+        );
+        // зачисление перевода и сами комиссии без тарифа
+        for operation in [
+            Operation::TransferIncrease,
+            Operation::Commission,
+            Operation::CommissionIncome,
+        ] {
+            assert_eq!(schedule.charge(&operation, value), Money::ZERO);
+        }
+        assert_eq!(
+            FeeSchedule::default().charge(&Operation::Withdraw, value),
+            Money::ZERO
+        );
+    }
+
+    #[test]
+    fn replenish_fee_is_capped_at_credited_value() {
+        let schedule = FeeSchedule {
+            replenish: Some(rule(100, 0, 0, None)),
+            withdraw: Some(rule(100, 0, 0, None)),
+            ..FeeSchedule::default()
+        };
+        assert_eq!(
+            schedule.charge(&Operation::Replenish, Money::from_minor(50)),
+            Money::from_minor(50)
+        );
+        assert_eq!(
+            schedule.charge(&Operation::Replenish, Money::from_minor(100)),
+            Money::from_minor(100)
+        );
+        assert_eq!(
+            schedule.charge(&Operation::Replenish, Money::from_minor(150)),
+            Money::from_minor(100)
+        );
+        // списание не ограничивается: недостаток средств - овердрафт
+        assert_eq!(
+            schedule.charge(&Operation::Withdraw, Money::from_minor(50)),
+            Money::from_minor(100)
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Состояние бд.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Бд принимает запросы.
+    Ok,
+    /// Восстановление бд при старте.
+    Recovering,
+    /// Бд закрыта после сбоя до перезапуска сервиса.
+    Failed,
+}
+
+/// Итог загрузки бд при старте.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RecoveryReport {
+    pub accounts: usize,
+    pub transactions: usize,
+    pub operations: usize,
+    /// Время фонового восстановления in-memory бд.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+}
+
+/// Структура ответа проверки состояния сервиса.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: HealthStatus,
+    /// Причина закрытия бд.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Итог загрузки бд, нет - бд еще восстанавливается или стартовала пустой.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<RecoveryReport>,
+}
+
+impl HealthResponse {
+    /// Конструктор ответа проверки состояния.
+    pub fn new(status: HealthStatus, reason: Option<String>) -> Self {
+        Self {
+            status,
+            reason,
+            recovery: None,
+        }
+    }
+
+    /// Итог загрузки бд при старте.
+    pub fn with_recovery(mut self, recovery: Option<RecoveryReport>) -> Self {
+        self.recovery = recovery;
+        self
+    }
+}
@@ -0,0 +1,152 @@
+use crate::domain::entities::money::Money;
+use crate::domain::entities::transaction::{Operation, Transaction};
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::InvalidHistoryQuery;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+
+/// Размер страницы истории по умолчанию.
+pub const HISTORY_LIMIT: usize = 100;
+/// Максимальный размер страницы истории.
+pub const HISTORY_MAX_LIMIT: usize = 1000;
+
+/// Порядок выдачи истории.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    /// По возрастанию (от старых транзакций к новым).
+    #[default]
+    Asc,
+    /// По убыванию (от новых транзакций к старым).
+    Desc,
+}
+
+/// Параметры запроса страницы истории: курсор, размер страницы, фильтры и порядок.
+/// Границы диапазонов включаются, кроме `to_time`.
+#[derive(Debug, Default, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// Курсор `next_cursor` предыдущей страницы.
+    pub cursor: Option<String>,
+    /// Размер страницы (1..=1000, по умолчанию 100).
+    pub limit: Option<usize>,
+    /// Тип операции транзакции.
+    #[param(value_type = Option<Operation>)]
+    pub operation: Option<Operation>,
+    /// Минимальный id транзакции.
+    pub from_id: Option<u32>,
+    /// Максимальный id транзакции.
+    pub to_id: Option<u32>,
+    /// Минимальная сумма транзакции.
+    #[param(value_type = Option<String>, example = "10.00")]
+    pub min_amount: Option<Money>,
+    /// Максимальная сумма транзакции.
+    #[param(value_type = Option<String>, example = "100.00")]
+    pub max_amount: Option<Money>,
+    /// Начало периода (RFC 3339).
+    pub from_time: Option<DateTime<Utc>>,
+    /// Конец периода, не включается (RFC 3339).
+    pub to_time: Option<DateTime<Utc>>,
+    /// Порядок выдачи.
+    #[serde(default)]
+    #[param(value_type = Option<Order>)]
+    pub order: Order,
+}
+
+impl HistoryQuery {
+    /// Размер страницы.
+    pub fn limit(&self) -> Result<usize, AppError> {
+        match self.limit {
+            None => Ok(HISTORY_LIMIT),
+            Some(limit) if (1..=HISTORY_MAX_LIMIT).contains(&limit) => Ok(limit),
+            Some(limit) => Err(InvalidHistoryQuery(format!(
+                "limit must be 1 to {}, got {}",
+                HISTORY_MAX_LIMIT, limit
+            ))),
+        }
+    }
+
+    /// Позиция, после которой начинается страница.
+    pub fn cursor(&self) -> Result<Option<Cursor>, AppError> {
+        self.cursor.as_deref().map(str::parse).transpose()
+    }
+
+    /// Транзакция проходит фильтры запроса.
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        self.operation.as_ref().is_none_or(|op| *op == tx.operation)
+            && self.from_id.is_none_or(|id| tx.id >= id)
+            && self.to_id.is_none_or(|id| tx.id <= id)
+            && self.min_amount.is_none_or(|amount| tx.delta >= amount)
+            && self.max_amount.is_none_or(|amount| tx.delta <= amount)
+            && self.from_time.is_none_or(|time| tx.timestamp >= time)
+            && self.to_time.is_none_or(|time| tx.timestamp < time)
+    }
+}
+
+/// Курсор страницы: последняя выданная транзакция (`<id счета>.<id транзакции>`).
+/// Транзакции не изменяются и не удаляются, поэтому курсор не устаревает.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub account_id: u32,
+    pub transaction_id: u32,
+}
+
+impl Cursor {
+    /// Конструктор курсора.
+    pub fn new(account_id: u32, transaction_id: u32) -> Self {
+        Self {
+            account_id,
+            transaction_id,
+        }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.account_id, self.transaction_id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once('.')
+            .and_then(|(account_id, transaction_id)| {
+                Some(Self::new(
+                    account_id.parse().ok()?,
+                    transaction_id.parse().ok()?,
+                ))
+            })
+            .ok_or_else(|| InvalidHistoryQuery(format!("invalid cursor: '{}'", s)))
+    }
+}
+
+/// Транзакция в истории.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistoryEntry {
+    pub account_id: u32,
+    pub transaction: Transaction,
+}
+
+impl HistoryEntry {
+    /// Конструктор записи истории.
+    pub fn new(account_id: u32, transaction: Transaction) -> Self {
+        Self {
+            account_id,
+            transaction,
+        }
+    }
+}
+
+/// Структура ответа страницы истории.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistoryPage {
+    pub transactions: Vec<HistoryEntry>,
+    /// Курсор следующей страницы, отсутствует на последней странице.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Заголовок (http) и ключ метаданных (gRPC) с ключом идемпотентности.
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+
+/// Максимальная длина ключа идемпотентности.
+pub const IDEMPOTENCY_KEY_MAX_LEN: usize = 255;
+
+/// Окно хранения ключей идемпотентности по умолчанию (секунд).
+pub const IDEMPOTENCY_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Сохраненный результат операции, выполненной с ключом идемпотентности.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct IdempotencyRecord {
+    pub key: String,
+    // отпечаток запроса (json): повтор ключа с другим запросом отклоняется
+    pub request: String,
+    // исходный ответ (json)
+    pub response: String,
+    // время выполнения операции
+    pub timestamp: DateTime<Utc>,
+}
+
+impl IdempotencyRecord {
+    /// Конструктор записи ключа идемпотентности.
+    pub fn new(key: String, request: String, response: String, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            key,
+            request,
+            response,
+            timestamp,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::domain::entities::money::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Операция банка, сохраняемая в журнале.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "type")]
+pub enum Command {
+    /// Регистрация счета.
+    NewAccount {
+        account_id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+    },
+    /// Пополнение счета.
+    Replenish {
+        account_id: u32,
+        value: Money,
+        #[serde(default, skip_serializing_if = "Money::is_zero")]
+        fee: Money,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reference: Option<String>,
+    },
+    /// Списание со счета.
+    Withdraw {
+        account_id: u32,
+        value: Money,
+        #[serde(default, skip_serializing_if = "Money::is_zero")]
+        fee: Money,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reference: Option<String>,
+    },
+    /// Перевод со счета на счет.
+    Transfer {
+        account_from: u32,
+        account_to: u32,
+        value: Money,
+        #[serde(default, skip_serializing_if = "Money::is_zero")]
+        fee: Money,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reference: Option<String>,
+    },
+    /// Закрытие счета.
+    Close { account_id: u32 },
+    /// Заморозка счета.
+    Freeze { account_id: u32 },
+    /// Повторное открытие (разморозка) счета.
+    Reopen { account_id: u32 },
+}
+
+impl Command {
+    /// Счета, затронутые операцией.
+    pub fn accounts(&self) -> Vec<u32> {
+        match *self {
+            Self::NewAccount { account_id, .. }
+            | Self::Replenish { account_id, .. }
+            | Self::Withdraw { account_id, .. }
+            | Self::Close { account_id }
+            | Self::Freeze { account_id }
+            | Self::Reopen { account_id } => vec![account_id],
+            Self::Transfer {
+                account_from,
+                account_to,
+                ..
+            } => vec![account_from, account_to],
+        }
+    }
+}
+
+/// Ссылка на транзакцию счета, созданную операцией.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct TransactionRef {
+    pub account_id: u32,
+    pub transaction_id: u32,
+}
+
+impl TransactionRef {
+    /// Конструктор ссылки на транзакцию.
+    pub fn new(account_id: u32, transaction_id: u32) -> Self {
+        Self {
+            account_id,
+            transaction_id,
+        }
+    }
+}
+
+/// Запись журнала операций.
+/// Id записи - глобально уникальный номер операции в порядке выполнения (начиная с 1).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct Record {
+    pub id: u64,
+    pub command: Command,
+    pub transactions: Vec<TransactionRef>,
+    // время выполнения операции
+    #[serde(default)]
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Record {
+    /// Конструктор записи журнала.
+    pub fn new(
+        id: u64,
+        command: Command,
+        transactions: Vec<TransactionRef>,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id,
+            command,
+            transactions,
+            timestamp,
+        }
+    }
+
+    /// Счета, затронутые операцией, включая счета проводок (например, счет комиссий).
+    pub fn accounts(&self) -> Vec<u32> {
+        let mut accounts: Vec<u32> = self.command.accounts();
+        for tx in &self.transactions {
+            if !accounts.contains(&tx.account_id) {
+                accounts.push(tx.account_id);
+            }
+        }
+
+        accounts
+    }
+}
+
+/// Структура ответа восстановления бд из журнала операций.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplayResponse {
+    pub operations: usize,
+    pub accounts: usize,
+    pub transactions: usize,
+}
+
+impl ReplayResponse {
+    /// Конструктор ответа восстановления бд.
+    pub fn new(operations: usize, accounts: usize, transactions: usize) -> Self {
+        Self {
+            operations,
+            accounts,
+            transactions,
+        }
+    }
+}
@@ -1,2 +1,8 @@
 pub mod account;
+pub mod fee;
+pub mod health;
+pub mod history;
+pub mod idempotency;
+pub mod journal;
+pub mod money;
 pub mod transaction;
@@ -0,0 +1,2 @@
+/// Денежная сумма с фиксированной точкой (общий с клиентом крейт `money`).
+pub use ::money::Money;
@@ -1,8 +1,11 @@
+use crate::domain::entities::account::AccountKey;
+use crate::domain::entities::money::Money;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 /// Статусы транзакции.
-#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize, ToSchema)]
 pub enum Operation {
     #[default]
     Registration,
@@ -10,53 +13,86 @@ pub enum Operation {
     Withdraw,
     TransferIncrease,
     TransferDecrease,
+    Commission,
+    CommissionIncome,
 }
 
 /// Структура транзакции.
-#[derive(Debug, Default, Clone, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
 pub struct Transaction {
     pub id: u32,
     pub operation: Operation,
-    pub previous: f64,
-    pub delta: f64,
-    pub current: f64,
+    pub previous: Money,
+    pub delta: Money,
+    pub current: Money,
+    // время проведения
+    #[serde(default)]
+    pub timestamp: DateTime<Utc>,
+    // назначение платежа, переданное клиентом
+    #[serde(default)]
+    pub reference: Option<String>,
+    // счет контрагента (для переводов и комиссий)
+    #[serde(default)]
+    pub counterparty: Option<u32>,
 }
 
 impl Transaction {
     /// Конструктор транзакции.
-    pub fn new(id: u32, operation: Operation, previous: f64, delta: f64, current: f64) -> Self {
+    pub fn new(
+        id: u32,
+        operation: Operation,
+        previous: Money,
+        delta: Money,
+        current: Money,
+    ) -> Self {
         Self {
             id,
             operation,
             previous,
             delta,
             current,
+            ..Default::default()
         }
     }
 }
 
+/// Максимальная длина назначения платежа.
+pub const REFERENCE_MAX_LEN: usize = 140;
+
 /// Структура для парсинга деталий транзакции.
 #[derive(Deserialize, ToSchema)]
 pub struct TransactionRequest {
-    pub account_id: u32,
-    pub transaction_value: f64,
+    pub account_id: AccountKey,
+    pub transaction_value: Money,
+    #[serde(default)]
+    pub reference: Option<String>,
 }
 
 /// Структура ответа совершенной транзакции.
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct TransactionResponse {
     pub account_id: u32,
     pub transaction_id: u32,
-    pub balance: f64,
+    pub balance: Money,
+    pub operation_id: u64,
+    pub fee: Money,
 }
 
 impl TransactionResponse {
     /// Конструктор ответа совершенной транзакции.
-    pub fn new(account_id: u32, transaction_id: u32, balance: f64) -> Self {
+    pub fn new(
+        account_id: u32,
+        transaction_id: u32,
+        balance: Money,
+        operation_id: u64,
+        fee: Money,
+    ) -> Self {
         Self {
             account_id,
             transaction_id,
             balance,
+            operation_id,
+            fee,
         }
     }
 }
@@ -64,24 +100,30 @@ impl TransactionResponse {
 /// Структура для парсинга деталий перевода со счета на счет.
 #[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransferRequest {
-    pub account_from: u32,
-    pub account_to: u32,
-    pub transfer_value: f64,
+    pub account_from: AccountKey,
+    pub account_to: AccountKey,
+    pub transfer_value: Money,
+    #[serde(default)]
+    pub reference: Option<String>,
 }
 
 /// Структура ответа успешного перевода.
-#[derive(Serialize, ToSchema)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct TransferResponse {
     pub info: String,
     pub details: TransferRequest,
+    pub operation_id: u64,
+    pub fee: Money,
 }
 
 impl TransferResponse {
     /// Конструктор ответа успешного перевода.
-    pub fn new(details: TransferRequest) -> Self {
+    pub fn new(details: TransferRequest, operation_id: u64, fee: Money) -> Self {
         Self {
             info: "successfully transfered".to_string(),
             details,
+            operation_id,
+            fee,
         }
     }
 }
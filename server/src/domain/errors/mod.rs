@@ -1,7 +1,9 @@
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::json;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
 use thiserror::Error;
 use utoipa::ToSchema;
 
@@ -12,21 +14,75 @@ pub type Result<T, E = AppError> = core::result::Result<T, E>;
 #[derive(Debug, Error, ToSchema)]
 pub enum AppError {
     // #[schema(example = "Some error from enum 'Errors'")]
-    // счет не существует
-    #[error("account with name: '{0}' not found")]
+    // некорректный http запрос (тело, путь, заголовки)
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    // тело http запроса больше лимита
+    #[error("payload too large: {0}")]
+    PayloadTooLarge(String),
+    // Content-Type тела http запроса не json
+    #[error("unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+    // json тело http запроса не соответствует схеме (поле с ошибкой, если известно)
+    #[error("invalid request body: {1}")]
+    InvalidBody(Option<String>, String),
+    // счет не существует (ключ поиска: id или имя)
+    #[error("account: '{0}' not found")]
     AccountNotExists(String),
+    // счет с таким именем уже существует
+    #[error("account with name: '{0}' already exists")]
+    AccountNameExists(String),
+    // некорректное имя счета
+    #[error("invalid account name: '{0}'")]
+    InvalidAccountName(String),
     // транзакция не существует
     #[error("account: '{0}' has no transaction with id: '{1}'")]
     TransactionNotExists(String, String),
+    // операция не существует
+    #[error("operation with id: '{0}' not found")]
+    OperationNotExists(String),
+    // слишком длинное назначение платежа
+    #[error("reference is longer than {0} characters")]
+    InvalidReference(usize),
+    // некорректные параметры запроса истории (курсор, размер страницы)
+    #[error("invalid history query: {0}")]
+    InvalidHistoryQuery(String),
+    // некорректный ключ идемпотентности
+    #[error("invalid idempotency key: must be 1 to {0} printable ASCII characters")]
+    InvalidIdempotencyKey(usize),
+    // ключ идемпотентности повторно использован с другим запросом
+    #[error("idempotency key: '{0}' was already used with a different request")]
+    IdempotencyKeyReused(String),
     // транзакция со значением 0
     #[error("forbid transaction with 0 or less")]
     ZeroValueTransaction,
     // на счете не хватает средств для проведения транзакция
     #[error("account balance less than operation value")]
     Overdraft,
+    // некорректная денежная сумма
+    #[error("invalid money amount: '{0}'")]
+    InvalidAmount(String),
+    // переполнение баланса счета
+    #[error("account balance overflow")]
+    BalanceOverflow,
     // транзакция самому себе
     #[error("forbid transaction to yourself")]
     SelfTransfer,
+    // операция по закрытому счету
+    #[error("account: '{0}' is closed")]
+    AccountClosed(String),
+    // операция по замороженному счету
+    #[error("account: '{0}' is frozen")]
+    AccountFrozen(String),
+    // закрытие счета с ненулевым балансом
+    #[error("account: '{0}' has non-zero balance")]
+    NonZeroBalance(String),
+    // счет уже имеет запрошенный статус
+    #[error("account: '{0}' already has status: '{1}'")]
+    StatusUnchanged(String, String),
+    // списание или изменение статуса системного счета
+    #[error("account: '{0}' is a system account")]
+    SystemAccount(String),
     // пустая бд
     #[error("empty database")]
     EmptyDb,
@@ -39,30 +95,275 @@ pub enum AppError {
     // ошибка загрузки файла репликации backup.json
     #[error("backup load file error")]
     BackupLoadFile,
+    // состояние, восстановленное из журнала, не совпадает с исходным
+    #[error("replay mismatch: {0}")]
+    ReplayMismatch(String),
+    // ошибка хранилища (SQLite и т.п.)
+    #[error("storage error: {0}")]
+    StorageFailure(String),
+    // бд восстанавливается при старте сервиса, операции записи отклоняются
+    #[error("storage recovery in progress")]
+    Recovering,
+    // бд закрыта после сбоя (паника под блокировкой), который не удалось исправить перечитыванием
+    #[error("storage unavailable: {0}")]
+    StorageUnavailable(String),
     // // остальные
     // #[error(transparent)]
     // Other(#[from] anyhow::Error),
 }
 
-/// Имплементация для Axum Response.
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, err_msg) = match self {
-            AppError::AccountNotExists(_) | AppError::TransactionNotExists(_, _) => {
-                (StatusCode::NOT_FOUND, self.to_string())
+impl AppError {
+    /// Стабильный машиночитаемый код ошибки (не меняется вместе с текстом сообщения).
+    pub fn reason(&self) -> &'static str {
+        match self {
+            AppError::InvalidRequest(_) => "INVALID_REQUEST",
+            AppError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            AppError::UnsupportedMediaType(_) => "UNSUPPORTED_MEDIA_TYPE",
+            AppError::InvalidBody(_, _) => "INVALID_BODY",
+            AppError::AccountNotExists(_) => "ACCOUNT_NOT_FOUND",
+            AppError::AccountNameExists(_) => "ACCOUNT_NAME_EXISTS",
+            AppError::InvalidAccountName(_) => "INVALID_ACCOUNT_NAME",
+            AppError::TransactionNotExists(_, _) => "TRANSACTION_NOT_FOUND",
+            AppError::OperationNotExists(_) => "OPERATION_NOT_FOUND",
+            AppError::InvalidReference(_) => "INVALID_REFERENCE",
+            AppError::InvalidHistoryQuery(_) => "INVALID_HISTORY_QUERY",
+            AppError::InvalidIdempotencyKey(_) => "INVALID_IDEMPOTENCY_KEY",
+            AppError::IdempotencyKeyReused(_) => "IDEMPOTENCY_KEY_REUSED",
+            AppError::ZeroValueTransaction => "ZERO_VALUE_TRANSACTION",
+            AppError::Overdraft => "OVERDRAFT",
+            AppError::InvalidAmount(_) => "INVALID_AMOUNT",
+            AppError::BalanceOverflow => "BALANCE_OVERFLOW",
+            AppError::SelfTransfer => "SELF_TRANSFER",
+            AppError::AccountClosed(_) => "ACCOUNT_CLOSED",
+            AppError::AccountFrozen(_) => "ACCOUNT_FROZEN",
+            AppError::NonZeroBalance(_) => "NON_ZERO_BALANCE",
+            AppError::StatusUnchanged(_, _) => "STATUS_UNCHANGED",
+            AppError::SystemAccount(_) => "SYSTEM_ACCOUNT",
+            AppError::EmptyDb => "EMPTY_DB",
+            AppError::EmptyBackupFile => "EMPTY_BACKUP_FILE",
+            AppError::InvalidBackupFile => "INVALID_BACKUP_FILE",
+            AppError::BackupLoadFile => "BACKUP_LOAD_FILE",
+            AppError::ReplayMismatch(_) => "REPLAY_MISMATCH",
+            AppError::StorageFailure(_) => "STORAGE_FAILURE",
+            AppError::Recovering => "RECOVERING",
+            AppError::StorageUnavailable(_) => "STORAGE_UNAVAILABLE",
+        }
+    }
+
+    /// Параметры ошибки: счет, транзакция, операция и т.п.
+    pub fn metadata(&self) -> Vec<(&'static str, String)> {
+        match self {
+            AppError::AccountNotExists(account)
+            | AppError::AccountClosed(account)
+            | AppError::AccountFrozen(account)
+            | AppError::NonZeroBalance(account)
+            | AppError::SystemAccount(account) => vec![("account", account.clone())],
+            AppError::AccountNameExists(name) | AppError::InvalidAccountName(name) => {
+                vec![("name", name.clone())]
             }
-            AppError::ZeroValueTransaction
+            AppError::TransactionNotExists(account, tx_id) => vec![
+                ("account", account.clone()),
+                ("transaction_id", tx_id.clone()),
+            ],
+            AppError::OperationNotExists(op_id) => vec![("operation_id", op_id.clone())],
+            AppError::StatusUnchanged(account, status) => {
+                vec![("account", account.clone()), ("status", status.clone())]
+            }
+            AppError::InvalidReference(max_len) | AppError::InvalidIdempotencyKey(max_len) => {
+                vec![("max_len", max_len.to_string())]
+            }
+            AppError::IdempotencyKeyReused(key) => vec![("idempotency_key", key.clone())],
+            AppError::InvalidAmount(amount) => vec![("amount", amount.clone())],
+            AppError::InvalidBody(Some(field), _) => vec![("field", field.clone())],
+            _ => Vec::new(),
+        }
+    }
+}
+
+tokio::task_local! {
+    /// Id текущего http запроса (устанавливается middleware).
+    pub static REQUEST_ID: String;
+}
+
+/// Тело ответа с ошибкой.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorBody,
+}
+
+/// Описание ошибки.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// Стабильный код ошибки, например `ACCOUNT_NOT_FOUND`.
+    #[schema(example = "OVERDRAFT")]
+    pub code: String,
+    /// Сообщение для человека, может меняться.
+    #[schema(example = "account balance less than operation value")]
+    pub message: String,
+    /// Параметры ошибки: счет, транзакция, операция и т.п.
+    pub fields: BTreeMap<String, String>,
+    /// Id запроса (заголовок `x-request-id`).
+    #[schema(example = "1b4e28ba-2fa1-11d2-883f-0016d3cca427")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Конструктор тела ответа с ошибкой для текущего запроса.
+    pub fn new(err: &AppError) -> Self {
+        Self {
+            error: ErrorBody {
+                code: err.reason().to_string(),
+                message: err.to_string(),
+                fields: err
+                    .metadata()
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+                request_id: REQUEST_ID.try_with(String::clone).ok(),
+            },
+        }
+    }
+}
+
+impl AppError {
+    /// Http статус ошибки.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::AccountNotExists(_)
+            | AppError::TransactionNotExists(_, _)
+            | AppError::OperationNotExists(_)
+            | AppError::EmptyDb => StatusCode::NOT_FOUND,
+            AppError::InvalidRequest(_)
+            | AppError::InvalidAccountName(_)
+            | AppError::InvalidReference(_)
+            | AppError::InvalidIdempotencyKey(_)
+            | AppError::InvalidHistoryQuery(_)
+            | AppError::InvalidAmount(_)
+            | AppError::ZeroValueTransaction
+            | AppError::SelfTransfer => StatusCode::BAD_REQUEST,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::AccountNameExists(_)
+            | AppError::AccountClosed(_)
+            | AppError::AccountFrozen(_)
+            | AppError::NonZeroBalance(_)
+            | AppError::StatusUnchanged(_, _)
+            | AppError::SystemAccount(_) => StatusCode::CONFLICT,
+            AppError::InvalidBody(_, _)
+            | AppError::IdempotencyKeyReused(_)
             | AppError::Overdraft
-            | AppError::SelfTransfer
-            | AppError::EmptyDb
-            | AppError::EmptyBackupFile
+            | AppError::BalanceOverflow => StatusCode::UNPROCESSABLE_ENTITY,
+            // AppError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::EmptyBackupFile
             | AppError::InvalidBackupFile
-            | AppError::BackupLoadFile => (StatusCode::BAD_REQUEST, self.to_string()),
-            // AppError::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
-        };
-        let body = Json(json!({
-            "error": err_msg,
-        }));
-        (status, body).into_response()
+            | AppError::BackupLoadFile
+            | AppError::ReplayMismatch(_)
+            | AppError::StorageFailure(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Recovering | AppError::StorageUnavailable(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        }
+    }
+
+    /// Пример тела ответа для документации.
+    pub fn example(&self) -> Value {
+        let mut body: ErrorResponse = ErrorResponse::new(self);
+        body.error.request_id = Some("1b4e28ba-2fa1-11d2-883f-0016d3cca427".to_string());
+        serde_json::to_value(body).unwrap_or_default()
+    }
+}
+
+/// Имплементация для Axum Response.
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        (self.status(), Json(ErrorResponse::new(&self))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AppError::*;
+    use super::*;
+
+    #[test]
+    fn status_and_code_follow_error() {
+        let cases: Vec<(AppError, StatusCode, &str)> = vec![
+            (
+                InvalidRequest("bad json".to_string()),
+                StatusCode::BAD_REQUEST,
+                "INVALID_REQUEST",
+            ),
+            (
+                PayloadTooLarge("length limit exceeded".to_string()),
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "PAYLOAD_TOO_LARGE",
+            ),
+            (
+                UnsupportedMediaType("expected application/json".to_string()),
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "UNSUPPORTED_MEDIA_TYPE",
+            ),
+            (
+                InvalidBody(Some("name".to_string()), "invalid type".to_string()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "INVALID_BODY",
+            ),
+            (
+                AccountNotExists("savings".to_string()),
+                StatusCode::NOT_FOUND,
+                "ACCOUNT_NOT_FOUND",
+            ),
+            (
+                AccountFrozen("1".to_string()),
+                StatusCode::CONFLICT,
+                "ACCOUNT_FROZEN",
+            ),
+            (
+                SystemAccount("1".to_string()),
+                StatusCode::CONFLICT,
+                "SYSTEM_ACCOUNT",
+            ),
+            (SelfTransfer, StatusCode::BAD_REQUEST, "SELF_TRANSFER"),
+            (Overdraft, StatusCode::UNPROCESSABLE_ENTITY, "OVERDRAFT"),
+            (
+                IdempotencyKeyReused("key".to_string()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "IDEMPOTENCY_KEY_REUSED",
+            ),
+            (
+                StorageFailure("disk full".to_string()),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "STORAGE_FAILURE",
+            ),
+            (Recovering, StatusCode::SERVICE_UNAVAILABLE, "RECOVERING"),
+        ];
+        for (err, status, code) in cases {
+            assert_eq!(err.status(), status, "{}", err);
+            assert_eq!(err.reason(), code, "{}", err);
+        }
+    }
+
+    #[test]
+    fn not_found_names_lookup_key() {
+        let by_name = AccountNotExists("savings".to_string());
+        assert_eq!(by_name.to_string(), "account: 'savings' not found");
+        assert_eq!(
+            AccountNotExists("42".to_string()).to_string(),
+            "account: '42' not found"
+        );
+        assert_eq!(by_name.metadata(), vec![("account", "savings".to_string())]);
+    }
+
+    #[test]
+    fn envelope_contains_code_message_and_fields() {
+        let err = TransactionNotExists("7".to_string(), "3".to_string());
+        let body: ErrorResponse = ErrorResponse::new(&err);
+
+        assert_eq!(body.error.code, "TRANSACTION_NOT_FOUND");
+        assert_eq!(body.error.message, err.to_string());
+        assert_eq!(body.error.fields["account"], "7");
+        assert_eq!(body.error.fields["transaction_id"], "3");
+        // вне http запроса id нет
+        assert_eq!(body.error.request_id, None);
     }
 }
@@ -1,174 +1,1151 @@
 use crate::adapter::storage::cache::Cache;
+use crate::adapter::storage::locks::LockKey;
+use crate::adapter::storage::wal::WalEntry;
 use crate::adapter::storage::Storages;
-use crate::domain::entities::account::{Account, BalanceResponse};
+use crate::domain::entities::account::{
+    Account, AccountKey, BalanceResponse, Status, StatusResponse,
+};
+use crate::domain::entities::fee::FEE_ACCOUNT;
+use crate::domain::entities::idempotency::IdempotencyRecord;
+use crate::domain::entities::journal::Command;
+use crate::domain::entities::money::Money;
 use crate::domain::entities::transaction::Operation::{
-    Replenish, TransferDecrease, TransferIncrease, Withdraw,
+    Commission, CommissionIncome, Replenish, TransferDecrease, TransferIncrease, Withdraw,
 };
 use crate::domain::entities::transaction::{
     Operation, Transaction, TransactionResponse, TransferRequest, TransferResponse,
+    REFERENCE_MAX_LEN,
 };
 use crate::domain::errors::AppError;
 use crate::domain::errors::AppError::{
-    AccountNotExists, Overdraft, SelfTransfer, ZeroValueTransaction,
+    AccountClosed, AccountFrozen, AccountNotExists, BalanceOverflow, InvalidReference,
+    NonZeroBalance, Overdraft, Recovering, SelfTransfer, StatusUnchanged, SystemAccount,
+    ZeroValueTransaction,
 };
-use std::sync::{Arc, RwLock};
+use crate::domain::usecases::idempotency::Idempotency;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Проверка готовности бд к запросам: при восстановлении на старте бд еще пуста,
+/// запросы отклоняются, чтобы не записать в пустую бд и не вернуть неполные данные.
+pub(crate) fn ready<S: Storages>(storage: &S) -> Result<(), AppError> {
+    if storage.recovering() {
+        return Err(Recovering);
+    }
 
-/// Создание нового счета.
-pub fn new_account<S: Storages>(storage: Arc<RwLock<S>>) -> TransactionResponse {
-    // // получение названия последнего счета
-    // let last_name: String = storage.write().unwrap().db().get_last_account_name();
+    Ok(())
+}
+
+/// Поиск id счета по ключу (id или имя).
+pub fn resolve<C: Cache + ?Sized>(db: &C, key: &AccountKey) -> Result<u32, AppError> {
+    let acc_id: Option<u32> = match key {
+        AccountKey::Id(id) => Some(*id).filter(|id| db.check_key(*id)),
+        AccountKey::Name(name) => db.find_account(name),
+    };
 
+    acc_id.ok_or_else(|| AccountNotExists(key.to_string()))
+}
+
+/// Новый счет с транзакцией регистрации.
+fn registration(name: Option<String>, timestamp: DateTime<Utc>) -> Account {
     // создание нового счета
     let mut account: Account = Account::new();
-    // let acc_id: u32 = account.id.clone();
-
+    account.name = name;
     // создание транзакиции о создании счета
-    let tx_new: Transaction = Transaction::new(
+    let mut tx_new: Transaction = Transaction::new(
         0_u32,
         Operation::default(),
-        f64::default(),
-        f64::default(),
-        f64::default(),
+        Money::ZERO,
+        Money::ZERO,
+        Money::ZERO,
     );
+    tx_new.timestamp = timestamp;
     // добавление транзакции в список транзакций счета
     account.transactions.push(tx_new);
+
+    account
+}
+
+/// Создание нового счета с необязательным уникальным именем.
+pub fn new_account<S: Storages>(
+    storage: Arc<S>,
+    name: Option<String>,
+) -> Result<TransactionResponse, AppError> {
+    // проверка имени счета
+    if let Some(name) = &name {
+        Account::validate_name(name)?;
+    }
+
+    register(storage, name)
+}
+
+/// Открытие системного счета комиссий при повторе операций журнала (имя зарезервировано).
+pub(crate) fn new_fee_account<S: Storages>(
+    storage: Arc<S>,
+) -> Result<TransactionResponse, AppError> {
+    register(storage, Some(FEE_ACCOUNT.to_string()))
+}
+
+/// Создание счета без проверки имени.
+fn register<S: Storages>(
+    storage: Arc<S>,
+    name: Option<String>,
+) -> Result<TransactionResponse, AppError> {
+    ready(storage.as_ref())?;
+    // новый счет еще никому не виден: блокировка счетов не нужна,
+    // счета с одним именем открываются по очереди, имя проверяется при записи операции
+    let keys: Vec<LockKey> = name.iter().map(|name| LockKey::Name(name)).collect();
+    let lock = storage.lock(&keys);
     // добавление счета в db
-    let acc_id: u32 = storage.write().unwrap().db().create_account(account);
-    // body
-    let tx: TransactionResponse = TransactionResponse::new(acc_id, 0_u32, 0_f64);
+    let tx: TransactionResponse = create_account(storage.as_ref(), name.clone())?;
+    drop(lock);
     // backup
-    storage.write().unwrap().db().backup_store();
+    storage.backup();
 
-    tx
+    Ok(tx)
 }
 
-/// Изменение баланса счета.
-pub fn change_acc_balance<S: Storages>(
-    storage: &Arc<RwLock<S>>,
-    trans_value: f64,
-    account_id: u32,
-    operation: Operation,
+/// Фиксация нового счета: id счета, операции и время выдаются при записи операции.
+fn create_account<S: Storages>(
+    storage: &S,
+    name: Option<String>,
 ) -> Result<TransactionResponse, AppError> {
-    // проверка наличия счета
-    if !storage.write().unwrap().db().check_key(account_id) {
-        return Err(AccountNotExists(account_id.to_string()));
+    storage.commit(|seq| {
+        let mut account: Account = registration(name, seq.timestamp);
+        account.id = seq.account_id;
+        let tx: TransactionResponse = TransactionResponse::new(
+            seq.account_id,
+            0_u32,
+            Money::ZERO,
+            seq.operation_id,
+            Money::ZERO,
+        );
+
+        Ok((
+            WalEntry::account(account, seq.operation_id, seq.timestamp),
+            tx,
+        ))
+    })
+}
+
+/// Id системного счета комиссий. Счет создается, если его еще нет.
+fn fee_account_id<S: Storages>(storage: &S) -> Result<u32, AppError> {
+    // счет комиссий открывается один раз: повторная проверка под блокировкой имени
+    let _lock = storage.lock(&[LockKey::Name(FEE_ACCOUNT)]);
+    if let Some(acc_id) = storage.db()?.find_account(FEE_ACCOUNT) {
+        return Ok(acc_id);
     }
-    // проверка на наличие изменение баланса на 0 или меньше
-    if trans_value <= 0_f64 {
-        return Err(ZeroValueTransaction);
+
+    Ok(create_account(storage, Some(FEE_ACCOUNT.to_string()))?.account_id)
+}
+
+/// Открытие системного счета комиссий при старте сервиса.
+/// Backup не делается: реплика перезапишется при первой операции.
+pub fn open_fee_account<S: Storages>(storage: &Arc<S>) -> Result<u32, AppError> {
+    fee_account_id(storage.as_ref())
+}
+
+/// Id счета комиссий для блокировки вместе со счетами операции.
+/// Если счет не открыт при старте, он создается до блокировки счетов операции.
+fn fee_account<S: Storages>(storage: &S) -> Result<u32, AppError> {
+    if let Some(acc_id) = storage.db()?.find_account(FEE_ACCOUNT) {
+        return Ok(acc_id);
     }
 
-    let mut binding = storage.write().unwrap();
-    // получение счета
-    let cur_acc: &mut Account = binding.db().get_mut_account(account_id);
+    fee_account_id(storage)
+}
+
+/// Расчет новой транзакции по балансу счета (без изменения бд).
+fn next_tx(
+    id: u32,
+    balance: Money,
+    trans_value: Money,
+    operation: Operation,
+) -> Result<Transaction, AppError> {
+    let increase: bool = matches!(operation, Replenish | TransferIncrease | CommissionIncome);
     // проверка на снятие или перевод больше, чем есть на счете
-    if matches!(operation, Withdraw | TransferDecrease) && cur_acc.balance < trans_value {
+    if !increase && balance < trans_value {
         return Err(Overdraft);
     }
-    // id последней транзакции (совпадает с индексом)
-    let last_tx_id: u32 = (cur_acc.transactions.len() - 1) as u32;
-    // id новой транзакции
-    let new_tx_id: u32 = last_tx_id + 1;
     // новый баланс счета
-    let new_balance: f64 = if operation == Replenish || operation == TransferIncrease {
+    let new_balance: Money = if increase {
         // пополнение счета
-        cur_acc.transactions[last_tx_id as usize].current + trans_value
+        balance.checked_add(trans_value)
     } else {
         // списание со счета
-        cur_acc.transactions[last_tx_id as usize].current - trans_value
-    };
+        balance.checked_sub(trans_value)
+    }
+    .ok_or(BalanceOverflow)?;
     // создание новой транзакции
-    let tx_new: Transaction = Transaction::new(
-        new_tx_id,
+    Ok(Transaction::new(
+        id,
         operation,
-        cur_acc.transactions[last_tx_id as usize].current,
+        balance,
         trans_value,
         new_balance,
-    );
-    // добавление транзакции в бд
-    cur_acc.transactions.push(tx_new);
-    // обновление текущего баланса счета
-    cur_acc.balance = new_balance;
-    // body
-    let tx: TransactionResponse = TransactionResponse::new(account_id, new_tx_id, cur_acc.balance);
+    ))
+}
+
+/// Проводки одной операции.
+/// Каждая транзакция рассчитывается от результата предыдущей проводки того же счета.
+struct Legs {
+    entries: Vec<(u32, Transaction)>,
+    // назначение платежа
+    reference: Option<String>,
+}
+
+impl Legs {
+    /// Конструктор проводок операции.
+    fn new(reference: Option<String>) -> Self {
+        Self {
+            entries: Vec::new(),
+            reference,
+        }
+    }
+
+    /// Проводки со временем операции (выдается при записи операции).
+    fn stamped(self, timestamp: DateTime<Utc>) -> Vec<(u32, Transaction)> {
+        self.entries
+            .into_iter()
+            .map(|(acc_id, mut tx)| {
+                tx.timestamp = timestamp;
+                (acc_id, tx)
+            })
+            .collect()
+    }
+
+    /// Добавление проводки по счету.
+    fn push<C: Cache + ?Sized>(
+        &mut self,
+        db: &C,
+        acc_id: u32,
+        trans_value: Money,
+        operation: Operation,
+        counterparty: Option<u32>,
+    ) -> Result<&Transaction, AppError> {
+        let mut tx_new: Transaction = match self.entries.iter().rev().find(|(id, _)| *id == acc_id)
+        {
+            // следующая проводка того же счета
+            Some((_, prev)) => next_tx(prev.id + 1, prev.current, trans_value, operation)?,
+            None => {
+                let account: &Account = db.get_account(acc_id)?;
+                // операции разрешены только по открытому счету
+                match account.status {
+                    Status::Opened => {}
+                    Status::Closed => return Err(AccountClosed(account.id.to_string())),
+                    Status::Frozen => return Err(AccountFrozen(account.id.to_string())),
+                }
+                // со счета комиссий списания запрещены
+                if account.is_system() && matches!(operation, Withdraw | TransferDecrease) {
+                    return Err(SystemAccount(account.id.to_string()));
+                }
+                // id новой транзакции (совпадает с индексом)
+                let tx_id: u32 = account.transactions.len() as u32;
+                next_tx(tx_id, account.balance, trans_value, operation)?
+            }
+        };
+        tx_new.reference = self.reference.clone();
+        tx_new.counterparty = counterparty;
+        self.entries.push((acc_id, tx_new));
+
+        Ok(&self.entries[self.entries.len() - 1].1)
+    }
+
+    /// Проводки комиссии: списание с плательщика и зачисление на счет комиссий.
+    fn push_fee<C: Cache + ?Sized>(
+        &mut self,
+        db: &C,
+        payer: u32,
+        fee_account: Option<u32>,
+        fee: Money,
+    ) -> Result<(), AppError> {
+        let Some(fee_acc) = fee_account.filter(|_| !fee.is_zero()) else {
+            return Ok(());
+        };
+        self.push(db, payer, fee, Commission, Some(fee_acc))?;
+        self.push(db, fee_acc, fee, CommissionIncome, Some(payer))?;
+
+        Ok(())
+    }
+
+    /// Последняя проводка счета.
+    fn last(&self, acc_id: u32) -> Option<&Transaction> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == acc_id)
+            .map(|(_, tx)| tx)
+    }
+}
+
+/// Комиссия операции по тарифу. Операции счета комиссий проводятся без комиссии.
+fn tariff<S: Storages>(
+    storage: &S,
+    payer: u32,
+    operation: &Operation,
+    value: Money,
+) -> Result<Money, AppError> {
+    if storage.db()?.find_account(FEE_ACCOUNT) == Some(payer) {
+        return Ok(Money::ZERO);
+    }
+
+    Ok(storage.fees().charge(operation, value))
+}
+
+/// Ключи блокировки операции: счета и ключ идемпотентности.
+fn lock_keys<'a>(
+    accounts: &[Option<u32>],
+    idempotency: &'a Option<Idempotency>,
+) -> Vec<LockKey<'a>> {
+    let mut keys: Vec<LockKey> = accounts
+        .iter()
+        .flatten()
+        .map(|id| LockKey::Account(*id))
+        .collect();
+    if let Some(idempotency) = idempotency {
+        keys.push(LockKey::Idempotency(idempotency.key()));
+    }
+
+    keys
+}
+
+/// Проверка назначения платежа.
+fn check_reference(reference: &Option<String>) -> Result<(), AppError> {
+    match reference {
+        Some(text) if text.chars().count() > REFERENCE_MAX_LEN => {
+            Err(InvalidReference(REFERENCE_MAX_LEN))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Изменение баланса счета с комиссией по тарифу.
+/// Повтор запроса с тем же ключом идемпотентности возвращает исходный ответ.
+pub fn change_acc_balance<S: Storages>(
+    storage: &Arc<S>,
+    trans_value: Money,
+    account: AccountKey,
+    operation: Operation,
+    reference: Option<String>,
+    idempotency_key: Option<String>,
+) -> Result<TransactionResponse, AppError> {
+    // отпечаток запроса
+    let request = json!({
+        "operation": operation,
+        "account_id": account,
+        "transaction_value": trans_value,
+        "reference": reference,
+    });
+    let idempotency: Option<Idempotency> = Idempotency::new(idempotency_key, &request)?;
+
+    charge_balance(
+        storage,
+        trans_value,
+        account,
+        operation,
+        reference,
+        None,
+        idempotency,
+    )
+}
+
+/// Изменение баланса счета.
+/// Комиссия: None - по тарифу, Some - заданная (при повторе операций журнала).
+pub(crate) fn charge_balance<S: Storages>(
+    storage: &Arc<S>,
+    trans_value: Money,
+    account: AccountKey,
+    operation: Operation,
+    reference: Option<String>,
+    fee: Option<Money>,
+    idempotency: Option<Idempotency>,
+) -> Result<TransactionResponse, AppError> {
+    // проверка на наличие изменение баланса на 0 или меньше
+    if !trans_value.is_positive() {
+        return Err(ZeroValueTransaction);
+    }
+    check_reference(&reference)?;
+
+    ready(storage.as_ref())?;
+    // ключи идемпотентности раньше `expired` не возвращают сохраненный ответ
+    let window: TimeDelta = storage.idempotency_window();
+    let expired: DateTime<Utc> = storage.now() - window;
+    // поиск счета
+    let account_id: u32 = resolve(&**storage.db()?, &account)?;
+    // комиссия
+    let fee: Money = match fee {
+        Some(fee) => fee,
+        None => tariff(storage.as_ref(), account_id, &operation, trans_value)?,
+    };
+    let fee_acc: Option<u32> = match fee.is_zero() {
+        true => None,
+        false => Some(fee_account(storage.as_ref())?),
+    };
+    // блокировка счетов операции до фиксации
+    let lock = storage.lock(&lock_keys(&[Some(account_id), fee_acc], &idempotency));
+    // повтор запроса с ключом идемпотентности
+    if let Some(idempotency) = &idempotency {
+        if let Some(tx) = idempotency.replayed(&**storage.db()?, expired)? {
+            return Ok(tx);
+        }
+    }
+    // операция для журнала
+    let command: Command = match operation {
+        Replenish | TransferIncrease => Command::Replenish {
+            account_id,
+            value: trans_value,
+            fee,
+            reference: reference.clone(),
+        },
+        _ => Command::Withdraw {
+            account_id,
+            value: trans_value,
+            fee,
+            reference: reference.clone(),
+        },
+    };
+    // новая транзакция и комиссия (счета операции заблокированы, бд - на чтение)
+    let mut legs: Legs = Legs::new(reference);
+    let (tx_id, balance): (u32, Money) = {
+        let db = storage.db()?;
+        let tx_id: u32 = legs
+            .push(&**db, account_id, trans_value, operation, None)?
+            .id;
+        legs.push_fee(&**db, account_id, fee_acc, fee)?;
+        let balance: Money = legs
+            .last(account_id)
+            .map(|tx| tx.current)
+            .unwrap_or_default();
+        (tx_id, balance)
+    };
+    // добавление транзакций в бд
+    let tx: TransactionResponse = storage.commit(|seq| {
+        // id операции выдается при записи: ответ для повторов запроса сохраняется вместе с операцией
+        let tx: TransactionResponse =
+            TransactionResponse::new(account_id, tx_id, balance, seq.operation_id, fee);
+        let saved: Option<IdempotencyRecord> = idempotency
+            .map(|idempotency| idempotency.record(&tx, seq.timestamp))
+            .transpose()?;
+        let entry: WalEntry = WalEntry::commit(
+            seq.operation_id,
+            command,
+            legs.stamped(seq.timestamp),
+            seq.timestamp,
+            saved.map(|saved| (saved, seq.timestamp - window)),
+        );
+
+        Ok((entry, tx))
+    })?;
+    drop(lock);
     // backup
-    binding.db().backup_store();
+    storage.backup();
 
     Ok(tx)
 }
 
-/// Перевод со счета на счет.
+/// Перевод со счета на счет с комиссией по тарифу.
+/// Повтор запроса с тем же ключом идемпотентности возвращает исходный ответ.
 pub fn transfer<S: Storages>(
-    storage: &Arc<RwLock<S>>,
+    storage: &Arc<S>,
     payload: TransferRequest,
+    idempotency_key: Option<String>,
 ) -> Result<TransferResponse, AppError> {
-    let p = payload.clone();
-    let tx_value: f64 = payload.transfer_value;
+    // отпечаток запроса
+    let request = json!({
+        "operation": TransferDecrease,
+        "request": payload,
+    });
+    let idempotency: Option<Idempotency> = Idempotency::new(idempotency_key, &request)?;
+
+    charge_transfer(storage, payload, None, idempotency)
+}
+
+/// Перевод со счета на счет.
+/// Списание, пополнение и комиссия проводятся под блокировкой всех счетов операции единой операцией.
+/// Комиссия: None - по тарифу, Some - заданная (при повторе операций журнала).
+pub(crate) fn charge_transfer<S: Storages>(
+    storage: &Arc<S>,
+    payload: TransferRequest,
+    fee: Option<Money>,
+    idempotency: Option<Idempotency>,
+) -> Result<TransferResponse, AppError> {
+    let tx_value: Money = payload.transfer_value;
     // проверка на наличие изменение баланса на 0 или меньше
-    if tx_value <= 0_f64 {
+    if !tx_value.is_positive() {
         return Err(ZeroValueTransaction);
     }
+    check_reference(&payload.reference)?;
+
+    ready(storage.as_ref())?;
+    // ключи идемпотентности раньше `expired` не возвращают сохраненный ответ
+    let window: TimeDelta = storage.idempotency_window();
+    let expired: DateTime<Utc> = storage.now() - window;
+    // поиск счетов
+    let (account_from, account_to): (u32, u32) = {
+        let db = storage.db()?;
+        (
+            resolve(&**db, &payload.account_from)?,
+            resolve(&**db, &payload.account_to)?,
+        )
+    };
     // проверка на перевод самому себе
-    if payload.account_to == payload.account_from {
+    if account_to == account_from {
         return Err(SelfTransfer);
     }
-    // проверка наличия счета
-    if !storage
-        .write()
-        .unwrap()
-        .db()
-        .check_key(payload.account_from)
-    {
-        return Err(AccountNotExists(payload.account_from.to_string()));
+    // комиссия отправителя
+    let fee: Money = match fee {
+        Some(fee) => fee,
+        None => tariff(storage.as_ref(), account_from, &TransferDecrease, tx_value)?,
+    };
+    let fee_acc: Option<u32> = match fee.is_zero() {
+        true => None,
+        false => Some(fee_account(storage.as_ref())?),
+    };
+    // блокировка обоих счетов и счета комиссий в порядке шардов (встречные переводы не блокируют друг друга)
+    let lock = storage.lock(&lock_keys(
+        &[Some(account_from), Some(account_to), fee_acc],
+        &idempotency,
+    ));
+    // повтор запроса с ключом идемпотентности
+    if let Some(idempotency) = &idempotency {
+        if let Some(tx) = idempotency.replayed(&**storage.db()?, expired)? {
+            return Ok(tx);
+        }
     }
-    // проверка наличия счета
-    if !storage.write().unwrap().db().check_key(payload.account_to) {
-        return Err(AccountNotExists(payload.account_to.to_string()));
+    // списание со счета отправителя, пополнение счета получателя, комиссия
+    let mut legs: Legs = Legs::new(payload.reference.clone());
+    {
+        let db = storage.db()?;
+        legs.push(
+            &**db,
+            account_from,
+            tx_value,
+            TransferDecrease,
+            Some(account_to),
+        )?;
+        legs.push(
+            &**db,
+            account_to,
+            tx_value,
+            TransferIncrease,
+            Some(account_from),
+        )?;
+        legs.push_fee(&**db, account_from, fee_acc, fee)?;
     }
-    // списание со счета отправителя
-    change_acc_balance(storage, tx_value, payload.account_from, TransferDecrease)?;
-    // пополнение счета получателя
-    change_acc_balance(storage, tx_value, payload.account_to, TransferIncrease)?;
+    // операция для журнала
+    let command = Command::Transfer {
+        account_from,
+        account_to,
+        value: tx_value,
+        fee,
+        reference: payload.reference.clone(),
+    };
+    // в ответе - id найденных счетов
+    let details = TransferRequest {
+        account_from: account_from.into(),
+        account_to: account_to.into(),
+        transfer_value: tx_value,
+        reference: payload.reference,
+    };
+    // проведение всех транзакций единой операцией
+    let tx: TransferResponse = storage.commit(|seq| {
+        // id операции выдается при записи: ответ для повторов запроса сохраняется вместе с операцией
+        let tx: TransferResponse = TransferResponse::new(details, seq.operation_id, fee);
+        let saved: Option<IdempotencyRecord> = idempotency
+            .map(|idempotency| idempotency.record(&tx, seq.timestamp))
+            .transpose()?;
+        let entry: WalEntry = WalEntry::commit(
+            seq.operation_id,
+            command,
+            legs.stamped(seq.timestamp),
+            seq.timestamp,
+            saved.map(|saved| (saved, seq.timestamp - window)),
+        );
 
-    // body
-    let tx: TransferResponse = TransferResponse::new(p);
+        Ok((entry, tx))
+    })?;
+    drop(lock);
     // backup
-    storage.write().unwrap().db().backup_store();
+    storage.backup();
 
     Ok(tx)
 }
 
-/// Баланса счета.
+/// Изменение статуса счета.
+fn change_status<S: Storages>(
+    storage: &Arc<S>,
+    account: AccountKey,
+    status: Status,
+) -> Result<StatusResponse, AppError> {
+    ready(storage.as_ref())?;
+    // поиск счета
+    let account_id: u32 = resolve(&**storage.db()?, &account)?;
+    // блокировка счета до фиксации
+    let lock = storage.lock(&[LockKey::Account(account_id)]);
+    let db = storage.db()?;
+    let account: &Account = db.get_account(account_id)?;
+    // счет комиссий всегда открыт
+    if account.is_system() {
+        return Err(SystemAccount(account_id.to_string()));
+    }
+    // проверка на повторное изменение статуса
+    if account.status == status {
+        return Err(StatusUnchanged(
+            account_id.to_string(),
+            format!("{:?}", status),
+        ));
+    }
+    // операция для журнала
+    let command: Command = match status {
+        Status::Opened => Command::Reopen { account_id },
+        Status::Closed => {
+            // закрыть можно только счет с нулевым балансом
+            if account.balance != Money::ZERO {
+                return Err(NonZeroBalance(account_id.to_string()));
+            }
+            Command::Close { account_id }
+        }
+        Status::Frozen => {
+            // закрытый счет заморозить нельзя
+            if account.status == Status::Closed {
+                return Err(AccountClosed(account_id.to_string()));
+            }
+            Command::Freeze { account_id }
+        }
+    };
+    drop(db);
+    // изменение статуса
+    let op_id: u64 = storage.commit(|seq| {
+        let entry: WalEntry = WalEntry::status(
+            seq.operation_id,
+            command,
+            account_id,
+            status.clone(),
+            seq.timestamp,
+        );
+
+        Ok((entry, seq.operation_id))
+    })?;
+    drop(lock);
+    // backup
+    storage.backup();
+
+    Ok(StatusResponse::new(account_id, status, op_id))
+}
+
+/// Закрытие счета. Баланс счета должен быть нулевым.
+pub fn close<S: Storages>(
+    storage: &Arc<S>,
+    account: AccountKey,
+) -> Result<StatusResponse, AppError> {
+    change_status(storage, account, Status::Closed)
+}
+
+/// Заморозка счета. Пополнение, списание и переводы по счету запрещены.
+pub fn freeze<S: Storages>(
+    storage: &Arc<S>,
+    account: AccountKey,
+) -> Result<StatusResponse, AppError> {
+    change_status(storage, account, Status::Frozen)
+}
+
+/// Повторное открытие закрытого или замороженного счета.
+pub fn reopen<S: Storages>(
+    storage: &Arc<S>,
+    account: AccountKey,
+) -> Result<StatusResponse, AppError> {
+    change_status(storage, account, Status::Opened)
+}
+
+/// Срез операций счета на момент `as_of`: число транзакций и статус счета.
+/// Операции счета просматриваются по журналу до первой операции позже `as_of`
+/// (время выдается при записи вместе с id операции: журнал упорядочен по времени).
+fn as_of<C: Cache + ?Sized>(
+    db: &C,
+    account: &Account,
+    as_of: DateTime<Utc>,
+) -> Result<(usize, Status), AppError> {
+    let records = db.get_account_journal(account.id);
+    let (count, status): (usize, Status) = if records.is_empty() {
+        // счет из данных без журнала: срез по времени транзакций, статус неизвестен
+        let count: usize = account
+            .transactions
+            .iter()
+            .position(|tx| tx.timestamp > as_of)
+            .unwrap_or(account.transactions.len());
+        (count, account.status.clone())
+    } else {
+        let mut count: usize = 0;
+        let mut status: Status = Status::Opened;
+        for record in records
+            .iter()
+            .take_while(|record| record.timestamp <= as_of)
+        {
+            match record.command {
+                Command::Close { .. } => status = Status::Closed,
+                Command::Freeze { .. } => status = Status::Frozen,
+                Command::Reopen { .. } => status = Status::Opened,
+                _ => {}
+            }
+            count += record
+                .transactions
+                .iter()
+                .filter(|tx| tx.account_id == account.id)
+                .count();
+        }
+        (count.min(account.transactions.len()), status)
+    };
+    // счет открыт позже `as_of`
+    if count == 0 {
+        return Err(AccountNotExists(account.id.to_string()));
+    }
+
+    Ok((count, status))
+}
+
+/// Баланс счета: текущий или на момент `as_of`.
 pub fn balance<S: Storages>(
-    storage: &Arc<RwLock<S>>,
-    account_id: u32,
+    storage: &Arc<S>,
+    account: AccountKey,
+    at: Option<DateTime<Utc>>,
 ) -> Result<BalanceResponse, AppError> {
-    // проверка наличия счета
-    if !storage.write().unwrap().db().check_key(account_id) {
-        return Err(AccountNotExists(account_id.to_string()));
-    }
-    let mut binding = storage.write().unwrap();
+    ready(storage.as_ref())?;
+    let db = storage.db()?;
+    // поиск счета
+    let account_id: u32 = resolve(&**db, &account)?;
     // получение счета
-    let account: &Account = binding.db().get_account(account_id);
-    // body
-    let balance: BalanceResponse = BalanceResponse::new(account.balance);
+    let account: &Account = db.get_account(account_id)?;
+    // баланс после последней транзакции среза
+    let balance: Money = match at {
+        Some(at) => {
+            let (count, _) = as_of(&**db, account, at)?;
+            account.transactions[count - 1].current
+        }
+        None => account.balance,
+    };
 
-    Ok(balance)
+    Ok(BalanceResponse::new(balance))
 }
 
-/// Получение всех транзакций счета.
+/// Получение счета со всеми транзакциями: текущего или на момент `as_of`.
 pub fn account<S: Storages>(
-    storage: &Arc<RwLock<S>>,
-    account_id: u32,
+    storage: &Arc<S>,
+    account: AccountKey,
+    at: Option<DateTime<Utc>>,
 ) -> Result<Account, AppError> {
-    // проверка наличия счета
-    if !storage.write().unwrap().db().check_key(account_id) {
-        return Err(AccountNotExists(account_id.to_string()));
+    ready(storage.as_ref())?;
+    let db = storage.db()?;
+    // поиск счета
+    let account_id: u32 = resolve(&**db, &account)?;
+    // получение счета
+    let account: &Account = db.get_account(account_id)?;
+    let Some(at) = at else {
+        return Ok(account.clone());
+    };
+    // счет на момент `as_of`
+    let (count, status) = as_of(&**db, account, at)?;
+    let transactions: Vec<Transaction> = account.transactions[..count].to_vec();
+
+    Ok(Account {
+        id: account.id,
+        name: account.name.clone(),
+        status,
+        balance: transactions[count - 1].current,
+        transactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::storage::cache::CacheImpl;
+    use crate::adapter::storage::{Storage, StorageState};
+    use crate::domain::entities::fee::FeeRule;
+    use crate::domain::entities::fee::FeeSchedule;
+    use crate::domain::entities::journal::TransactionRef;
+    use crate::domain::errors::AppError::InvalidAccountName;
+    use crate::domain::usecases::testing::{self, bank, bank_with_fees, money, open};
+    use std::thread;
+
+    /// Снимок бд: счета и число операций журнала.
+    fn state(storage: &StorageState) -> (Vec<Account>, usize) {
+        let db = storage.db().unwrap();
+        let mut accounts: Vec<Account> = db.get_accounts().values().cloned().collect();
+        accounts.sort_by_key(|acc| acc.id);
+        (accounts, db.get_journal().len())
     }
 
-    let mut binding = storage.write().unwrap();
-    // получение счета
-    let account: &Account = binding.db().get_account(account_id);
+    #[test]
+    fn transfer_moves_money_in_one_operation() {
+        let storage: StorageState = bank();
+        let from: u32 = open(&storage, "100.00");
+        let to: u32 = open(&storage, "0");
+        let journal: usize = state(&storage).1;
+
+        let tx: TransferResponse =
+            transfer(&storage, testing::transfer(from, to, "30.50"), None).unwrap();
+
+        assert_eq!(testing::balance(&storage, from), money("69.50"));
+        assert_eq!(testing::balance(&storage, to), money("30.50"));
+        // обе проводки - одна операция журнала
+        let db = storage.db().unwrap();
+        assert_eq!(db.get_journal().len(), journal + 1);
+        let record = &db.get_journal()[journal];
+        assert_eq!(record.id, tx.operation_id);
+        assert_eq!(
+            record.transactions,
+            vec![TransactionRef::new(from, 2), TransactionRef::new(to, 1)]
+        );
+        let credit: &Transaction = &db.get_account(to).unwrap().transactions[1];
+        assert_eq!(credit.operation, TransferIncrease);
+        assert_eq!(credit.counterparty, Some(from));
+    }
+
+    #[test]
+    fn failed_transfer_changes_nothing() {
+        let storage: StorageState = bank();
+        let from: u32 = open(&storage, "10.00");
+        let to: u32 = open(&storage, "5.00");
+        let frozen: u32 = open(&storage, "0");
+        freeze(&storage, frozen.into()).unwrap();
+        let before = state(&storage);
+
+        // овердрафт отправителя
+        assert!(matches!(
+            transfer(&storage, testing::transfer(from, to, "10.01"), None),
+            Err(Overdraft)
+        ));
+        // получатель заморожен: списание с отправителя не проводится
+        assert!(matches!(
+            transfer(&storage, testing::transfer(from, frozen, "1.00"), None),
+            Err(AccountFrozen(_))
+        ));
+        // получатель не существует
+        assert!(matches!(
+            transfer(&storage, testing::transfer(from, 99, "1.00"), None),
+            Err(AccountNotExists(_))
+        ));
+        // перевод самому себе и нулевая сумма
+        assert!(matches!(
+            transfer(&storage, testing::transfer(from, from, "1.00"), None),
+            Err(SelfTransfer)
+        ));
+        assert!(matches!(
+            transfer(&storage, testing::transfer(from, to, "0"), None),
+            Err(ZeroValueTransaction)
+        ));
+
+        assert_eq!(state(&storage), before);
+    }
+
+    #[test]
+    fn transfer_fee_fails_whole_operation() {
+        let storage: StorageState = bank_with_fees(FeeSchedule {
+            transfer: Some(FeeRule {
+                flat: money("1.00"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let from: u32 = open(&storage, "10.00");
+        let to: u32 = open(&storage, "0");
+        let before = state(&storage);
+
+        // на перевод хватает, на комиссию - нет
+        assert!(matches!(
+            transfer(&storage, testing::transfer(from, to, "9.50"), None),
+            Err(Overdraft)
+        ));
+        assert_eq!(state(&storage), before);
+
+        let tx: TransferResponse =
+            transfer(&storage, testing::transfer(from, to, "9.00"), None).unwrap();
+        assert_eq!(tx.fee, money("1.00"));
+        assert_eq!(testing::balance(&storage, from), Money::ZERO);
+        assert_eq!(testing::balance(&storage, to), money("9.00"));
+        assert_eq!(
+            testing::balance(&storage, AccountKey::Name(FEE_ACCOUNT.to_string())),
+            money("1.00")
+        );
+    }
+
+    #[test]
+    fn replenish_fee_does_not_exceed_credited_value() {
+        let storage: StorageState = bank_with_fees(FeeSchedule {
+            replenish: Some(FeeRule {
+                flat: money("1.00"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let id: u32 = open(&storage, "0");
+
+        // пополнение меньше фиксированной комиссии: комиссия - вся сумма, овердрафта нет
+        let tx: TransactionResponse =
+            change_acc_balance(&storage, money("0.50"), id.into(), Replenish, None, None).unwrap();
+        assert_eq!(tx.fee, money("0.50"));
+        assert_eq!(testing::balance(&storage, id), Money::ZERO);
+        let tx: TransactionResponse =
+            change_acc_balance(&storage, money("3.00"), id.into(), Replenish, None, None).unwrap();
+        assert_eq!(tx.fee, money("1.00"));
+        assert_eq!(testing::balance(&storage, id), money("2.00"));
+        assert_eq!(
+            testing::balance(&storage, AccountKey::Name(FEE_ACCOUNT.to_string())),
+            money("1.50")
+        );
+    }
+
+    #[test]
+    fn fee_account_name_is_reserved() {
+        let storage: StorageState = bank();
+
+        assert!(matches!(
+            new_account(Arc::clone(&storage), Some(FEE_ACCOUNT.to_string())),
+            Err(InvalidAccountName(_))
+        ));
+        assert!(storage.db().unwrap().find_account(FEE_ACCOUNT).is_none());
+    }
+
+    #[test]
+    fn fee_account_can_not_be_debited_or_changed() {
+        let storage: StorageState = bank_with_fees(FeeSchedule {
+            withdraw: Some(FeeRule {
+                flat: money("1.00"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let payer: u32 = open(&storage, "10.00");
+        change_acc_balance(&storage, money("5.00"), payer.into(), Withdraw, None, None).unwrap();
+        let fees = || AccountKey::Name(FEE_ACCOUNT.to_string());
+        let fee_id: u32 = storage.db().unwrap().find_account(FEE_ACCOUNT).unwrap();
+        let before = state(&storage);
+
+        // списания по имени и по id
+        assert!(matches!(
+            change_acc_balance(&storage, money("1.00"), fees(), Withdraw, None, None),
+            Err(SystemAccount(_))
+        ));
+        let req = TransferRequest {
+            account_from: fees(),
+            account_to: payer.into(),
+            transfer_value: money("1.00"),
+            reference: None,
+        };
+        assert!(matches!(
+            transfer(&storage, req, None),
+            Err(SystemAccount(_))
+        ));
+        assert!(matches!(
+            transfer(&storage, testing::transfer(fee_id, payer, "1.00"), None),
+            Err(SystemAccount(_))
+        ));
+        // изменения статуса
+        assert!(matches!(close(&storage, fees()), Err(SystemAccount(_))));
+        assert!(matches!(freeze(&storage, fees()), Err(SystemAccount(_))));
+        assert!(matches!(
+            reopen(&storage, fee_id.into()),
+            Err(SystemAccount(_))
+        ));
+        assert_eq!(state(&storage), before);
 
-    Ok(account.clone())
+        // зачисления разрешены
+        change_acc_balance(&storage, money("2.00"), fees(), Replenish, None, None).unwrap();
+        transfer(&storage, testing::transfer(payer, fee_id, "1.00"), None).unwrap();
+        assert_eq!(testing::balance(&storage, fees()), money("4.00"));
+    }
+
+    #[test]
+    fn concurrent_transfers_keep_total_balance() {
+        let storage: StorageState = bank();
+        let ids: Vec<u32> = (0..4).map(|_| open(&storage, "1000.00")).collect();
+
+        let workers: Vec<_> = (0..4)
+            .map(|n| {
+                let storage: StorageState = Arc::clone(&storage);
+                let ids: Vec<u32> = ids.clone();
+                thread::spawn(move || {
+                    for i in 0..200 {
+                        // встречные переводы между всеми парами счетов
+                        let from: u32 = ids[(n + i) % ids.len()];
+                        let to: u32 = ids[(n + i + 1 + i % 3) % ids.len()];
+                        if from != to {
+                            transfer(&storage, testing::transfer(from, to, "1.25"), None).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let (accounts, _) = state(&storage);
+        let total: Money = accounts.iter().fold(Money::ZERO, |sum, acc| {
+            sum.checked_add(acc.balance).unwrap()
+        });
+        assert_eq!(total, money("4000.00"));
+        // баланс каждого счета - итог его цепочки транзакций
+        for account in &accounts {
+            for pair in account.transactions.windows(2) {
+                assert_eq!(pair[0].current, pair[1].previous);
+                assert_eq!(pair[1].id, pair[0].id + 1);
+            }
+            assert_eq!(
+                account.transactions.last().unwrap().current,
+                account.balance
+            );
+        }
+    }
+
+    #[test]
+    fn concurrent_operations_are_persisted_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = CacheImpl::with_backup(dir.path()).with_snapshots(7);
+        let storage: StorageState = Arc::new(Storage::new(Box::new(db)));
+        let ids: Vec<u32> = (0..4).map(|_| open(&storage, "100.00")).collect();
+
+        let workers: Vec<_> = (0..4)
+            .map(|n| {
+                let storage: StorageState = Arc::clone(&storage);
+                let ids: Vec<u32> = ids.clone();
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        // переводы, новые счета и сброс на диск из нескольких потоков
+                        let from: u32 = ids[(n + i) % ids.len()];
+                        let to: u32 = ids[(n + i + 1) % ids.len()];
+                        transfer(&storage, testing::transfer(from, to, "0.50"), None).unwrap();
+                        if i % 10 == 0 {
+                            new_account(Arc::clone(&storage), Some(format!("w{n}-{i}"))).unwrap();
+                        }
+                        storage.backup();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // операции применены в порядке id без пропусков
+        let db = storage.db().unwrap();
+        for (n, record) in db.get_journal().iter().enumerate() {
+            assert_eq!(record.id, n as u64 + 1);
+        }
+        drop(db);
+        // после перезапуска - то же состояние из снимка и WAL
+        let mut restored = CacheImpl::with_backup(dir.path());
+        restored.backup_load().unwrap();
+        let mut accounts: Vec<Account> = restored.get_accounts().values().cloned().collect();
+        accounts.sort_by_key(|acc| acc.id);
+        assert_eq!((accounts, restored.get_journal().len()), state(&storage));
+    }
+
+    /// Время `hour` часов от начала 2024-01-01 (UTC).
+    fn at(hour: i64) -> DateTime<Utc> {
+        "2024-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap() + chrono::TimeDelta::hours(hour)
+    }
+
+    #[test]
+    fn balance_as_of_takes_operations_up_to_that_time() {
+        let storage: StorageState = bank();
+        storage.set_clock(Some(at(10)));
+        let acc_id: u32 = open(&storage, "100.00");
+        let other: u32 = open(&storage, "50.00");
+        storage.set_clock(Some(at(15)));
+        change_acc_balance(
+            &storage,
+            money("30.00"),
+            acc_id.into(),
+            Withdraw,
+            None,
+            None,
+        )
+        .unwrap();
+        storage.set_clock(Some(at(30)));
+        transfer(&storage, testing::transfer(other, acc_id, "20.00"), None).unwrap();
+        let balance_at = |as_of: Option<DateTime<Utc>>| {
+            balance(&storage, acc_id.into(), as_of).map(|b| b.balance)
+        };
+
+        // конец первого дня, время операции включается в срез, текущий баланс
+        assert_eq!(balance_at(Some(at(24))).unwrap(), money("70.00"));
+        assert_eq!(balance_at(Some(at(15))).unwrap(), money("70.00"));
+        assert_eq!(balance_at(Some(at(12))).unwrap(), money("100.00"));
+        assert_eq!(balance_at(None).unwrap(), money("90.00"));
+        // счет еще не открыт
+        assert!(matches!(balance_at(Some(at(9))), Err(AccountNotExists(_))));
+    }
+
+    #[test]
+    fn account_as_of_restores_status_and_transactions() {
+        let storage: StorageState = bank();
+        storage.set_clock(Some(at(1)));
+        let acc_id: u32 = open(&storage, "10.00");
+        storage.set_clock(Some(at(2)));
+        freeze(&storage, acc_id.into()).unwrap();
+        storage.set_clock(Some(at(4)));
+        reopen(&storage, acc_id.into()).unwrap();
+        change_acc_balance(
+            &storage,
+            money("5.00"),
+            acc_id.into(),
+            Replenish,
+            None,
+            None,
+        )
+        .unwrap();
+
+        let frozen: Account = account(&storage, acc_id.into(), Some(at(3))).unwrap();
+        assert_eq!(frozen.status, Status::Frozen);
+        assert_eq!(frozen.transactions.len(), 2);
+        assert_eq!(frozen.balance, money("10.00"));
+
+        let reopened: Account = account(&storage, acc_id.into(), Some(at(5))).unwrap();
+        assert_eq!(reopened.status, Status::Opened);
+        assert_eq!(reopened.balance, money("15.00"));
+        assert_eq!(reopened, account(&storage, acc_id.into(), None).unwrap());
+    }
+
+    #[test]
+    fn operation_time_follows_operation_order() {
+        let storage: StorageState = bank();
+        let ids: Vec<u32> = (0..4).map(|_| open(&storage, "10.00")).collect();
+
+        let workers: Vec<_> = (0..4)
+            .map(|n| {
+                let storage: StorageState = Arc::clone(&storage);
+                let ids: Vec<u32> = ids.clone();
+                thread::spawn(move || {
+                    for i in 0..50 {
+                        let from: u32 = ids[(n + i) % ids.len()];
+                        let to: u32 = ids[(n + i + 1) % ids.len()];
+                        transfer(&storage, testing::transfer(from, to, "0.01"), None).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        // время операций не убывает по журналу, проводки несут время своей операции
+        let db = storage.db().unwrap();
+        let journal = db.get_journal();
+        for pair in journal.windows(2) {
+            assert!(pair[0].timestamp <= pair[1].timestamp);
+        }
+        let accounts = db.get_accounts();
+        for record in journal.iter() {
+            for leg in &record.transactions {
+                let tx: &Transaction = accounts[&leg.account_id]
+                    .transactions
+                    .iter()
+                    .find(|tx| tx.id == leg.transaction_id)
+                    .unwrap();
+                assert_eq!(tx.timestamp, record.timestamp);
+            }
+        }
+    }
 }
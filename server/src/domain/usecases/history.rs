@@ -0,0 +1,339 @@
+use crate::adapter::storage::cache::Cache;
+use crate::adapter::storage::Storages;
+use crate::domain::entities::account::{Account, AccountKey};
+use crate::domain::entities::history::{Cursor, HistoryEntry, HistoryPage, HistoryQuery, Order};
+use crate::domain::entities::transaction::Transaction;
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::InvalidHistoryQuery;
+use crate::domain::usecases::account::{ready, resolve};
+use std::sync::Arc;
+
+/// Транзакция в порядке выдачи истории.
+type Entry<'a> = (u32, &'a Transaction);
+
+/// Страница истории счета.
+pub fn account_history<S: Storages>(
+    storage: &Arc<S>,
+    account: AccountKey,
+    query: HistoryQuery,
+) -> Result<HistoryPage, AppError> {
+    ready(storage.as_ref())?;
+    let limit: usize = query.limit()?;
+    let cursor: Option<Cursor> = query.cursor()?;
+    let db = storage.db()?;
+    // поиск счета
+    let account_id: u32 = resolve(&**db, &account)?;
+    // получение счета
+    let account: &Account = db.get_account(account_id)?;
+    // курсор страницы другого счета
+    if cursor.is_some_and(|cursor| cursor.account_id != account_id) {
+        return Err(InvalidHistoryQuery(format!(
+            "cursor does not belong to account: '{}'",
+            account_id
+        )));
+    }
+    let after: Option<u32> = cursor.map(|cursor| cursor.transaction_id);
+
+    Ok(page(entries(account, &query, after), &query, limit))
+}
+
+/// Страница истории всех счетов: транзакции по возрастанию (убыванию) id счета, затем id транзакции.
+pub fn transactions<S: Storages>(
+    storage: &Arc<S>,
+    query: HistoryQuery,
+) -> Result<HistoryPage, AppError> {
+    ready(storage.as_ref())?;
+    let limit: usize = query.limit()?;
+    let cursor: Option<Cursor> = query.cursor()?;
+    let db = storage.db()?;
+    // счета в порядке выдачи
+    let mut ids: Vec<u32> = db.get_accounts().keys().copied().collect();
+    ids.sort_unstable();
+    if query.order == Order::Desc {
+        ids.reverse();
+    }
+    // счета до курсора уже выданы
+    let start: usize = match cursor {
+        Some(cursor) => ids
+            .iter()
+            .position(|id| match query.order {
+                Order::Asc => *id >= cursor.account_id,
+                Order::Desc => *id <= cursor.account_id,
+            })
+            .unwrap_or(ids.len()),
+        None => 0,
+    };
+    let accounts = db.get_accounts();
+    let entries = ids[start..]
+        .iter()
+        .filter_map(|id| accounts.get(id))
+        .flat_map(|account| {
+            // транзакции счета курсора до курсора уже выданы
+            let after: Option<u32> = cursor
+                .filter(|cursor| cursor.account_id == account.id)
+                .map(|cursor| cursor.transaction_id);
+            entries(account, &query, after)
+        });
+
+    Ok(page(entries, &query, limit))
+}
+
+/// Транзакции счета в порядке выдачи после транзакции `after`.
+/// Диапазон id транзакций выбирается срезом: id транзакции совпадает с индексом.
+fn entries<'a>(
+    account: &'a Account,
+    query: &HistoryQuery,
+    after: Option<u32>,
+) -> Box<dyn Iterator<Item = Entry<'a>> + 'a> {
+    let len: usize = account.transactions.len();
+    let mut from: usize = query.from_id.map_or(0, |id| id as usize);
+    let mut to: usize = query.to_id.map_or(len, |id| id as usize + 1).min(len);
+    match (query.order, after) {
+        (Order::Asc, Some(after)) => from = from.max(after as usize + 1),
+        (Order::Desc, Some(after)) => to = to.min(after as usize),
+        (_, None) => {}
+    }
+    let txs: &[Transaction] = account.transactions.get(from..to).unwrap_or_default();
+    let id: u32 = account.id;
+    match query.order {
+        Order::Asc => Box::new(txs.iter().map(move |tx| (id, tx))),
+        Order::Desc => Box::new(txs.iter().rev().map(move |tx| (id, tx))),
+    }
+}
+
+/// Страница из первых `limit` транзакций, прошедших фильтры.
+/// Курсор следующей страницы возвращается, только если за страницей есть еще транзакции.
+fn page<'a>(
+    entries: impl Iterator<Item = Entry<'a>>,
+    query: &HistoryQuery,
+    limit: usize,
+) -> HistoryPage {
+    let mut found: Vec<Entry> = entries
+        .filter(|(_, tx)| query.matches(tx))
+        .take(limit + 1)
+        .collect();
+    let next_cursor: Option<String> = if found.len() > limit {
+        found.truncate(limit);
+        found
+            .last()
+            .map(|(account_id, tx)| Cursor::new(*account_id, tx.id).to_string())
+    } else {
+        None
+    };
+
+    HistoryPage {
+        transactions: found
+            .into_iter()
+            .map(|(account_id, tx)| HistoryEntry::new(account_id, tx.clone()))
+            .collect(),
+        next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::storage::StorageState;
+    use crate::domain::entities::transaction::Operation::{self, Replenish, Withdraw};
+    use crate::domain::usecases::account;
+    use crate::domain::usecases::testing::{bank, money, open};
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    /// Операция по счету.
+    fn charge(storage: &StorageState, acc_id: u32, amount: &str, operation: Operation) {
+        account::change_acc_balance(storage, money(amount), acc_id.into(), operation, None, None)
+            .unwrap();
+    }
+
+    /// Все страницы истории: (счет, id транзакции) по порядку выдачи и число страниц.
+    fn pages(
+        mut next: impl FnMut(HistoryQuery) -> Result<HistoryPage, AppError>,
+        query: HistoryQuery,
+    ) -> (Vec<(u32, u32)>, usize) {
+        let mut found: Vec<(u32, u32)> = Vec::new();
+        let mut pages: usize = 0;
+        let mut cursor: Option<String> = None;
+        loop {
+            let page: HistoryPage = next(HistoryQuery {
+                cursor: cursor.take(),
+                ..query.clone()
+            })
+            .unwrap();
+            pages += 1;
+            found.extend(
+                page.transactions
+                    .iter()
+                    .map(|entry| (entry.account_id, entry.transaction.id)),
+            );
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => return (found, pages),
+            }
+        }
+    }
+
+    #[test]
+    fn account_pages_follow_cursor_in_both_orders() {
+        let storage: StorageState = bank();
+        let acc_id: u32 = open(&storage, "0");
+        for _ in 0..7 {
+            charge(&storage, acc_id, "1.00", Replenish);
+        }
+        let history = |query| account_history(&storage, acc_id.into(), query);
+
+        // 8 транзакций страницами по 3: без пропусков и повторов, на последней странице нет курсора
+        let query = HistoryQuery {
+            limit: Some(3),
+            ..HistoryQuery::default()
+        };
+        let (asc, count) = pages(history, query.clone());
+        assert_eq!(count, 3);
+        assert_eq!(asc, (0..8).map(|id| (acc_id, id)).collect::<Vec<_>>());
+
+        let desc_query = HistoryQuery {
+            order: Order::Desc,
+            ..query
+        };
+        let (desc, count) = pages(history, desc_query);
+        assert_eq!(count, 3);
+        assert_eq!(desc, asc.into_iter().rev().collect::<Vec<_>>());
+
+        // ровно на границе страницы курсор не выдается
+        let page: HistoryPage = history(HistoryQuery {
+            limit: Some(8),
+            ..HistoryQuery::default()
+        })
+        .unwrap();
+        assert_eq!(page.transactions.len(), 8);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn filters_apply_before_limit() {
+        let storage: StorageState = bank();
+        let acc_id: u32 = open(&storage, "100.00");
+        for amount in ["1.00", "5.00", "20.00", "50.00"] {
+            charge(&storage, acc_id, amount, Withdraw);
+            charge(&storage, acc_id, "1.00", Replenish);
+        }
+        let history = |query| account_history(&storage, acc_id.into(), query);
+
+        // списания от 5 до 20 включительно, по одной на странице
+        let query = HistoryQuery {
+            limit: Some(1),
+            operation: Some(Withdraw),
+            min_amount: Some(money("5.00")),
+            max_amount: Some(money("20.00")),
+            ..HistoryQuery::default()
+        };
+        let (found, count) = pages(history, query);
+        assert_eq!(found, vec![(acc_id, 4), (acc_id, 6)]);
+        assert_eq!(count, 2);
+
+        // диапазон id включает обе границы
+        let query = HistoryQuery {
+            from_id: Some(2),
+            to_id: Some(4),
+            order: Order::Desc,
+            ..HistoryQuery::default()
+        };
+        let (found, _) = pages(history, query);
+        assert_eq!(found, vec![(acc_id, 4), (acc_id, 3), (acc_id, 2)]);
+    }
+
+    #[test]
+    fn time_range_excludes_its_end() {
+        let storage: StorageState = bank();
+        let start: DateTime<Utc> = "2024-01-01T00:00:00Z".parse().unwrap();
+        storage.set_clock(Some(start));
+        let acc_id: u32 = open(&storage, "0");
+        for hour in 1..=3 {
+            storage.set_clock(Some(start + TimeDelta::hours(hour)));
+            charge(&storage, acc_id, "1.00", Replenish);
+        }
+
+        let query = HistoryQuery {
+            from_time: Some(start + TimeDelta::hours(1)),
+            to_time: Some(start + TimeDelta::hours(3)),
+            ..HistoryQuery::default()
+        };
+        let page: HistoryPage = account_history(&storage, acc_id.into(), query).unwrap();
+        let ids: Vec<u32> = page.transactions.iter().map(|e| e.transaction.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn transactions_pages_cross_accounts() {
+        let storage: StorageState = bank();
+        let first: u32 = open(&storage, "10.00");
+        let second: u32 = open(&storage, "0");
+        let third: u32 = open(&storage, "20.00");
+        charge(&storage, first, "1.00", Withdraw);
+        let all: Vec<(u32, u32)> = vec![
+            (first, 0),
+            (first, 1),
+            (first, 2),
+            (second, 0),
+            (third, 0),
+            (third, 1),
+        ];
+        let history = |query| transactions(&storage, query);
+
+        // страница может закончиться на границе счетов и внутри счета
+        for limit in 1..=4 {
+            let query = HistoryQuery {
+                limit: Some(limit),
+                ..HistoryQuery::default()
+            };
+            assert_eq!(pages(history, query.clone()).0, all);
+            let desc_query = HistoryQuery {
+                order: Order::Desc,
+                ..query
+            };
+            let desc: Vec<(u32, u32)> = pages(history, desc_query).0;
+            assert_eq!(desc, all.iter().rev().copied().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn invalid_query_is_rejected() {
+        let storage: StorageState = bank();
+        let first: u32 = open(&storage, "10.00");
+        let second: u32 = open(&storage, "10.00");
+        let page: HistoryPage = account_history(
+            &storage,
+            first.into(),
+            HistoryQuery {
+                limit: Some(1),
+                ..HistoryQuery::default()
+            },
+        )
+        .unwrap();
+
+        // курсор другого счета, некорректный курсор, размер страницы вне 1..=1000
+        let queries = [
+            HistoryQuery {
+                cursor: page.next_cursor,
+                ..HistoryQuery::default()
+            },
+            HistoryQuery {
+                cursor: Some("1-0".to_string()),
+                ..HistoryQuery::default()
+            },
+            HistoryQuery {
+                limit: Some(0),
+                ..HistoryQuery::default()
+            },
+            HistoryQuery {
+                limit: Some(1001),
+                ..HistoryQuery::default()
+            },
+        ];
+        for query in queries {
+            assert!(matches!(
+                account_history(&storage, second.into(), query),
+                Err(InvalidHistoryQuery(_))
+            ));
+        }
+    }
+}
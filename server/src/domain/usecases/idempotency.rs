@@ -0,0 +1,227 @@
+use crate::adapter::storage::cache::Cache;
+use crate::domain::entities::idempotency::{IdempotencyRecord, IDEMPOTENCY_KEY_MAX_LEN};
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::{
+    IdempotencyKeyReused, InvalidIdempotencyKey, StorageFailure,
+};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Ключ идемпотентности операции с отпечатком запроса.
+pub(crate) struct Idempotency {
+    key: String,
+    request: String,
+}
+
+impl Idempotency {
+    /// Проверка ключа и отпечаток запроса. None - операция без ключа.
+    pub(crate) fn new<T: Serialize>(
+        key: Option<String>,
+        request: &T,
+    ) -> Result<Option<Self>, AppError> {
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        // ключ - непустая строка из печатных ASCII символов
+        if key.is_empty()
+            || key.len() > IDEMPOTENCY_KEY_MAX_LEN
+            || !key.chars().all(|ch| ch.is_ascii_graphic())
+        {
+            return Err(InvalidIdempotencyKey(IDEMPOTENCY_KEY_MAX_LEN));
+        }
+        let request: String =
+            serde_json::to_string(request).map_err(|err| StorageFailure(err.to_string()))?;
+
+        Ok(Some(Self { key, request }))
+    }
+
+    /// Ключ идемпотентности.
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Исходный ответ операции, выполненной с этим ключом после `expired`.
+    /// Ключ, использованный с другим запросом, отклоняется.
+    pub(crate) fn replayed<C: Cache + ?Sized, T: DeserializeOwned>(
+        &self,
+        db: &C,
+        expired: DateTime<Utc>,
+    ) -> Result<Option<T>, AppError> {
+        let Some(saved) = db.find_idempotency(&self.key) else {
+            return Ok(None);
+        };
+        // окно ключа истекло - операция выполняется заново
+        if saved.timestamp < expired {
+            return Ok(None);
+        }
+        if saved.request != self.request {
+            return Err(IdempotencyKeyReused(self.key.clone()));
+        }
+        serde_json::from_str(&saved.response)
+            .map(Some)
+            .map_err(|err| StorageFailure(err.to_string()))
+    }
+
+    /// Запись ответа операции для сохранения вместе с операцией.
+    pub(crate) fn record<T: Serialize>(
+        self,
+        response: &T,
+        now: DateTime<Utc>,
+    ) -> Result<IdempotencyRecord, AppError> {
+        let response: String =
+            serde_json::to_string(response).map_err(|err| StorageFailure(err.to_string()))?;
+
+        Ok(IdempotencyRecord::new(
+            self.key,
+            self.request,
+            response,
+            now,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adapter::storage::cache::{Cache, CacheImpl};
+    use crate::adapter::storage::sqlite::SqliteImpl;
+    use crate::adapter::storage::wal::{self, WalEntry};
+    use crate::adapter::storage::{Storage, StorageState, Storages};
+    use crate::domain::entities::transaction::Operation::Replenish;
+    use crate::domain::entities::transaction::{TransactionResponse, TransferResponse};
+    use crate::domain::errors::AppError;
+    use crate::domain::errors::AppError::IdempotencyKeyReused;
+    use crate::domain::usecases::account::{change_acc_balance, transfer};
+    use crate::domain::usecases::testing::{self, bank, money, open};
+    use chrono::TimeDelta;
+    use std::sync::Arc;
+
+    /// Пополнение счета с ключом идемпотентности.
+    fn replenish(
+        storage: &StorageState,
+        acc_id: u32,
+        amount: &str,
+        key: &str,
+    ) -> Result<TransactionResponse, AppError> {
+        change_acc_balance(
+            storage,
+            money(amount),
+            acc_id.into(),
+            Replenish,
+            None,
+            Some(key.to_string()),
+        )
+    }
+
+    /// Число операций журнала.
+    fn operations(storage: &StorageState) -> usize {
+        storage.db().unwrap().get_journal().len()
+    }
+
+    #[test]
+    fn retry_returns_original_response() {
+        let storage: StorageState = bank();
+        let acc_id: u32 = open(&storage, "0");
+
+        let first: TransactionResponse = replenish(&storage, acc_id, "10.00", "key-1").unwrap();
+        let retry: TransactionResponse = replenish(&storage, acc_id, "10.00", "key-1").unwrap();
+
+        assert_eq!(retry.operation_id, first.operation_id);
+        assert_eq!(retry.balance, money("10.00"));
+        assert_eq!(testing::balance(&storage, acc_id), money("10.00"));
+        assert_eq!(operations(&storage), 2);
+    }
+
+    #[test]
+    fn reused_key_with_other_request_is_rejected() {
+        let storage: StorageState = bank();
+        let from: u32 = open(&storage, "0");
+        let to: u32 = open(&storage, "0");
+        replenish(&storage, from, "10.00", "key-1").unwrap();
+
+        // другая сумма и другая операция с тем же ключом
+        assert!(matches!(
+            replenish(&storage, from, "20.00", "key-1"),
+            Err(IdempotencyKeyReused(_))
+        ));
+        assert!(matches!(
+            transfer(
+                &storage,
+                testing::transfer(from, to, "1.00"),
+                Some("key-1".to_string())
+            ),
+            Err(IdempotencyKeyReused(_))
+        ));
+        assert_eq!(testing::balance(&storage, from), money("10.00"));
+        assert_eq!(operations(&storage), 3);
+
+        // перевод со своим ключом повторяется без повторного списания
+        let req = || testing::transfer(from, to, "4.00");
+        let first: TransferResponse = transfer(&storage, req(), Some("key-2".to_string())).unwrap();
+        let retry: TransferResponse = transfer(&storage, req(), Some("key-2".to_string())).unwrap();
+        assert_eq!(retry.operation_id, first.operation_id);
+        assert_eq!(testing::balance(&storage, to), money("4.00"));
+    }
+
+    #[test]
+    fn expired_key_executes_again() {
+        let storage: StorageState =
+            Arc::new(Storage::default().with_idempotency_window(TimeDelta::seconds(60)));
+        let acc_id: u32 = open(&storage, "0");
+        let first: TransactionResponse = replenish(&storage, acc_id, "10.00", "key-1").unwrap();
+
+        storage.set_clock(Some(storage.now() + TimeDelta::seconds(61)));
+        let again: TransactionResponse = replenish(&storage, acc_id, "10.00", "key-1").unwrap();
+
+        assert_ne!(again.operation_id, first.operation_id);
+        assert_eq!(testing::balance(&storage, acc_id), money("20.00"));
+    }
+
+    #[test]
+    fn key_is_written_in_operation_wal_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage: StorageState =
+            Arc::new(Storage::new(Box::new(CacheImpl::with_backup(dir.path()))));
+        let acc_id: u32 = open(&storage, "0");
+        let first: TransactionResponse = replenish(&storage, acc_id, "10.00", "key-1").unwrap();
+
+        // одна запись WAL на операцию, ключ - в записи операции
+        let entries: Vec<WalEntry> = wal::read(&wal::segment_path(dir.path(), 0))
+            .unwrap()
+            .entries;
+        assert_eq!(entries.len(), operations(&storage));
+        assert!(matches!(
+            entries.last(),
+            Some(WalEntry::Commit { idempotency: Some(saved), .. }) if saved.key == "key-1"
+        ));
+
+        // после перезапуска повтор возвращает исходный ответ
+        let mut db: CacheImpl = CacheImpl::with_backup(dir.path());
+        db.backup_load().unwrap();
+        let restarted: StorageState = Arc::new(Storage::new(Box::new(db)));
+        let retry: TransactionResponse = replenish(&restarted, acc_id, "10.00", "key-1").unwrap();
+        assert_eq!(retry.operation_id, first.operation_id);
+        assert_eq!(testing::balance(&restarted, acc_id), money("10.00"));
+    }
+
+    #[test]
+    fn sqlite_keeps_key_with_operation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bank.db");
+        let storage: StorageState =
+            Arc::new(Storage::new(Box::new(SqliteImpl::open(&path).unwrap())));
+        let acc_id: u32 = open(&storage, "0");
+        let first: TransactionResponse = replenish(&storage, acc_id, "10.00", "key-1").unwrap();
+        drop(storage);
+
+        let reopened: StorageState =
+            Arc::new(Storage::new(Box::new(SqliteImpl::open(&path).unwrap())));
+        assert!(matches!(
+            replenish(&reopened, acc_id, "20.00", "key-1"),
+            Err(IdempotencyKeyReused(_))
+        ));
+        let retry: TransactionResponse = replenish(&reopened, acc_id, "10.00", "key-1").unwrap();
+        assert_eq!(retry.operation_id, first.operation_id);
+        assert_eq!(testing::balance(&reopened, acc_id), money("10.00"));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::adapter::storage::cache::Cache;
+use crate::adapter::storage::Storages;
+use crate::domain::entities::account::AccountKey;
+use crate::domain::entities::journal::Record;
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::OperationNotExists;
+use crate::domain::usecases::account::{ready, resolve};
+use std::sync::Arc;
+
+/// Получение всего журнала операций в порядке выполнения.
+pub fn journal<S: Storages>(storage: &Arc<S>) -> Result<Vec<Record>, AppError> {
+    ready(storage.as_ref())?;
+
+    Ok(storage.db()?.get_journal().to_vec())
+}
+
+/// Получение операции по id.
+pub fn operation<S: Storages>(storage: &Arc<S>, operation_id: u64) -> Result<Record, AppError> {
+    ready(storage.as_ref())?;
+    let db = storage.db()?;
+    // id операции совпадает с позицией в журнале + 1
+    usize::try_from(operation_id)
+        .ok()
+        .and_then(|id| id.checked_sub(1))
+        .and_then(|pos| db.get_journal().get(pos).cloned())
+        .ok_or_else(|| OperationNotExists(operation_id.to_string()))
+}
+
+/// Получение операций счета в порядке выполнения.
+pub fn account_journal<S: Storages>(
+    storage: &Arc<S>,
+    account: AccountKey,
+) -> Result<Vec<Record>, AppError> {
+    ready(storage.as_ref())?;
+    let db = storage.db()?;
+    // поиск счета
+    let account_id: u32 = resolve(&**db, &account)?;
+
+    Ok(db
+        .get_account_journal(account_id)
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::storage::StorageState;
+    use crate::domain::entities::journal::{Command, TransactionRef};
+    use crate::domain::entities::money::Money;
+    use crate::domain::entities::transaction::Operation::Withdraw;
+    use crate::domain::usecases::account;
+    use crate::domain::usecases::testing::{bank, money, open, transfer};
+
+    #[test]
+    fn operations_are_numbered_in_execution_order() {
+        let storage: StorageState = bank();
+        let alice: u32 = open(&storage, "50.00");
+        let bob: u32 = open(&storage, "0");
+        account::transfer(&storage, transfer(alice, bob, "20.00"), None).unwrap();
+        account::change_acc_balance(
+            &storage,
+            money("5.00"),
+            bob.into(),
+            Withdraw,
+            Some("cash".to_string()),
+            None,
+        )
+        .unwrap();
+        account::freeze(&storage, alice.into()).unwrap();
+
+        let records: Vec<Record> = journal(&storage).unwrap();
+        let ids: Vec<u64> = records.iter().map(|record| record.id).collect();
+        assert_eq!(ids, (1..=6).collect::<Vec<u64>>());
+        let commands: Vec<&Command> = records.iter().map(|record| &record.command).collect();
+        assert!(
+            matches!(commands[0], Command::NewAccount { account_id, .. } if *account_id == alice)
+        );
+        assert!(
+            matches!(commands[1], Command::Replenish { account_id, .. } if *account_id == alice)
+        );
+        assert!(
+            matches!(commands[2], Command::NewAccount { account_id, .. } if *account_id == bob)
+        );
+        assert!(matches!(
+            commands[3],
+            Command::Transfer { account_from, account_to, .. }
+                if *account_from == alice && *account_to == bob
+        ));
+        assert!(matches!(
+            commands[4],
+            Command::Withdraw { reference: Some(reference), .. } if reference == "cash"
+        ));
+        assert_eq!(commands[5], &Command::Freeze { account_id: alice });
+        // изменение статуса не создает транзакций
+        assert!(records[5].transactions.is_empty());
+        assert_eq!(records[4].transactions, vec![TransactionRef::new(bob, 2)]);
+    }
+
+    #[test]
+    fn operation_is_found_by_id() {
+        let storage: StorageState = bank();
+        let id: u32 = open(&storage, "1.00");
+
+        assert_eq!(
+            operation(&storage, 2).unwrap().command,
+            Command::Replenish {
+                account_id: id,
+                value: money("1.00"),
+                fee: Money::ZERO,
+                reference: None,
+            }
+        );
+        for missing in [0, 3, u64::MAX] {
+            assert!(matches!(
+                operation(&storage, missing),
+                Err(OperationNotExists(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn account_journal_contains_only_account_operations() {
+        let storage: StorageState = bank();
+        let alice: u32 = open(&storage, "10.00");
+        let bob: u32 = open(&storage, "10.00");
+        let carol: u32 = open(&storage, "10.00");
+        account::transfer(&storage, transfer(bob, carol, "1.00"), None).unwrap();
+        account::transfer(&storage, transfer(alice, bob, "1.00"), None).unwrap();
+
+        let ids = |account: u32| -> Vec<u64> {
+            account_journal(&storage, account.into())
+                .unwrap()
+                .iter()
+                .map(|record| record.id)
+                .collect()
+        };
+        assert_eq!(ids(alice), vec![1, 2, 8]);
+        assert_eq!(ids(bob), vec![3, 4, 7, 8]);
+        assert_eq!(ids(carol), vec![5, 6, 7]);
+        assert!(matches!(
+            account_journal(&storage, 42.into()),
+            Err(AppError::AccountNotExists(_))
+        ));
+    }
+}
@@ -1,3 +1,9 @@
 pub mod account;
+pub mod history;
+mod idempotency;
+pub mod journal;
+pub mod replay;
 pub mod storage;
+#[cfg(test)]
+mod testing;
 pub mod transaction;
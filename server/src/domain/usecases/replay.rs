@@ -0,0 +1,281 @@
+use crate::adapter::storage::cache::{Cache, CacheImpl};
+use crate::adapter::storage::{Storage, StorageState, Storages};
+use crate::domain::entities::account::Account;
+use crate::domain::entities::fee::FEE_ACCOUNT;
+use crate::domain::entities::journal::{Command, Record, ReplayResponse};
+use crate::domain::entities::transaction::Operation::{Replenish, Withdraw};
+use crate::domain::entities::transaction::TransferRequest;
+use crate::domain::errors::AppError;
+use crate::domain::errors::AppError::ReplayMismatch;
+use crate::domain::usecases::account::{self, ready};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Повторное выполнение операций журнала через usecases счета.
+/// Id каждой выполненной операции должен совпасть с id записи журнала.
+pub fn replay<S: Storages>(storage: &Arc<S>, journal: &[Record]) -> Result<(), AppError> {
+    for record in journal {
+        // операция выполняется со временем из журнала
+        storage.set_clock(Some(record.timestamp));
+        let op_id: u64 = match record.command.clone() {
+            Command::NewAccount { account_id, name } => {
+                let tx = match name.as_deref() {
+                    // имя счета комиссий зарезервировано
+                    Some(FEE_ACCOUNT) => account::new_fee_account(Arc::clone(storage))?,
+                    _ => account::new_account(Arc::clone(storage), name)?,
+                };
+                // id нового счета должен совпасть с исходным
+                if tx.account_id != account_id {
+                    return Err(ReplayMismatch(format!(
+                        "operation '{}' created account '{}' instead of '{}'",
+                        record.id, tx.account_id, account_id
+                    )));
+                }
+                tx.operation_id
+            }
+            Command::Replenish {
+                account_id,
+                value,
+                fee,
+                reference,
+            } => {
+                account::charge_balance(
+                    storage,
+                    value,
+                    account_id.into(),
+                    Replenish,
+                    reference,
+                    Some(fee),
+                    None,
+                )?
+                .operation_id
+            }
+            Command::Withdraw {
+                account_id,
+                value,
+                fee,
+                reference,
+            } => {
+                account::charge_balance(
+                    storage,
+                    value,
+                    account_id.into(),
+                    Withdraw,
+                    reference,
+                    Some(fee),
+                    None,
+                )?
+                .operation_id
+            }
+            Command::Transfer {
+                account_from,
+                account_to,
+                value,
+                fee,
+                reference,
+            } => {
+                let req = TransferRequest {
+                    account_from: account_from.into(),
+                    account_to: account_to.into(),
+                    transfer_value: value,
+                    reference,
+                };
+                account::charge_transfer(storage, req, Some(fee), None)?.operation_id
+            }
+            Command::Close { account_id } => {
+                account::close(storage, account_id.into())?.operation_id
+            }
+            Command::Freeze { account_id } => {
+                account::freeze(storage, account_id.into())?.operation_id
+            }
+            Command::Reopen { account_id } => {
+                account::reopen(storage, account_id.into())?.operation_id
+            }
+        };
+        // порядок операций должен сохраниться
+        if op_id != record.id {
+            return Err(ReplayMismatch(format!(
+                "operation '{}' replayed with id '{}'",
+                record.id, op_id
+            )));
+        }
+    }
+    storage.set_clock(None);
+
+    Ok(())
+}
+
+/// Восстановление бд в новом экземпляре банка по журналу операций и сверка с исходной бд.
+pub fn rebuild<S: Storages>(storage: &Arc<S>) -> Result<ReplayResponse, AppError> {
+    // копия исходной бд
+    let (accounts, journal) = {
+        ready(storage.as_ref())?;
+        let db = storage.db()?;
+        (db.get_accounts().clone(), db.get_journal().to_vec())
+    };
+    // новый экземпляр банка без репликации
+    let target: StorageState = Arc::new(Storage::new(Box::new(CacheImpl::ephemeral())));
+    // повторное выполнение всех операций
+    replay(&target, &journal)?;
+
+    let db = target.db()?;
+    // сверка счетов
+    verify(&accounts, db.get_accounts())?;
+    // сверка журнала
+    if db.get_journal() != journal.as_slice() {
+        return Err(ReplayMismatch("operations journal differs".to_string()));
+    }
+    // body
+    let transactions: usize = accounts.values().map(|acc| acc.transactions.len()).sum();
+
+    Ok(ReplayResponse::new(
+        journal.len(),
+        accounts.len(),
+        transactions,
+    ))
+}
+
+/// Сверка счетов: id, статусы, балансы и списки транзакций.
+fn verify(
+    original: &HashMap<u32, Account>,
+    rebuilt: &HashMap<u32, Account>,
+) -> Result<(), AppError> {
+    if original.len() != rebuilt.len() {
+        return Err(ReplayMismatch(format!(
+            "rebuilt '{}' accounts instead of '{}'",
+            rebuilt.len(),
+            original.len()
+        )));
+    }
+    for (acc_id, account) in original {
+        let Some(other) = rebuilt.get(acc_id) else {
+            return Err(ReplayMismatch(format!("account '{}' is missing", acc_id)));
+        };
+        if other.balance != account.balance {
+            return Err(ReplayMismatch(format!(
+                "account '{}' balance '{}' instead of '{}'",
+                acc_id, other.balance, account.balance
+            )));
+        }
+        if other != account {
+            return Err(ReplayMismatch(format!(
+                "account '{}' transactions differ",
+                acc_id
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::account::AccountKey;
+    use crate::domain::entities::fee::{FeeRule, FeeSchedule};
+    use crate::domain::entities::money::Money;
+    use crate::domain::usecases::testing::{balance, bank_with_fees, money, open, transfer};
+
+    /// Банк с комиссиями, именами, назначениями платежей и изменениями статусов.
+    fn busy_bank() -> StorageState {
+        let storage: StorageState = bank_with_fees(FeeSchedule {
+            withdraw: Some(FeeRule {
+                flat: money("0.50"),
+                percent_bp: 100,
+                ..Default::default()
+            }),
+            transfer: Some(FeeRule {
+                percent_bp: 50,
+                min: money("0.10"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let alice: u32 = account::new_account(Arc::clone(&storage), Some("alice".to_string()))
+            .unwrap()
+            .account_id;
+        account::change_acc_balance(
+            &storage,
+            money("100.00"),
+            AccountKey::Name("alice".to_string()),
+            Replenish,
+            Some("salary".to_string()),
+            Some("key-1".to_string()),
+        )
+        .unwrap();
+        let bob: u32 = open(&storage, "3.00");
+        account::transfer(&storage, transfer(alice, bob, "40.00"), None).unwrap();
+        account::change_acc_balance(&storage, money("10.00"), bob.into(), Withdraw, None, None)
+            .unwrap();
+        account::freeze(&storage, alice.into()).unwrap();
+        account::reopen(&storage, alice.into()).unwrap();
+        let empty: u32 = open(&storage, "0");
+        account::close(&storage, empty.into()).unwrap();
+        storage
+    }
+
+    #[test]
+    fn rebuild_reproduces_state() {
+        let storage: StorageState = busy_bank();
+
+        let report: ReplayResponse = rebuild(&storage).unwrap();
+
+        let db = storage.db().unwrap();
+        assert_eq!(report.operations, db.get_journal().len());
+        assert_eq!(report.accounts, db.get_accounts().len());
+        assert_eq!(
+            report.transactions,
+            db.get_accounts()
+                .values()
+                .map(|acc| acc.transactions.len())
+                .sum::<usize>()
+        );
+        drop(db);
+        // комиссии перевода (0.20) и снятия (0.60) зачислены на системный счет
+        let fees: AccountKey = AccountKey::Name(FEE_ACCOUNT.to_string());
+        assert_eq!(balance(&storage, fees), money("0.80"));
+    }
+
+    #[test]
+    fn replay_keeps_timestamps_and_operation_ids() {
+        let storage: StorageState = busy_bank();
+        let (accounts, journal) = {
+            let db = storage.db().unwrap();
+            (db.get_accounts().clone(), db.get_journal().to_vec())
+        };
+        let target: StorageState = Arc::new(Storage::default());
+
+        replay(&target, &journal).unwrap();
+
+        let db = target.db().unwrap();
+        assert_eq!(db.get_journal(), journal.as_slice());
+        assert_eq!(db.get_accounts(), &accounts);
+        // часы банка возвращены к текущему времени
+        assert!(target.now() > journal[journal.len() - 1].timestamp);
+    }
+
+    #[test]
+    fn rebuild_detects_mismatch() {
+        let storage: StorageState = busy_bank();
+        let (mut accounts, journal) = {
+            let db = storage.db().unwrap();
+            (db.get_accounts().clone(), db.get_journal().to_vec())
+        };
+        // баланс счета изменен в обход журнала
+        let account: &mut Account = accounts.values_mut().max_by_key(|acc| acc.id).unwrap();
+        account.balance = Money::from_minor(1);
+        let mut db: CacheImpl = CacheImpl::ephemeral();
+        db.restore(accounts, journal.clone());
+        let tampered: StorageState = Arc::new(Storage::new(Box::new(db)));
+        assert!(matches!(rebuild(&tampered), Err(ReplayMismatch(_))));
+
+        // закрытие счета выпало из журнала
+        let mut db: CacheImpl = CacheImpl::ephemeral();
+        let accounts: HashMap<u32, Account> = storage.db().unwrap().get_accounts().clone();
+        let mut truncated: Vec<Record> = journal;
+        truncated.pop();
+        db.restore(accounts, truncated);
+        let tampered: StorageState = Arc::new(Storage::new(Box::new(db)));
+        assert!(matches!(rebuild(&tampered), Err(ReplayMismatch(_))));
+    }
+}
@@ -1,15 +1,18 @@
 use crate::adapter::storage::cache::Cache;
 use crate::adapter::storage::Storages;
 use crate::domain::entities::account::Account;
+use crate::domain::entities::health::HealthResponse;
 use crate::domain::errors::AppError;
 use crate::domain::errors::AppError::EmptyDb;
+use crate::domain::usecases::account::ready;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 /// Получение всех счетов.
-pub fn history<S: Storages>(storage: &Arc<RwLock<S>>) -> Result<HashMap<u32, Account>, AppError> {
+pub fn history<S: Storages>(storage: &Arc<S>) -> Result<HashMap<u32, Account>, AppError> {
+    ready(storage.as_ref())?;
     // копия бд
-    let db: HashMap<u32, Account> = storage.write().unwrap().db().get_accounts().clone();
+    let db: HashMap<u32, Account> = storage.db()?.get_accounts().clone();
     // проверка на пустую бд
     if db.is_empty() {
         return Err(EmptyDb);
@@ -19,6 +22,14 @@ pub fn history<S: Storages>(storage: &Arc<RwLock<S>>) -> Result<HashMap<u32, Acc
 }
 
 /// Backup БД.
-pub fn backup<S: Storages>(storage: &Arc<RwLock<S>>) -> Result<(), AppError> {
-    storage.write().unwrap().db().backup_load()
+/// Бд заменяется целиком: ждет окончания операций по всем счетам.
+pub fn backup<S: Storages>(storage: &Arc<S>) -> Result<(), AppError> {
+    ready(storage.as_ref())?;
+    let _lock = storage.lock_all();
+    storage.db_mut()?.backup_load()
+}
+
+/// Состояние бд. Доступно и при восстановлении, и после закрытия бд.
+pub fn health<S: Storages>(storage: &Arc<S>) -> HealthResponse {
+    storage.health()
 }
@@ -0,0 +1,54 @@
+use crate::adapter::storage::{Storage, StorageState};
+use crate::domain::entities::account::AccountKey;
+use crate::domain::entities::fee::FeeSchedule;
+use crate::domain::entities::money::Money;
+use crate::domain::entities::transaction::Operation::Replenish;
+use crate::domain::entities::transaction::TransferRequest;
+use crate::domain::usecases::account;
+use std::sync::Arc;
+
+/// Новый in-memory банк без репликации.
+pub(crate) fn bank() -> StorageState {
+    Arc::new(Storage::default())
+}
+
+/// Новый in-memory банк с тарифами комиссий и открытым счетом комиссий.
+pub(crate) fn bank_with_fees(fees: FeeSchedule) -> StorageState {
+    let storage: StorageState = Arc::new(Storage::default().with_fees(fees));
+    account::open_fee_account(&storage).unwrap();
+    storage
+}
+
+/// Сумма из строки ("10.00").
+pub(crate) fn money(amount: &str) -> Money {
+    amount.parse().unwrap()
+}
+
+/// Новый счет, пополненный на `amount` (без пополнения, если сумма нулевая).
+pub(crate) fn open(storage: &StorageState, amount: &str) -> u32 {
+    let id: u32 = account::new_account(Arc::clone(storage), None)
+        .unwrap()
+        .account_id;
+    if !money(amount).is_zero() {
+        account::change_acc_balance(storage, money(amount), id.into(), Replenish, None, None)
+            .unwrap();
+    }
+    id
+}
+
+/// Текущий баланс счета.
+pub(crate) fn balance(storage: &StorageState, account: impl Into<AccountKey>) -> Money {
+    account::balance(storage, account.into(), None)
+        .unwrap()
+        .balance
+}
+
+/// Запрос перевода без назначения платежа.
+pub(crate) fn transfer(from: u32, to: u32, amount: &str) -> TransferRequest {
+    TransferRequest {
+        account_from: from.into(),
+        account_to: to.into(),
+        transfer_value: money(amount),
+        reference: None,
+    }
+}
@@ -1,25 +1,24 @@
 use crate::adapter::storage::cache::Cache;
 use crate::adapter::storage::Storages;
-use crate::domain::entities::account::Account;
+use crate::domain::entities::account::{Account, AccountKey};
 use crate::domain::entities::transaction::Transaction;
 use crate::domain::errors::AppError;
-use crate::domain::errors::AppError::{AccountNotExists, TransactionNotExists};
-use std::sync::{Arc, RwLock};
+use crate::domain::errors::AppError::TransactionNotExists;
+use crate::domain::usecases::account::{ready, resolve};
+use std::sync::Arc;
 
 /// Получение транзакции счета по id.
 pub fn transaction<S: Storages>(
-    storage: &Arc<RwLock<S>>,
-    account_id: u32,
+    storage: &Arc<S>,
+    account: AccountKey,
     transaction_id: u32,
 ) -> Result<Transaction, AppError> {
-    // проверка наличия счета
-    if !storage.write().unwrap().db().check_key(account_id) {
-        return Err(AccountNotExists(account_id.to_string()));
-    }
-
-    let mut binding = storage.write().unwrap();
+    ready(storage.as_ref())?;
+    let db = storage.db()?;
+    // поиск счета
+    let account_id: u32 = resolve(&**db, &account)?;
     // получение счета
-    let account: &Account = binding.db().get_account(account_id);
+    let account: &Account = db.get_account(account_id)?;
     // текущая транзакция
     let Some(tx) = account.transactions.get(transaction_id as usize) else {
         return Err(TransactionNotExists(
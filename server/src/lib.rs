@@ -0,0 +1,8 @@
+//! Банковский сервис: бд, usecases и роутеры http/gRPC.
+//! Библиотека используется бинарником сервиса и нагрузочными тестами (`cargo bench -p server`).
+
+pub mod adapter;
+pub mod domain;
+mod server;
+
+pub use crate::server::execute;
@@ -4,13 +4,17 @@
 // #![allow(unused_assignments)]
 // #![allow(unused_mut)]
 
-mod adapter;
-mod domain;
-mod server;
-
-use crate::server::execute;
+use server::execute;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    execute().await
+async fn main() -> ExitCode {
+    // ошибки запуска (конфигурация, бд, порты) выводятся текстом, без Debug-форматирования
+    match execute().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
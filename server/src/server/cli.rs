@@ -1,16 +1,81 @@
-use clap::Parser;
+use crate::server::config::{Backend, LogFormat, Protocol, Restore};
+use clap::{Parser, Subcommand};
 use std::net::IpAddr;
+use std::path::PathBuf;
 
 /// Cli-конфиг сервиса.
+/// Флаги переопределяют переменные окружения `BANK_*`, те - файл конфигурации (`--config`).
 #[derive(Parser, Debug)]
 pub struct Cli {
-    // app host
-    #[arg(long, default_value = "127.0.0.1")]
-    pub host: IpAddr,
-    // app port
-    #[arg(long, default_value = "8080")]
-    pub port: u16,
-    // app protocol
-    #[arg(long, default_value = "http")]
-    pub protocol: String,
+    // path to TOML config file
+    #[arg(long, env = "BANK_CONFIG")]
+    pub config: Option<PathBuf>,
+    // app host (default 127.0.0.1)
+    #[arg(long, env = "BANK_HOST")]
+    pub host: Option<IpAddr>,
+    // app port (default 8080)
+    #[arg(long, env = "BANK_PORT")]
+    pub port: Option<u16>,
+    // app protocol: http | grpc | both (default http)
+    #[arg(long, env = "BANK_PROTOCOL", value_enum)]
+    pub protocol: Option<Protocol>,
+    // gRPC port for protocol 'both' (default 50051, http is served on 'port')
+    #[arg(long, env = "BANK_GRPC_PORT")]
+    pub grpc_port: Option<u16>,
+    // request timeout in seconds (default 30)
+    #[arg(long, env = "BANK_REQUEST_TIMEOUT")]
+    pub request_timeout: Option<u64>,
+    // storage backend: memory | sqlite (default memory)
+    #[arg(long, env = "BANK_STORAGE", value_enum)]
+    pub storage: Option<Backend>,
+    // path to SQLite database file (default server/backup/bank.db)
+    #[arg(long, env = "BANK_DB_PATH")]
+    pub db_path: Option<PathBuf>,
+    // directory of backup.json for memory storage (default server/backup)
+    #[arg(long, env = "BANK_BACKUP_DIR")]
+    pub backup_dir: Option<PathBuf>,
+    // startup recovery: auto | never | required (default auto)
+    #[arg(long, env = "BANK_RESTORE", value_enum)]
+    pub restore: Option<Restore>,
+    // confirms '--restore never': start a new persistent replica, stored snapshots and log are rotated out
+    // (required with '--restore never' and backup enabled, default false)
+    #[arg(long, env = "BANK_DISCARD", num_args = 0..=1, default_missing_value = "true")]
+    pub discard: Option<bool>,
+    // number of account lock shards (default 64)
+    #[arg(long, env = "BANK_LOCK_SHARDS")]
+    pub lock_shards: Option<usize>,
+    // write operations to WAL and periodic backup.json snapshots (default true)
+    #[arg(long, env = "BANK_BACKUP")]
+    pub backup: Option<bool>,
+    // number of previous backup.json replicas to keep (default 3)
+    #[arg(long, env = "BANK_BACKUP_KEEP")]
+    pub backup_keep: Option<usize>,
+    // number of operations between backup.json snapshots (default 1000)
+    #[arg(long, env = "BANK_SNAPSHOT_EVERY")]
+    pub snapshot_every: Option<u64>,
+    // log format: compact | pretty | json (default compact)
+    #[arg(long, env = "BANK_LOG_FORMAT", value_enum)]
+    pub log_format: Option<LogFormat>,
+    // log filter in RUST_LOG format (default info)
+    #[arg(long, env = "BANK_LOG_LEVEL")]
+    pub log_level: Option<String>,
+    // idempotency keys window in seconds (default 86400)
+    #[arg(long, env = "BANK_IDEMPOTENCY_WINDOW")]
+    pub idempotency_window: Option<u64>,
+    // max request body (gRPC message) size in bytes (default 2 MiB)
+    #[arg(long, env = "BANK_MAX_BODY_BYTES")]
+    pub max_body_bytes: Option<usize>,
+    // path to json file with fee schedule (default - fees from config file or no fees)
+    #[arg(long, env = "BANK_FEES")]
+    pub fees: Option<PathBuf>,
+    // service command (default - start server)
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+/// Команды сервиса.
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Rebuild bank state by replaying the operations journal and verify it against the stored state
+    Replay,
 }
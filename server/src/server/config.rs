@@ -0,0 +1,555 @@
+use crate::adapter::storage::cache::{BACKUP_KEEP, PATH, SNAPSHOT_EVERY};
+use crate::adapter::storage::locks::LOCK_SHARDS;
+use crate::domain::entities::fee::FeeSchedule;
+use crate::domain::entities::idempotency::IDEMPOTENCY_WINDOW_SECS;
+use crate::server::cli::Cli;
+use chrono::TimeDelta;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
+
+/// Протокол сервиса.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Http,
+    Grpc,
+    // http и gRPC в одном процессе на разных портах
+    Both,
+}
+
+/// Реализация бд.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Memory,
+    Sqlite,
+}
+
+/// Восстановление бд при старте.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Restore {
+    // загрузка сохраненных данных, без них - пустая бд
+    Auto,
+    // новая реплика без загрузки: сохраненные данные уходят в ротацию
+    // (только с 'discard' - явное согласие; SQLite не поддерживается)
+    Never,
+    // загрузка сохраненных данных, без них - ошибка старта
+    Required,
+}
+
+/// Формат логов.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Compact,
+    Pretty,
+    Json,
+}
+
+/// Конфигурация сервиса.
+/// Источники по возрастанию приоритета: значения по умолчанию, TOML файл,
+/// переменные окружения `BANK_*`, флаги cli.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub backup: BackupConfig,
+    pub log: LogConfig,
+    pub limits: LimitsConfig,
+    // тарифы комиссий (по умолчанию - без комиссий)
+    pub fees: FeeSchedule,
+}
+
+/// Сетевые настройки.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+    // gRPC порт для протокола 'both' (http - на 'port')
+    pub grpc_port: u16,
+    pub protocol: Protocol,
+    // таймаут обработки запроса
+    pub request_timeout_secs: u64,
+}
+
+/// Настройки бд.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub backend: Backend,
+    // файл SQLite бд
+    pub db_path: PathBuf,
+    // директория backup.json (in-memory бд)
+    pub backup_dir: PathBuf,
+    // восстановление бд при старте
+    pub restore: Restore,
+    // подтверждение restore = 'never': сохраненные данные уходят в ротацию
+    pub discard: bool,
+    // число шардов блокировок счетов
+    pub lock_shards: usize,
+}
+
+/// Политика backup in-memory бд.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    // запись операций в WAL (wal.jsonl) и периодические снимки backup.json
+    pub enabled: bool,
+    // число хранимых предыдущих реплик backup.json.1..keep (0 - без ротации)
+    pub keep: usize,
+    // число операций между снимками backup.json
+    pub snapshot_every: u64,
+}
+
+/// Настройки логов.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    // фильтр в формате RUST_LOG, например "info" или "server=debug"
+    pub level: String,
+}
+
+/// Ограничения запросов.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    // окно хранения ключей идемпотентности
+    pub idempotency_window_secs: u64,
+    // максимальный размер тела запроса (http) или сообщения (gRPC)
+    pub max_body_bytes: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 8080,
+            grpc_port: 50051,
+            protocol: Protocol::Http,
+            request_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: Backend::Memory,
+            db_path: Path::new(PATH).join("bank.db"),
+            backup_dir: PathBuf::from(PATH),
+            restore: Restore::Auto,
+            discard: false,
+            lock_shards: LOCK_SHARDS,
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keep: BACKUP_KEEP,
+            snapshot_every: SNAPSHOT_EVERY,
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Compact,
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            idempotency_window_secs: IDEMPOTENCY_WINDOW_SECS,
+            max_body_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl Config {
+    /// Сборка конфигурации из TOML файла (`--config`/`BANK_CONFIG`), переменных окружения и флагов cli
+    /// с проверкой.
+    pub fn load(cli: &Cli) -> Result<Self, String> {
+        // файл конфигурации
+        let mut cfg: Config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None => Config::default(),
+        };
+        // переменные окружения и флаги cli (clap объединяет их с приоритетом флагов)
+        cfg.apply(cli)?;
+        cfg.validate()?;
+
+        Ok(cfg)
+    }
+
+    /// Чтение TOML файла.
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let payload: String = fs::read_to_string(path)
+            .map_err(|err| format!("config file '{}' read error: {}", path.display(), err))?;
+        toml::from_str(&payload)
+            .map_err(|err| format!("invalid config file '{}': {}", path.display(), err))
+    }
+
+    /// Переопределение значений из переменных окружения и флагов cli.
+    fn apply(&mut self, cli: &Cli) -> Result<(), String> {
+        if let Some(host) = cli.host {
+            self.server.host = host;
+        }
+        if let Some(port) = cli.port {
+            self.server.port = port;
+        }
+        if let Some(grpc_port) = cli.grpc_port {
+            self.server.grpc_port = grpc_port;
+        }
+        if let Some(protocol) = cli.protocol {
+            self.server.protocol = protocol;
+        }
+        if let Some(timeout) = cli.request_timeout {
+            self.server.request_timeout_secs = timeout;
+        }
+        if let Some(backend) = cli.storage {
+            self.storage.backend = backend;
+        }
+        if let Some(db_path) = &cli.db_path {
+            self.storage.db_path = db_path.clone();
+        }
+        if let Some(backup_dir) = &cli.backup_dir {
+            self.storage.backup_dir = backup_dir.clone();
+        }
+        if let Some(restore) = cli.restore {
+            self.storage.restore = restore;
+        }
+        if let Some(discard) = cli.discard {
+            self.storage.discard = discard;
+        }
+        if let Some(shards) = cli.lock_shards {
+            self.storage.lock_shards = shards;
+        }
+        if let Some(enabled) = cli.backup {
+            self.backup.enabled = enabled;
+        }
+        if let Some(keep) = cli.backup_keep {
+            self.backup.keep = keep;
+        }
+        if let Some(every) = cli.snapshot_every {
+            self.backup.snapshot_every = every;
+        }
+        if let Some(format) = cli.log_format {
+            self.log.format = format;
+        }
+        if let Some(level) = &cli.log_level {
+            self.log.level = level.clone();
+        }
+        if let Some(window) = cli.idempotency_window {
+            self.limits.idempotency_window_secs = window;
+        }
+        if let Some(max_body_bytes) = cli.max_body_bytes {
+            self.limits.max_body_bytes = max_body_bytes;
+        }
+        if let Some(path) = &cli.fees {
+            self.fees = load_fees(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Проверка конфигурации.
+    pub fn validate(&self) -> Result<(), String> {
+        let err = |message: String| Err(format!("invalid config: {}", message));
+        if self.server.protocol == Protocol::Both && self.server.port == self.server.grpc_port {
+            return err(format!(
+                "http and gRPC ports must differ: '{}'",
+                self.server.port
+            ));
+        }
+        if self.server.request_timeout_secs == 0 {
+            return err("server.request_timeout_secs must be positive".to_string());
+        }
+        if self.storage.backend == Backend::Sqlite && self.storage.db_path.as_os_str().is_empty() {
+            return err("storage.db_path is required for sqlite backend".to_string());
+        }
+        if self.storage.backend == Backend::Memory
+            && self.backup.enabled
+            && self.storage.backup_dir.as_os_str().is_empty()
+        {
+            return err("storage.backup_dir is required when backup is enabled".to_string());
+        }
+        if self.storage.restore == Restore::Required
+            && self.storage.backend == Backend::Memory
+            && !self.backup.enabled
+        {
+            return err("storage.restore = 'required' needs backup enabled".to_string());
+        }
+        if self.storage.restore == Restore::Never && self.storage.backend == Backend::Sqlite {
+            return err(
+                "storage.restore = 'never' is not supported for sqlite backend".to_string(),
+            );
+        }
+        if self.storage.restore == Restore::Never
+            && self.storage.backend == Backend::Memory
+            && self.backup.enabled
+            && !self.storage.discard
+        {
+            return err(
+                "storage.restore = 'never' discards stored data: confirm with storage.discard = true (--discard) \
+                 or disable backup for a storage without persistence"
+                    .to_string(),
+            );
+        }
+        if self.storage.discard
+            && (self.storage.restore != Restore::Never
+                || self.storage.backend != Backend::Memory
+                || !self.backup.enabled)
+        {
+            return err(
+                "storage.discard needs storage.restore = 'never' and memory backend with backup"
+                    .to_string(),
+            );
+        }
+        if self.storage.lock_shards == 0 {
+            return err("storage.lock_shards must be positive".to_string());
+        }
+        if self.backup.snapshot_every == 0 {
+            return err("backup.snapshot_every must be positive".to_string());
+        }
+        if let Err(parse) = EnvFilter::try_new(&self.log.level) {
+            return err(format!("log.level '{}': {}", self.log.level, parse));
+        }
+        if self.limits.idempotency_window_secs == 0 || self.idempotency_window().is_none() {
+            return err(format!(
+                "limits.idempotency_window_secs out of range: '{}'",
+                self.limits.idempotency_window_secs
+            ));
+        }
+        if self.limits.max_body_bytes == 0 {
+            return err("limits.max_body_bytes must be positive".to_string());
+        }
+        if let Err(fees) = self.fees.validate() {
+            return err(format!("fees: {}", fees));
+        }
+
+        Ok(())
+    }
+
+    /// Окно хранения ключей идемпотентности.
+    pub fn idempotency_window(&self) -> Option<TimeDelta> {
+        i64::try_from(self.limits.idempotency_window_secs)
+            .ok()
+            .and_then(TimeDelta::try_seconds)
+    }
+
+    /// Таймаут обработки запроса.
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.server.request_timeout_secs)
+    }
+}
+
+/// Загрузка тарифов комиссий из json файла.
+fn load_fees(path: &Path) -> Result<FeeSchedule, String> {
+    let payload: String = fs::read_to_string(path)
+        .map_err(|err| format!("fee schedule '{}' read error: {}", path.display(), err))?;
+    serde_json::from_str(&payload)
+        .map_err(|err| format!("invalid fee schedule '{}': {}", path.display(), err))
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http => write!(f, "http"),
+            Self::Grpc => write!(f, "grpc"),
+            Self::Both => write!(f, "both"),
+        }
+    }
+}
+
+impl fmt::Display for Restore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Never => write!(f, "never"),
+            Self::Required => write!(f, "required"),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Memory => write!(f, "memory"),
+            Self::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::env;
+
+    /// Конфигурация из аргументов cli (и переменных окружения `BANK_*`).
+    fn load(args: &[&str]) -> Result<Config, String> {
+        let cli: Cli =
+            Cli::try_parse_from([&["server"], args].concat()).map_err(|err| err.to_string())?;
+        Config::load(&cli)
+    }
+
+    /// TOML файл конфигурации во временной директории.
+    fn config_file(dir: &Path, payload: &str) -> String {
+        let path: PathBuf = dir.join("bank.toml");
+        fs::write(&path, payload).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let cfg: Config = load(&[]).unwrap();
+
+        assert_eq!(cfg.server.port, 8080);
+        assert_eq!(cfg.server.protocol, Protocol::Http);
+        assert_eq!(cfg.storage.backend, Backend::Memory);
+        assert_eq!(cfg.storage.restore, Restore::Auto);
+        assert_eq!(cfg.backup.snapshot_every, SNAPSHOT_EVERY);
+        assert_eq!(cfg.fees, FeeSchedule::default());
+    }
+
+    #[test]
+    fn sources_follow_precedence() {
+        // значения по умолчанию < TOML < BANK_* < cli
+        // (переменные окружения процесса общие для тестов: только в этом тесте и только эти ключи)
+        let dir = tempfile::tempdir().unwrap();
+        let path: String = config_file(
+            dir.path(),
+            r#"
+            [server]
+            port = 9000
+            grpc_port = 9001
+
+            [backup]
+            keep = 7
+
+            [log]
+            level = "debug"
+            "#,
+        );
+        env::set_var("BANK_GRPC_PORT", "9101");
+        env::set_var("BANK_BACKUP_KEEP", "8");
+        let cfg: Result<Config, String> = load(&["--config", &path, "--backup-keep", "9"]);
+        env::remove_var("BANK_GRPC_PORT");
+        env::remove_var("BANK_BACKUP_KEEP");
+        let cfg: Config = cfg.unwrap();
+
+        assert_eq!(cfg.server.request_timeout_secs, 30);
+        assert_eq!(cfg.server.port, 9000);
+        assert_eq!(cfg.log.level, "debug");
+        assert_eq!(cfg.server.grpc_port, 9101);
+        assert_eq!(cfg.backup.keep, 9);
+    }
+
+    #[test]
+    fn unknown_and_invalid_keys_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        for (payload, message) in [
+            ("[server]\nprot = 8081", "unknown field `prot`"),
+            ("[cache]\nsize = 1", "unknown field `cache`"),
+            ("[server]\nport = \"http\"", "invalid type"),
+            ("[server]\nprotocol = \"udp\"", "unknown variant `udp`"),
+            ("[fees.deposit]\nflat = \"1.00\"", "unknown field `deposit`"),
+        ] {
+            let path: String = config_file(dir.path(), payload);
+            let err: String = load(&["--config", &path]).unwrap_err();
+            assert!(err.contains("invalid config file"), "{}", err);
+            assert!(err.contains(message), "{}: {}", payload, err);
+        }
+        // отсутствующий файл, некорректные значения cli
+        assert!(load(&["--config", "/nonexistent/bank.toml"])
+            .unwrap_err()
+            .contains("read error"));
+        assert!(load(&["--protocol", "udp"]).is_err());
+        assert!(load(&["--port", "-1"]).is_err());
+    }
+
+    #[test]
+    fn invalid_fee_file_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path: PathBuf = dir.path().join("fees.json");
+        let path_str: String = path.to_string_lossy().into_owned();
+
+        fs::write(&path, r#"{"deposit": {"flat": "1.00"}}"#).unwrap();
+        assert!(load(&["--fees", &path_str])
+            .unwrap_err()
+            .contains("invalid fee schedule"));
+        fs::write(&path, r#"{"withdraw": {"min": "5.00", "max": "1.00"}}"#).unwrap();
+        assert!(load(&["--fees", &path_str])
+            .unwrap_err()
+            .contains("withdraw fee"));
+        fs::write(&path, r#"{"withdraw": {"flat": "0.50"}}"#).unwrap();
+        let cfg: Config = load(&["--fees", &path_str]).unwrap();
+        assert!(cfg.fees.withdraw.is_some());
+    }
+
+    #[test]
+    fn invalid_combinations_are_rejected() {
+        let cases: Vec<(&[&str], &str)> = vec![
+            (&["--snapshot-every", "0"], "backup.snapshot_every"),
+            (&["--lock-shards", "0"], "storage.lock_shards"),
+            (&["--request-timeout", "0"], "server.request_timeout_secs"),
+            (&["--max-body-bytes", "0"], "limits.max_body_bytes"),
+            (
+                &["--idempotency-window", "0"],
+                "limits.idempotency_window_secs",
+            ),
+            (&["--log-level", "server=loud"], "log.level"),
+            (
+                &[
+                    "--protocol",
+                    "both",
+                    "--port",
+                    "9000",
+                    "--grpc-port",
+                    "9000",
+                ],
+                "ports must differ",
+            ),
+            (&["--storage", "sqlite", "--restore", "never"], "sqlite"),
+            (
+                &["--restore", "required", "--backup", "false"],
+                "needs backup",
+            ),
+            (&["--discard"], "storage.discard"),
+            (&["--restore", "never"], "--discard"),
+            (&["--restore", "never", "--discard", "false"], "--discard"),
+        ];
+        for (args, message) in cases {
+            let err: String = load(args).unwrap_err();
+            assert!(err.starts_with("invalid config"), "{:?}: {}", args, err);
+            assert!(err.contains(message), "{:?}: {}", args, err);
+        }
+        // restore = 'never': новая реплика только с подтверждением, без backup - бд в памяти
+        assert!(load(&["--restore", "never", "--discard"]).is_ok());
+        assert!(load(&["--restore", "never", "--backup", "false"]).is_ok());
+        // пустые пути (cli не принимает пустые значения, TOML - принимает)
+        let mut cfg: Config = Config::default();
+        cfg.storage.backup_dir = PathBuf::new();
+        assert!(cfg.validate().unwrap_err().contains("storage.backup_dir"));
+        cfg.storage.backend = Backend::Sqlite;
+        cfg.storage.db_path = PathBuf::new();
+        assert!(cfg.validate().unwrap_err().contains("storage.db_path"));
+    }
+}
@@ -1,49 +1,166 @@
 mod cli;
+mod config;
+mod recovery;
 
 use crate::adapter::router::grpc::proto::bank_server::BankServer;
 use crate::adapter::router::grpc::BankService;
 use crate::adapter::router::http::router;
-use crate::adapter::storage::cache::PATH;
-use crate::adapter::storage::{Storage, StorageState};
-use crate::server::cli::Cli;
+use crate::adapter::storage::cache::CacheImpl;
+use crate::adapter::storage::sqlite::SqliteImpl;
+use crate::adapter::storage::{DynCache, Storage, StorageState};
+use crate::domain::entities::journal::ReplayResponse;
+use crate::domain::usecases;
+use crate::server::cli::{Cli, Commands};
+use crate::server::config::{Backend, Config, LogConfig, LogFormat, Protocol, Restore};
 use axum::Router;
 use clap::Parser;
 use std::fs;
-use std::sync::{Arc, RwLock};
+use std::future::Future;
+use std::sync::Arc;
 use tokio::signal;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use tonic::transport::Server;
 use tracing::info;
+use tracing_subscriber::EnvFilter;
 
 /// Основная функция. Инициализация и запуск сервиса.
 pub async fn execute() -> Result<(), Box<dyn std::error::Error>> {
     // cli-конфиг
-    let cfg: Cli = Cli::parse();
+    let cli: Cli = Cli::parse();
+    // конфигурация: файл, переменные окружения, флаги cli
+    let cfg: Config = Config::load(&cli)?;
     // включение трейсинга
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .compact()
-        .init();
+    init_tracing(&cfg.log)?;
+    // выбор реализации бд
+    let db: Box<DynCache> = match cfg.storage.backend {
+        Backend::Memory if cfg.backup.enabled => {
+            // создание папки для backup.json
+            fs::create_dir_all(&cfg.storage.backup_dir).map_err(|err| {
+                format!(
+                    "backup dir '{}' create error: {}",
+                    cfg.storage.backup_dir.display(),
+                    err
+                )
+            })?;
+            let mut cache: CacheImpl = CacheImpl::with_backup(&cfg.storage.backup_dir)
+                .with_rotation(cfg.backup.keep)
+                .with_snapshots(cfg.backup.snapshot_every);
+            // новая реплика (restore = 'never' подтвержден 'discard'): сохраненные данные уходят в ротацию
+            if cfg.storage.restore == Restore::Never && cli.command.is_none() {
+                cache.discard_backup()?;
+            }
+            Box::new(cache)
+        }
+        Backend::Memory => Box::new(CacheImpl::ephemeral()),
+        Backend::Sqlite => {
+            // создание папки для файла бд
+            if let Some(dir) = cfg.storage.db_path.parent() {
+                fs::create_dir_all(dir)
+                    .map_err(|err| format!("db dir '{}' create error: {}", dir.display(), err))?;
+            }
+            Box::new(SqliteImpl::open(&cfg.storage.db_path)?)
+        }
+    };
+    info!("Storage backend: {}", cfg.storage.backend);
+    info!("Fee schedule: {:?}", cfg.fees);
+    // окно ключей идемпотентности (проверено при загрузке конфигурации)
+    let window = cfg.idempotency_window().unwrap_or_default();
+    info!(
+        "Idempotency window: {}s",
+        cfg.limits.idempotency_window_secs
+    );
     // создание 'state' объекта
-    let shared_state: Arc<RwLock<Storage>> = StorageState::default();
-    // создание папки для backup.json
-    fs::create_dir_all(PATH).expect("error occurred while creating backup folder");
+    let shared_state: StorageState = Arc::new(
+        Storage::new(db)
+            .with_fees(cfg.fees.clone())
+            .with_idempotency_window(window)
+            .with_lock_shards(cfg.storage.lock_shards),
+    );
+    // восстановление бд по журналу и сверка, без запуска сервера
+    if let Some(Commands::Replay) = cli.command {
+        return replay(&shared_state);
+    }
+    // восстановление бд по режиму 'restore'
+    let recovery: Option<JoinHandle<Result<(), String>>> = match cfg.storage.backend {
+        // in-memory бд с репликацией: последний снимок и хвост WAL в фоне
+        Backend::Memory if cfg.backup.enabled && cfg.storage.restore != Restore::Never => {
+            Some(recovery::spawn(&shared_state, cfg.storage.restore)?)
+        }
+        backend => {
+            // SQLite загружена при открытии
+            if backend == Backend::Sqlite {
+                recovery::loaded(&shared_state, cfg.storage.restore)?;
+            }
+            // системный счет комиссий
+            let fee_account: u32 = usecases::account::open_fee_account(&shared_state)?;
+            info!("Fee account: {}", fee_account);
+            None
+        }
+    };
     // хост и порт
-    let address: String = format!("{}:{}", cfg.host, cfg.port);
+    let address: String = format!("{}:{}", cfg.server.host, cfg.server.port);
     // старт сервиса http/gRPC
-    match cfg.protocol.as_str() {
-        "grpc" => grpc_start(shared_state, address).await,
-        "http" => {
-            http_start(shared_state, address).await;
-            Ok(())
+    let serve = async {
+        match cfg.server.protocol {
+            Protocol::Grpc => grpc_start(shared_state, &cfg, address, shutdown_signal()).await,
+            Protocol::Http => http_start(shared_state, &cfg, address, shutdown_signal()).await,
+            Protocol::Both => {
+                // http и gRPC на разных портах (проверено при загрузке конфигурации)
+                let grpc_address: String = format!("{}:{}", cfg.server.host, cfg.server.grpc_port);
+                both_start(shared_state, &cfg, address, grpc_address).await
+            }
         }
-        _ => Ok(()),
+    };
+    // ошибка восстановления останавливает сервис
+    match recovery {
+        Some(recovery) => {
+            let recovered = async {
+                match recovery.await {
+                    Ok(result) => result.map_err(Into::into),
+                    Err(err) => Err(err.into()),
+                }
+            };
+            tokio::try_join!(serve, recovered).map(|_| ())
+        }
+        None => serve.await,
+    }
+}
+
+/// Включение трейсинга в формате из конфигурации.
+fn init_tracing(cfg: &LogConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let builder = tracing_subscriber::fmt()
+        .with_target(false)
+        .with_env_filter(EnvFilter::try_new(&cfg.level)?);
+    match cfg.format {
+        LogFormat::Compact => builder.compact().init(),
+        LogFormat::Pretty => builder.pretty().init(),
+        LogFormat::Json => builder.json().init(),
     }
+
+    Ok(())
+}
+
+/// Загрузка сохраненной бд, повторное выполнение журнала в новом экземпляре банка и сверка.
+fn replay(state: &StorageState) -> Result<(), Box<dyn std::error::Error>> {
+    // загрузка сохраненной бд
+    usecases::storage::backup(state)?;
+    // восстановление и сверка
+    let report: ReplayResponse = usecases::replay::rebuild(state)?;
+    info!(
+        "Replay succeeded: operations={}, accounts={}, transactions={}",
+        report.operations, report.accounts, report.transactions
+    );
+
+    Ok(())
 }
 
 /// Страт gRPC сервера.
 async fn grpc_start(
     state: StorageState,
+    cfg: &Config,
     address: String,
+    shutdown: impl Future<Output = ()>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // создание приложения
     let app = BankService { state };
@@ -53,28 +170,71 @@ async fn grpc_start(
     );
     // запуск сервиса
     Server::builder()
-        .add_service(BankServer::new(app))
-        .serve_with_shutdown(address.parse()?, shutdown_signal())
+        .timeout(cfg.request_timeout())
+        .add_service(BankServer::new(app).max_decoding_message_size(cfg.limits.max_body_bytes))
+        .serve_with_shutdown(address.parse()?, shutdown)
         .await?;
 
     Ok(())
 }
 
 /// Страт Http сервера.
-async fn http_start(state: StorageState, address: String) {
+async fn http_start(
+    state: StorageState,
+    cfg: &Config,
+    address: String,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), Box<dyn std::error::Error>> {
     // создание роутера и регистрация хендлеров и swagger
-    let router: Router = router(state).await;
+    let router: Router = router(state, cfg.request_timeout(), cfg.limits.max_body_bytes).await;
     // tcp-движок
-    let listener = tokio::net::TcpListener::bind(address).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .map_err(|err| format!("http bind '{}' error: {}", address, err))?;
     info!(
         "🚀 Http server started successfully. Listening on {}...",
-        listener.local_addr().unwrap()
+        listener.local_addr()?
     );
     // запуск сервиса с graceful shutdown
     axum::serve(listener, router)
-        .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    Ok(())
+}
+
+/// Старт http и gRPC серверов в одном процессе над общим 'state'.
+/// Сигнал завершения останавливает оба сервера.
+async fn both_start(
+    state: StorageState,
+    cfg: &Config,
+    http_address: String,
+    grpc_address: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // общий сигнал завершения
+    let (sender, receiver) = watch::channel(());
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = sender.send(());
+    });
+    // запуск обоих серверов, ошибка одного из них останавливает процесс
+    tokio::try_join!(
+        http_start(
+            state.clone(),
+            cfg,
+            http_address,
+            wait_shutdown(receiver.clone())
+        ),
+        grpc_start(state, cfg, grpc_address, wait_shutdown(receiver)),
+    )?;
+
+    Ok(())
+}
+
+/// Ожидание общего сигнала завершения.
+async fn wait_shutdown(mut receiver: watch::Receiver<()>) {
+    // ошибка - отправитель удален, что тоже означает завершение
+    let _ = receiver.changed().await;
 }
 
 /// Graceful shutdown.
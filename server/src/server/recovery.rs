@@ -0,0 +1,95 @@
+use crate::adapter::storage::cache::CacheImpl;
+use crate::adapter::storage::{DynCache, StorageState, Storages};
+use crate::domain::entities::health::RecoveryReport;
+use crate::domain::errors::AppError;
+use crate::domain::usecases;
+use crate::server::config::Restore;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::{self, JoinHandle};
+use tracing::info;
+
+/// Подсчет загруженных данных бд.
+fn recovery_report(db: &DynCache, elapsed: Option<Duration>) -> RecoveryReport {
+    RecoveryReport {
+        accounts: db.get_accounts().len(),
+        transactions: db
+            .get_accounts()
+            .values()
+            .map(|acc| acc.transactions.len())
+            .sum(),
+        operations: db.get_journal().len(),
+        elapsed_ms: elapsed.map(|elapsed| elapsed.as_millis() as u64),
+    }
+}
+
+/// Фоновое восстановление бд из снимка и WAL, пока сервер уже принимает запросы.
+/// До окончания бд пуста, операции записи отклоняются (`RECOVERING`).
+/// После загрузки открывается системный счет комиссий.
+pub fn spawn(
+    state: &StorageState,
+    restore: Restore,
+) -> Result<JoinHandle<Result<(), String>>, AppError> {
+    // бд на время загрузки заменяется пустой
+    let mut db: Box<DynCache> = {
+        state.set_recovering(true);
+        mem::replace(&mut *state.db_mut()?, Box::new(CacheImpl::ephemeral()))
+    };
+    let state: StorageState = Arc::clone(state);
+
+    Ok(task::spawn_blocking(move || {
+        info!("Storage recovery started: restore={}", restore);
+        let started: Instant = Instant::now();
+        // загрузка без блокировки 'state': чтения не ждут окончания
+        match db.backup_load() {
+            Ok(()) => {}
+            Err(AppError::BackupLoadFile) if restore == Restore::Auto => {
+                info!("No stored data, starting with empty storage");
+            }
+            Err(AppError::BackupLoadFile) => {
+                return Err("restore required, but no stored data found".to_string());
+            }
+            // бд остается закрытой на запись до остановки сервиса
+            Err(err) => return Err(format!("storage recovery failed: {}", err)),
+        }
+        let report: RecoveryReport = recovery_report(db.as_ref(), Some(started.elapsed()));
+        // возврат бд и открытие записи
+        *state.db_mut().map_err(|err| err.to_string())? = db;
+        state.set_recovering(false);
+        info!(
+            "Storage recovered: accounts={}, transactions={}, operations={}, elapsed={}ms",
+            report.accounts,
+            report.transactions,
+            report.operations,
+            report.elapsed_ms.unwrap_or_default()
+        );
+        state.set_recovery(report);
+        // системный счет комиссий
+        let fee_account: u32 =
+            usecases::account::open_fee_account(&state).map_err(|err| err.to_string())?;
+        info!("Fee account: {}", fee_account);
+
+        Ok(())
+    }))
+}
+
+/// Проверка данных, загруженных при открытии бд (SQLite).
+/// SQLite всегда загружается из файла: `never` отклоняется (и при проверке конфигурации).
+pub fn loaded(state: &StorageState, restore: Restore) -> Result<(), String> {
+    if restore == Restore::Never {
+        return Err("restore=never is not supported for sqlite backend".to_string());
+    }
+    let report: RecoveryReport =
+        recovery_report(state.db().map_err(|err| err.to_string())?.as_ref(), None);
+    if restore == Restore::Required && report.operations == 0 && report.accounts == 0 {
+        return Err("restore required, but no stored data found".to_string());
+    }
+    info!(
+        "Storage loaded: accounts={}, transactions={}, operations={}",
+        report.accounts, report.transactions, report.operations
+    );
+    state.set_recovery(report);
+
+    Ok(())
+}